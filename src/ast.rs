@@ -4,8 +4,36 @@
 //
 // Author: Alexander Seifarth
 
+use std::path::Path;
+use std::sync::Arc;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Location of an AST node in its source file.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Span {
+    /// File the node was parsed from, `None` if parsed from a string. Not serialized, the file
+    /// is given once per module.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub file: Option<Arc<Path>>,
+
+    /// Byte offset of the first character of the node
+    pub offset: usize,
+
+    /// Length of the node in bytes (trailing whitespace excluded)
+    pub len: usize,
+
+    /// Line number starting at 1
+    pub line: u32,
+
+    /// Column (in characters) starting at 1
+    pub column: usize,
+}
+
 /// A module corresponds to a single FIDL file
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Module {
     /// Package the module defines.
     pub package: String,
@@ -17,25 +45,149 @@ pub struct Module {
     pub interfaces: Vec<Interface>,
 
     /// Defined type collections within the module
-    pub type_collections: Vec<TypeCollection>
+    pub type_collections: Vec<TypeCollection>,
+
+    /// Location of the definition in the source
+    pub span: Span,
+}
+
+/// FAnnotationBlock: '<**' (elements+=FAnnotation)+ '**>'
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AnnotationBlock {
+    /// Unprocessed text between `<**` and `**>`
+    pub raw: String,
+
+    /// Annotations of the block in order of appearance
+    pub entries: Vec<Annotation>,
+}
+
+impl AnnotationBlock {
+    /// Returns the unprocessed text of the annotation block.
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    /// Returns the text of the first annotation of the given kind.
+    pub fn first(&self, kind: &AnnotationKind) -> Option<&str> {
+        self.entries.iter().find(|a| &a.kind == kind).map(|a| a.text.as_str())
+    }
+}
+
+/// Splits the raw text of an annotation block into its annotations.
+impl From<&str> for AnnotationBlock {
+    fn from(raw: &str) -> Self {
+        use crate::parser::{parse_annotation_tag, parse_annotation_text};
+        let mut entries = Vec::new();
+        let (mut rest, leading) = parse_annotation_text(raw);
+        if !leading.is_empty() {
+            entries.push(Annotation{ kind: AnnotationKind::Description, text: leading });
+        }
+        while let Ok((r, tag)) = parse_annotation_tag(rest) {
+            let (r, text) = parse_annotation_text(r);
+            entries.push(Annotation{ kind: AnnotationKind::from_tag(tag), text });
+            rest = r;
+        }
+        AnnotationBlock{ raw: raw.to_string(), entries }
+    }
+}
+
+/// FAnnotation: rawText=ANNOTATION_STRING, e.g. `@author: John Doe`
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Annotation {
+    /// Serialized next to `text`, e.g. `{ "kind": "Author", "text": ... }`
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub kind: AnnotationKind,
+
+    /// Text of the annotation with escape sequences resolved and surrounding whitespace removed
+    pub text: String,
+}
+
+/// Annotation tags predefined by FRANCA, other tags are kept as `Custom`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
+pub enum AnnotationKind {
+    /// `@description`, also used for text preceding the first tag
+    Description,
+    Author,
+    Experimental,
+    Deprecated,
+    See,
+    Param,
+    HighVolume,
+    HighFrequency,
+    SourceUri,
+    SourceAlias,
+    Details,
+    Requirement,
+
+    /// Any other tag without the leading `@`
+    Custom(String),
+}
+
+impl AnnotationKind {
+    /// Maps an annotation tag (without `@` and `:`) to its kind.
+    pub fn from_tag(tag: &str) -> AnnotationKind {
+        match tag {
+            "description" => AnnotationKind::Description,
+            "author" => AnnotationKind::Author,
+            "experimental" => AnnotationKind::Experimental,
+            "deprecated" => AnnotationKind::Deprecated,
+            "see" => AnnotationKind::See,
+            "param" => AnnotationKind::Param,
+            "high-volume" => AnnotationKind::HighVolume,
+            "high-frequency" => AnnotationKind::HighFrequency,
+            "source-uri" => AnnotationKind::SourceUri,
+            "source-alias" => AnnotationKind::SourceAlias,
+            "details" => AnnotationKind::Details,
+            "requirement" => AnnotationKind::Requirement,
+            _ => AnnotationKind::Custom(tag.to_string()),
+        }
+    }
+
+    /// Returns the annotation tag (without `@` and `:`) of this kind.
+    pub fn tag(&self) -> &str {
+        match self {
+            AnnotationKind::Description => "description",
+            AnnotationKind::Author => "author",
+            AnnotationKind::Experimental => "experimental",
+            AnnotationKind::Deprecated => "deprecated",
+            AnnotationKind::See => "see",
+            AnnotationKind::Param => "param",
+            AnnotationKind::HighVolume => "high-volume",
+            AnnotationKind::HighFrequency => "high-frequency",
+            AnnotationKind::SourceUri => "source-uri",
+            AnnotationKind::SourceAlias => "source-alias",
+            AnnotationKind::Details => "details",
+            AnnotationKind::Requirement => "requirement",
+            AnnotationKind::Custom(tag) => tag,
+        }
+    }
 }
 
 /// FRANCA import used by a module
 /// An import can either import a full module (e.g. another FIDL file) or a namespace from a module
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Import {
     /// Imported namespace, maybe empty if a full module is imported
     pub namespace: String,
 
     /// URI (e.g. FIDL file name) of an imported module
     pub uri: String,
+
+    /// Location of the definition in the source
+    pub span: Span,
 }
 
 /// FRANCA interface specification
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Interface {
     /// Optional annotation associated with the interface
-    pub annotation: Option<String>,
+    pub annotation: Option<AnnotationBlock>,
 
     /// name of the interface - must be unique within a package/module
     pub name: String,
@@ -55,16 +207,27 @@ pub struct Interface {
     /// methods defined within this interface
     pub methods: Vec<Method>,
 
+    /// constants defined within this interface
+    pub constants: Vec<Constant>,
+
+    /// Optional contract (protocol state machine) of the interface
+    pub contract: Option<Contract>,
+
     /// Optional base interface identifier
     pub extends: Option<String>,
 
     /// Optional list of managed interface identifiers
     pub manages: Option<Vec<String>>,
+
+    /// Location of the definition in the source
+    pub span: Span,
 }
 
 /// Type reference that may reference a custom (derived) type by its name or FQN
 /// or one of the built-in types.
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
 pub enum TypeRef {
     /// Custom type identified by its name or fully qualified name.
     Derived(String),
@@ -83,84 +246,150 @@ pub enum TypeRef {
     Double,
     ByteBuffer,
 
-    /// Min-Max value range
-    IntegerInterval(Option<isize>, Option<isize>)
+    /// Min-Max value range `Integer(lower, upper)`, `None` stands for `minInt` / `maxInt`.
+    /// The bounds are BigIntegers in FRANCA, i128 covers the full range of all integer types.
+    IntegerInterval(
+        #[cfg_attr(feature = "serde", serde(with = "interval_bound"))] Option<i128>,
+        #[cfg_attr(feature = "serde", serde(with = "interval_bound"))] Option<i128>)
+}
+
+/// Serializes bounds of integer intervals as JSON numbers, bounds beyond the 64 bit range as
+/// strings of decimal digits: JSON readers and serde's buffering of tagged enums have no 128 bit
+/// numbers.
+#[cfg(feature = "serde")]
+mod interval_bound {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Bound {
+        Signed(i64),
+        Unsigned(u64),
+        Big(String),
+    }
+
+    pub fn serialize<S: Serializer>(bound: &Option<i128>, serializer: S) -> Result<S::Ok, S::Error> {
+        match bound {
+            None => serializer.serialize_none(),
+            Some(b) => match (i64::try_from(*b), u64::try_from(*b)) {
+                (Ok(signed), _) => serializer.serialize_some(&signed),
+                (_, Ok(unsigned)) => serializer.serialize_some(&unsigned),
+                _ => serializer.serialize_some(&b.to_string()),
+            },
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i128>, D::Error> {
+        match Option::<Bound>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(Bound::Signed(b)) => Ok(Some(b.into())),
+            Some(Bound::Unsigned(b)) => Ok(Some(b.into())),
+            Some(Bound::Big(b)) => b.parse().map(Some).map_err(serde::de::Error::custom),
+        }
+    }
 }
 
 /// FRANCA attribute specification within an interface
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Attribute {
-    pub annotation: Option<String>,
+    pub annotation: Option<AnnotationBlock>,
     pub name: String,
     pub array: bool,
     pub read_only: bool,
     pub no_subscription: bool,
     pub no_read: bool,
     pub type_ref: TypeRef,
+
+    /// Location of the definition in the source
+    pub span: Span,
 }
 
 /// {FBroadcast} (comment=FAnnotationBlock)?
 /// 'broadcast' name=ID (':' selector=ID)? (selective?='selective')? '{' ('out' '{' (outArgs+=FArgument)* '}' )? '}';
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Broadcast {
-    pub annotation: Option<String>,
+    pub annotation: Option<AnnotationBlock>,
     pub name: String,
     pub selector: Option<String>,
     pub selective: bool,
     pub out_args: Vec<Argument>,
+
+    /// Location of the definition in the source
+    pub span: Span,
 }
 
-///FField: 	(comment=FAnnotationBlock)?  type=FTypeRef (array?='[' ']')? name=ID;
+///FField: (comment=FAnnotationBlock)?  type=FTypeRef (array?='[' ']')? name=ID;
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Field {
-    pub annotation: Option<String>,
+    pub annotation: Option<AnnotationBlock>,
     pub name: String,
     pub type_ref: TypeRef,
     pub array: bool,
+
+    /// Location of the definition in the source
+    pub span: Span,
 }
 
 /// FArgument: (comment=FAnnotationBlock)? type=FTypeRef (array?='[' ']')? name=ID;
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Argument {
-    pub annotation: Option<String>,
+    pub annotation: Option<AnnotationBlock>,
     pub type_ref: TypeRef,
     pub array: bool,
     pub name: String,
+
+    /// Location of the definition in the source
+    pub span: Span,
 }
 
-///
+/// FMethod: (comment=FAnnotationBlock)? 'method' name=ID (':' selector=ID)? (fireAndForget?='fireAndForget')?
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Method {
-    pub annotation: Option<String>,
+    pub annotation: Option<AnnotationBlock>,
     pub name: String,
     pub selector: Option<String>,
     pub fire_and_forget: bool,
     pub in_args: Vec<Argument>,
     pub out_args: Vec<Argument>,
     pub error: Option<MethodErrorSpec>,
+
+    /// Location of the definition in the source
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind"))]
 pub enum MethodErrorSpec {
-    Reference{annotation: Option<String>, fqn: String},
-    EnumerationBody{annotation: Option<String>, extends: Option<TypeRef>, enumerators: Vec<Enumerator>}
+    Reference{annotation: Option<AnnotationBlock>, fqn: String},
+    EnumerationBody{annotation: Option<AnnotationBlock>, extends: Option<TypeRef>, enumerators: Vec<Enumerator>}
 }
 
 /// FEnumerator returns FEnumerator:
-/// 	(comment=FAnnotationBlock)?
-/// 	name=ID ('=' value=AdditiveExpression)?
+///     (comment=FAnnotationBlock)?
+///     name=ID ('=' value=AdditiveExpression)?
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Enumerator {
-    pub annotation: Option<String>,
+    pub annotation: Option<AnnotationBlock>,
     pub name: String,
     pub val: Option<u64>,
+
+    /// Location of the definition in the source
+    pub span: Span,
 }
 
 /// FRANCA type collection specification
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TypeCollection {
     /// Optional annotation associated with the interface
-    pub annotation: Option<String>,
+    pub annotation: Option<AnnotationBlock>,
 
     /// name of the interface - must be unique within a package/module
     pub name: Option<String>,
@@ -170,14 +399,345 @@ pub struct TypeCollection {
 
     /// Types defined within this type collection
     pub types: Vec<Type>,
+
+    /// Constants defined within this type collection
+    pub constants: Vec<Constant>,
+
+    /// Location of the definition in the source
+    pub span: Span,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind"))]
 pub enum Type {
-    TypeDef{ annotation: Option<String>, public: bool, name: String, array: bool, actual_type: TypeRef },
-    Array{ annotation: Option<String>, public: bool, name: String, element_type: TypeRef },
-    Struct{ annotation: Option<String>, public: bool, name: String, polymorphic: bool, extends: Option<String>, fields: Vec<Field>},
-    Union{ annotation: Option<String>, public: bool, name: String, base_type: Option<String>, fields: Vec<Field>},
-    Map{ annotation: Option<String>, public: bool, name: String, key_type: TypeRef, value_type: TypeRef},
-    Enumeration{ annotation: Option<String>, name: String, public: bool, base_type: Option<TypeRef>, enumerators: Vec<Enumerator>}
+    TypeDef{ annotation: Option<AnnotationBlock>, public: bool, name: String, array: bool, actual_type: TypeRef, span: Span },
+    Array{ annotation: Option<AnnotationBlock>, public: bool, name: String, element_type: TypeRef, span: Span },
+    Struct{ annotation: Option<AnnotationBlock>, public: bool, name: String, polymorphic: bool, extends: Option<String>, fields: Vec<Field>, span: Span },
+    Union{ annotation: Option<AnnotationBlock>, public: bool, name: String, base_type: Option<String>, fields: Vec<Field>, span: Span },
+    Map{ annotation: Option<AnnotationBlock>, public: bool, name: String, key_type: TypeRef, value_type: TypeRef, span: Span },
+    Enumeration{ annotation: Option<AnnotationBlock>, name: String, public: bool, base_type: Option<TypeRef>, enumerators: Vec<Enumerator>, span: Span }
+}
+
+impl Type {
+    /// Name of the defined type.
+    pub fn name(&self) -> &str {
+        match self {
+            Type::TypeDef{ name, .. } | Type::Array{ name, .. } | Type::Struct{ name, .. } |
+            Type::Union{ name, .. } | Type::Map{ name, .. } | Type::Enumeration{ name, .. } => name,
+        }
+    }
+
+    /// Location of the type definition in the source.
+    pub fn span(&self) -> &Span {
+        match self {
+            Type::TypeDef{ span, .. } | Type::Array{ span, .. } | Type::Struct{ span, .. } |
+            Type::Union{ span, .. } | Type::Map{ span, .. } | Type::Enumeration{ span, .. } => span,
+        }
+    }
+}
+
+/// FConstantDef: (comment=FAnnotationBlock)?
+///     'const' type=FTypeRef (array?='[' ']')? name=ID '=' rhs=FInitializerExpression
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Constant {
+    pub annotation: Option<AnnotationBlock>,
+    pub type_ref: TypeRef,
+    pub array: bool,
+    pub name: String,
+    pub value: InitializerExpression,
+
+    /// Location of the definition in the source
+    pub span: Span,
+}
+
+/// FInitializerExpression: LogicalOrExpression | FCompoundInitializer | FBracketInitializer
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
+pub enum InitializerExpression {
+    /// plain expression, e.g. `MAX_SPEED * 2`
+    Expression(Expression),
+
+    /// struct and union initializer, e.g. `{ a: 1, b: "x" }`
+    Compound(Vec<FieldInitializer>),
+
+    /// array and map initializer, e.g. `[ 1, 2 ]` or `[ 1 => "one", 2 => "two" ]`
+    Bracket(Vec<ElementInitializer>),
+}
+
+/// FFieldInitializer: element=[FField|ID] ':' value=FInitializerExpression
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FieldInitializer {
+    pub element: String,
+    pub value: InitializerExpression,
+}
+
+/// FElementInitializer: first=FInitializerExpression ('=>' second=FInitializerExpression)?
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ElementInitializer {
+    pub first: InitializerExpression,
+    pub second: Option<InitializerExpression>,
+}
+
+/// FRANCA expression as used by constant initializers, enumerator values and contracts.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
+pub enum Expression {
+    /// `left op right` for the logical, equality, relational, additive and multiplicative operators
+    Binary{ left: Box<Expression>, op: Operator, right: Box<Expression> },
+
+    /// `op operand` for negation (`!`) and subtraction (`-`)
+    Unary{ op: Operator, operand: Box<Expression> },
+
+    /// FQualifiedElementRef: element=FQN ('->' field=ID)*
+    ElementRef{ element: String, fields: Vec<String> },
+
+    /// 'errorval'
+    CurrentError,
+
+    /// 'errordef' '.' enumerator=ID
+    ErrorEnumRef(String),
+
+    /// constant value
+    Literal(Literal),
+}
+
+/// FConstant: FFloatConstant | FDoubleConstant | FIntegerConstant | FBooleanConstant | FStringConstant
+/// Float and double values are kept in their textual form (without the `f`/`d` suffix).
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
+pub enum Literal {
+    Integer(u64),
+    Float(String),
+    Double(String),
+    Boolean(bool),
+    String(String),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind"))]
+pub enum Operator {
+    Or,
+    And,
+    Equal,
+    Unequal,
+    Smaller,
+    SmallerOrEqual,
+    GreaterOrEqual,
+    Greater,
+    Addition,
+    Subtraction,
+    Multiplication,
+    Division,
+    Negation,
+}
+
+/// FContract: 'contract' '{' ('vars' '{' (variables+=FDeclaration (';')?)* '}')?
+///     'PSM' stateGraph=FStateGraph '}'
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Contract {
+    pub variables: Vec<Declaration>,
+    pub state_graph: StateGraph,
+}
+
+/// FDeclaration: type=FTypeRef (array?='[' ']')? name=ID ('=' rhs=FInitializerExpression)?
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Declaration {
+    pub type_ref: TypeRef,
+    pub array: bool,
+    pub name: String,
+    pub value: Option<InitializerExpression>,
+}
+
+/// FStateGraph: '{' 'initial' initial=[FState|ID] states+=FState* '}'
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StateGraph {
+    /// name of the initial state
+    pub initial: String,
+    pub states: Vec<State>,
+}
+
+/// FState: (comment=FAnnotationBlock)? 'state' name=ID '{' transitions+=FTransition* '}'
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct State {
+    pub annotation: Option<AnnotationBlock>,
+    pub name: String,
+    pub transitions: Vec<Transition>,
+}
+
+/// FTransition: 'on' trigger=FTrigger (guard=FGuard)? '->' to=[FState|ID] (action=FBlock)?
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Transition {
+    pub trigger: Trigger,
+
+    /// condition of the guard '[' condition ']'
+    pub guard: Option<Expression>,
+
+    /// name of the target state
+    pub to: String,
+
+    /// statements of the optional action block
+    pub action: Option<Vec<Statement>>,
+}
+
+/// FEventOnIf: events of an interface that trigger a state transition.
+/// Methods and broadcasts may be referenced with their selector (ID_WITH_SELECTOR).
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind"))]
+pub enum Trigger {
+    Call{ method: String, selector: Option<String> },
+    Respond{ method: String, selector: Option<String> },
+    Error{ method: String, selector: Option<String> },
+    Signal{ broadcast: String, selector: Option<String> },
+    Set{ attribute: String },
+    Update{ attribute: String },
+}
+
+/// FStatement: FAssignment | FIfStatement | FBlock
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
+pub enum Statement {
+    /// lhs=FQualifiedElementRef '=' rhs=LogicalOrExpression
+    Assignment{ lhs: Expression, rhs: Expression },
+
+    /// 'if' '(' condition ')' then=FStatement ('else' else=FStatement)?
+    If{ condition: Expression, then: Box<Statement>, otherwise: Option<Box<Statement>> },
+
+    /// '{' statements+=FStatement* '}'
+    Block(Vec<Statement>),
+}
+
+/// Nodes whose spans can be reset to `Span::default()`, together with the spans of all nodes they
+/// contain. Used to compare nodes by their content, e.g. nodes parsed from differently formatted
+/// text.
+pub trait ClearSpans {
+    fn clear_spans(&mut self);
+
+    /// The node with all spans reset.
+    fn without_spans(mut self) -> Self where Self: Sized {
+        self.clear_spans();
+        self
+    }
+}
+
+impl<T: ClearSpans> ClearSpans for Vec<T> {
+    fn clear_spans(&mut self) {
+        self.iter_mut().for_each(ClearSpans::clear_spans);
+    }
+}
+
+impl<T: ClearSpans> ClearSpans for Option<T> {
+    fn clear_spans(&mut self) {
+        self.iter_mut().for_each(ClearSpans::clear_spans);
+    }
+}
+
+impl ClearSpans for Module {
+    fn clear_spans(&mut self) {
+        self.imports.clear_spans();
+        self.interfaces.clear_spans();
+        self.type_collections.clear_spans();
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for Import {
+    fn clear_spans(&mut self) {
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for Interface {
+    fn clear_spans(&mut self) {
+        self.attributes.clear_spans();
+        self.types.clear_spans();
+        self.broadcasts.clear_spans();
+        self.methods.clear_spans();
+        self.constants.clear_spans();
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for Attribute {
+    fn clear_spans(&mut self) {
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for Broadcast {
+    fn clear_spans(&mut self) {
+        self.out_args.clear_spans();
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for Field {
+    fn clear_spans(&mut self) {
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for Argument {
+    fn clear_spans(&mut self) {
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for Method {
+    fn clear_spans(&mut self) {
+        self.in_args.clear_spans();
+        self.out_args.clear_spans();
+        if let Some(MethodErrorSpec::EnumerationBody{ enumerators, .. }) = &mut self.error {
+            enumerators.clear_spans();
+        }
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for Enumerator {
+    fn clear_spans(&mut self) {
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for TypeCollection {
+    fn clear_spans(&mut self) {
+        self.types.clear_spans();
+        self.constants.clear_spans();
+        self.span = Span::default();
+    }
+}
+
+impl ClearSpans for Type {
+    fn clear_spans(&mut self) {
+        match self {
+            Type::Struct{ fields, .. } | Type::Union{ fields, .. } => fields.clear_spans(),
+            Type::Enumeration{ enumerators, .. } => enumerators.clear_spans(),
+            _ => {},
+        }
+        match self {
+            Type::TypeDef{ span, .. } | Type::Array{ span, .. } | Type::Struct{ span, .. } |
+            Type::Union{ span, .. } | Type::Map{ span, .. } | Type::Enumeration{ span, .. } => *span = Span::default(),
+        }
+    }
+}
+
+impl ClearSpans for Constant {
+    fn clear_spans(&mut self) {
+        self.span = Span::default();
+    }
 }
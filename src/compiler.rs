@@ -5,15 +5,18 @@
 // Author: Alexander Seifarth
 
 use super::ast;
-use super::parser;
+use super::deployment::{self, ast::DeploymentModel};
+use super::diagnostic::Diagnostic;
+use super::import_graph::{ImportEdge, ImportGraph};
+use super::parser::{self, ParseMode};
+use super::source::{FileSystem, ResolveError, SourceProvider};
 use std::{
+    fmt,
     path::{Path, PathBuf},
-    sync::{Arc, Mutex},
+    sync::Arc,
 };
-use tokio;
-use tokio::task::JoinHandle;
 
-/// Errors reported during parsing, compilation and linking of FIDL files.
+/// Errors reported during parsing, compilation and linking of FIDL and FDEPL files.
 #[derive(Debug)]
 pub enum ParseError {
     /// I/O Errors (e.g. file not found, not readable, ... )
@@ -22,150 +25,538 @@ pub enum ParseError {
     /// Error resulting from path processing (e.g. invalid path strings)
     PathError{file: PathBuf, referenced_by: Option<PathBuf>, error: std::io::Error},
 
-    /// Maximum recursion level from FIDL imports reached.
-    MaxImportNestingReached{file: PathBuf, referenced_by: Option<PathBuf>},
+    /// Import of `file` exceeds the maximum import nesting. `chain` lists the import chain from
+    /// the file given to `parse_fidls` to `file`.
+    MaxImportNestingReached{file: PathBuf, referenced_by: Option<PathBuf>, chain: Vec<PathBuf>},
 
-    /// File not found
-    FileNotFound{file: PathBuf,  referenced_by: Option<PathBuf>},
+    /// Imported file not found, `searched` lists the paths tried in search order
+    FileNotFound{file: PathBuf,  referenced_by: Option<PathBuf>, searched: Vec<PathBuf>},
 
-    /// Syntax error while parsing a FIDL fild
-    SyntaxError{file: PathBuf,  referenced_by: Option<PathBuf>,}
+    /// Syntax error while parsing a FIDL or FDEPL file, the diagnostic tells where and what went wrong
+    SyntaxError{file: PathBuf,  referenced_by: Option<PathBuf>, diagnostic: Box<Diagnostic>},
+
+    /// Import URI which cannot be resolved for another reason than a missing file, e.g. an
+    /// unknown URI scheme
+    InvalidImport{uri: String, referenced_by: Option<PathBuf>, error: ResolveError},
+
+    /// Files importing each other, the last file of the cycle imports the first one
+    ImportCycle{cycle: Vec<PathBuf>},
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let paths = |paths: &[PathBuf]| paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>();
+        let referenced_by = match self {
+            ParseError::IoError{ file, referenced_by, error } => {
+                write!(f, "{}: error: {}", file.display(), error)?;
+                referenced_by
+            },
+            ParseError::PathError{ file, referenced_by, error } => {
+                write!(f, "{}: error: invalid path: {}", file.display(), error)?;
+                referenced_by
+            },
+            ParseError::MaxImportNestingReached{ file, referenced_by, chain } => {
+                write!(f, "{}: error: maximum import nesting reached, import chain {} -> {}", file.display(),
+                       paths(chain).join(" -> "), file.display())?;
+                referenced_by
+            },
+            ParseError::FileNotFound{ file, referenced_by, searched } => {
+                write!(f, "{}: error: file not found", file.display())?;
+                if !searched.is_empty() {
+                    write!(f, ", searched {}", paths(searched).join(", "))?;
+                }
+                referenced_by
+            },
+            ParseError::SyntaxError{ diagnostic, .. } => return write!(f, "{}", diagnostic),
+            ParseError::InvalidImport{ uri, referenced_by, error } => {
+                if let Some(file) = referenced_by {
+                    write!(f, "{}: ", file.display())?;
+                }
+                return write!(f, "error: cannot resolve import `{}`: {}", uri, error);
+            },
+            ParseError::ImportCycle{ cycle } => {
+                let first = cycle.first().map(|p| p.display().to_string()).unwrap_or_default();
+                return write!(f, "{}: error: import cycle {} -> {}", first, paths(cycle).join(" -> "), first);
+            },
+        };
+        match referenced_by {
+            Some(by) => write!(f, "\n  imported by {}", by.display()),
+            None => Ok(()),
+        }
+    }
 }
 
 /// Parses a list of FRANCA IDL files including imported FRANCA files transitively.
 /// The method only parses the files and returns them as list of ast::Modules, there is no
-/// semantic check.
+/// semantic check. Use `linker::link` to resolve the references between the modules.
 /// # Arguments
 /// * `fidls`:        List of FRANCA IDL (.fidl) files to parse
 /// * `search_dirs`:  List of directories used to search for imported FRANCA FIDL files.
-/// * `max_import_nesting`: Maximum depth of import file nesting.
+/// * `max_import_nesting`: Maximum length of an import chain, `0` allows no imports at all.
 /// * `follow_imports`:  If `true` the imports of each found FIDL file are searched and parsed too.
-pub async fn parse_fidls(fidls: &Vec<PathBuf>,
-                         search_dirs: &Vec<PathBuf>,
+///
+/// Each file must consist of a complete module, text following the last definition is reported
+/// as syntax error. Use `parse_fidls_with_mode` to ignore such text.
+/// Parsing continues after syntax errors, so all syntax errors of a file are reported and the module
+/// with the definitions that could be parsed is returned as well.
+/// Requires the `async` feature, `parse_fidls_sync` works without async runtime.
+#[cfg(feature = "async")]
+pub async fn parse_fidls(fidls: &[PathBuf],
+                         search_dirs: &[PathBuf],
                          max_import_nesting: usize,
                          follow_imports: bool)
                          ->  (Vec<(ast::Module, PathBuf)>, Vec<ParseError>)  {
-    let module_list = Arc::new(Mutex::new(Vec::new()));
-    let mut jhs = Vec::new();
-    for f in fidls {
-        let modules_clone = module_list.clone();
-        let jh = tokio::spawn(
-            parse_single_fidl(f.clone(), None,modules_clone,
-                              search_dirs.clone(), max_import_nesting, follow_imports)
-        );
-        jhs.push(jh);
-    }
-
-    let mut mods = Vec::new();
-    let mut errs = Vec::new();
-    for jh in jhs {
-        let res =  jh.await.unwrap();
-        for r in res {
-            match r {
-                Ok((vmod, path)) => mods.push((vmod, path)),
-                Err(err) => errs.push(err),
-            }
+    parse_fidls_with_mode(fidls, search_dirs, max_import_nesting, follow_imports, ParseMode::Strict).await
+}
+
+/// Same as `parse_fidls` but with explicit parse mode. With `ParseMode::Lenient` parsing of a file
+/// stops silently in front of the first definition with a syntax error, the module contains the
+/// definitions before it. Errors in the package and import declarations are still reported.
+#[cfg(feature = "async")]
+pub async fn parse_fidls_with_mode(fidls: &[PathBuf],
+                                   search_dirs: &[PathBuf],
+                                   max_import_nesting: usize,
+                                   follow_imports: bool,
+                                   mode: ParseMode)
+                                   ->  (Vec<(ast::Module, PathBuf)>, Vec<ParseError>)  {
+    let (mods, errs, _) = parse_fidls_with_graph(fidls, search_dirs, max_import_nesting, follow_imports, mode).await;
+    (mods, errs)
+}
+
+/// Same as `parse_fidls_with_mode` but returns the import dependency graph of the parsed files
+/// too. The modules are returned in topological order, each module after the modules it imports.
+/// Import cycles are reported as `ParseError::ImportCycle`.
+#[cfg(feature = "async")]
+pub async fn parse_fidls_with_graph(fidls: &[PathBuf],
+                                    search_dirs: &[PathBuf],
+                                    max_import_nesting: usize,
+                                    follow_imports: bool,
+                                    mode: ParseMode)
+                                    ->  (Vec<(ast::Module, PathBuf)>, Vec<ParseError>, ImportGraph)  {
+    parse_fidls_with_provider(fidls, search_dirs, max_import_nesting, follow_imports, mode, Arc::new(FileSystem::new())).await
+}
+
+/// Same as `parse_fidls_with_graph` but reads the sources from `provider` instead of the file
+/// system.
+#[cfg(feature = "async")]
+pub async fn parse_fidls_with_provider(fidls: &[PathBuf],
+                                       search_dirs: &[PathBuf],
+                                       max_import_nesting: usize,
+                                       follow_imports: bool,
+                                       mode: ParseMode,
+                                       provider: Arc<dyn SourceProvider>)
+                                       ->  (Vec<(ast::Module, PathBuf)>, Vec<ParseError>, ImportGraph)  {
+    let traversal = Traversal::new(fidls, search_dirs, max_import_nesting, follow_imports, provider.as_ref());
+    let (mods, _, errs, graph) = run_async(traversal, provider.clone(), mode).await;
+    (mods, errs, graph)
+}
+
+/// Deployment models and FIDL modules, each with the file they were read from, and the errors
+/// found while parsing them.
+pub type Deployments = (Vec<(DeploymentModel, PathBuf)>, Vec<(ast::Module, PathBuf)>, Vec<ParseError>);
+
+/// Parses FRANCA deployment files (.fdepl) and the FIDL and FDEPL files they import transitively.
+/// Returns the deployment models and the FIDL modules, each in topological order, and the errors.
+/// Requires the `async` feature, `parse_fdepls_sync` works without async runtime.
+#[cfg(feature = "async")]
+pub async fn parse_fdepls(fdepls: &[PathBuf],
+                          search_dirs: &[PathBuf],
+                          max_import_nesting: usize,
+                          mode: ParseMode)
+                          -> Deployments {
+    let provider: Arc<dyn SourceProvider> = Arc::new(FileSystem::new());
+    let traversal = Traversal::new(fdepls, search_dirs, max_import_nesting, true, provider.as_ref());
+    let (mods, deployments, errs, _) = run_async(traversal, provider.clone(), mode).await;
+    (deployments, mods, errs)
+}
+
+/// Parses the files of each level of `traversal` as concurrent tasks.
+#[cfg(feature = "async")]
+async fn run_async(mut traversal: Traversal<'_>, provider: Arc<dyn SourceProvider>, mode: ParseMode) -> Compiled {
+    loop {
+        let level = traversal.next_level();
+        if level.is_empty() {
+            break;
+        }
+        let jhs: Vec<_> = level.into_iter()
+            .map(|job| {
+                let (provider, file, referenced_by) = (provider.clone(), job.file.clone(), job.referenced_by());
+                let jh = tokio::spawn(async move { parse_source(provider.as_ref(), file, referenced_by, mode) });
+                (job, jh)
+            })
+            .collect();
+        for (job, jh) in jhs {
+            traversal.complete(job, jh.await.unwrap());
         }
     }
-    (mods, errs)
+    traversal.finish()
 }
 
-pub async fn parse_single_fidl(filepath: PathBuf,
-                               referenced_by: Option<PathBuf>,
-                               mod_list: Arc<Mutex<Vec<PathBuf>>>,
-                               search_dirs: Vec<PathBuf>,
-                               max_nesting: usize,
-                               follow_imports: bool)
-        -> Vec<Result<(ast::Module, PathBuf), ParseError>>
-{
-    use std::fs;
-    let file = match filepath.canonicalize() {
-        Ok(pt) => pt,
-        Err(error) => return vec![Err(ParseError::PathError {file: filepath, referenced_by, error})],
-    };
+/// Blocking variant of `parse_fidls_with_graph` which needs no async runtime. With `parallel` the
+/// files of each import level are parsed on up to `std::thread::available_parallelism()` threads.
+pub fn parse_fidls_sync(fidls: &[PathBuf],
+                        search_dirs: &[PathBuf],
+                        max_import_nesting: usize,
+                        follow_imports: bool,
+                        mode: ParseMode,
+                        parallel: bool)
+                        ->  (Vec<(ast::Module, PathBuf)>, Vec<ParseError>, ImportGraph)  {
+    parse_fidls_sync_with_provider(fidls, search_dirs, max_import_nesting, follow_imports, mode, parallel, &FileSystem::new())
+}
+
+/// Same as `parse_fidls_sync` but reads the sources from `provider` instead of the file system.
+pub fn parse_fidls_sync_with_provider(fidls: &[PathBuf],
+                                      search_dirs: &[PathBuf],
+                                      max_import_nesting: usize,
+                                      follow_imports: bool,
+                                      mode: ParseMode,
+                                      parallel: bool,
+                                      provider: &dyn SourceProvider)
+                                      ->  (Vec<(ast::Module, PathBuf)>, Vec<ParseError>, ImportGraph)  {
+    let traversal = Traversal::new(fidls, search_dirs, max_import_nesting, follow_imports, provider);
+    let (mods, _, errs, graph) = run_sync(traversal, provider, mode, parallel);
+    (mods, errs, graph)
+}
+
+/// Blocking variant of `parse_fdepls` which needs no async runtime. With `parallel` the files of
+/// each import level are parsed on up to `std::thread::available_parallelism()` threads.
+pub fn parse_fdepls_sync(fdepls: &[PathBuf],
+                         search_dirs: &[PathBuf],
+                         max_import_nesting: usize,
+                         mode: ParseMode,
+                         parallel: bool)
+                         -> Deployments {
+    parse_fdepls_sync_with_provider(fdepls, search_dirs, max_import_nesting, mode, parallel, &FileSystem::new())
+}
+
+/// Same as `parse_fdepls_sync` but reads the sources from `provider` instead of the file system.
+pub fn parse_fdepls_sync_with_provider(fdepls: &[PathBuf],
+                                       search_dirs: &[PathBuf],
+                                       max_import_nesting: usize,
+                                       mode: ParseMode,
+                                       parallel: bool,
+                                       provider: &dyn SourceProvider)
+                                       -> Deployments {
+    let traversal = Traversal::new(fdepls, search_dirs, max_import_nesting, true, provider);
+    let (mods, deployments, errs, _) = run_sync(traversal, provider, mode, parallel);
+    (deployments, mods, errs)
+}
 
-    {
-        let mut ml_lock = mod_list.lock().unwrap();
-        if ml_lock.contains(&file) {
-            return vec![]
+/// Parses the files of each level of `traversal`, with `parallel` on up to
+/// `std::thread::available_parallelism()` threads, each parsing a share of the level.
+fn run_sync(mut traversal: Traversal<'_>, provider: &dyn SourceProvider, mode: ParseMode, parallel: bool) -> Compiled {
+    let workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+    loop {
+        let level = traversal.next_level();
+        if level.is_empty() {
+            break;
+        }
+        if parallel && level.len() > 1 && workers > 1 {
+            let results: Vec<_> = std::thread::scope(|scope| {
+                let handles: Vec<_> = level.chunks(level.len().div_ceil(workers))
+                    .map(|jobs| scope.spawn(move || jobs.iter()
+                        .map(|job| parse_source(provider, job.file.clone(), job.referenced_by(), mode))
+                        .collect::<Vec<_>>()))
+                    .collect();
+                handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+            });
+            for (job, result) in level.into_iter().zip(results) {
+                traversal.complete(job, result);
+            }
+        } else {
+            for job in level {
+                let result = parse_source(provider, job.file.clone(), job.referenced_by(), mode);
+                traversal.complete(job, result);
+            }
         }
-        ml_lock.push(file.clone());
     }
+    traversal.finish()
+}
 
-    let text = match fs::read_to_string(file.clone()) {
-        Ok(r) => r,
-        Err(error) => return vec![Err(ParseError::IoError {file: file.to_path_buf(), referenced_by, error})]
-    };
+/// Import resolution shared by the async and the blocking compiler. Files are parsed level by
+/// level, so each file is reached by its shortest import chain: `next_level` returns the files
+/// to parse next, their results are passed to `complete`.
+struct Traversal<'a> {
+    provider: &'a dyn SourceProvider,
+    search_dirs: &'a [PathBuf],
+    max_nesting: usize,
+    follow_imports: bool,
+    pending: Vec<PendingFile>,
+    graph: ImportGraph,
+    mods: Vec<(ast::Module, PathBuf)>,
+    deployments: Vec<(DeploymentModel, PathBuf)>,
+    errs: Vec<ParseError>,
+}
 
-    let module = match parser::parse_module(&text) {
-        Ok(m) => m.1,
-        Err(_) => return vec![Err(ParseError::SyntaxError {file: file.to_path_buf(), referenced_by})]
-    };
+/// Result of a traversal: FIDL modules and deployment models in topological order, errors and
+/// import graph.
+type Compiled = (Vec<(ast::Module, PathBuf)>, Vec<(DeploymentModel, PathBuf)>, Vec<ParseError>, ImportGraph);
+
+/// Content of a parsed file, the kind of file is told by its extension.
+enum Source {
+    Fidl(ast::Module),
+    Fdepl(DeploymentModel),
+}
 
-    if module.imports.len() > 0 && max_nesting == 0 {
-        return vec![Err(ParseError::MaxImportNestingReached {file: file.to_path_buf(), referenced_by})];
-    }
-
-    if follow_imports {
-        let mut jhs = Vec::new();
-        let current_dir = filepath.parent();
-        for imp in module.imports.iter().filter(|i| !i.uri.is_empty()) {
-            let referenced_by = file.clone();
-            let ml = mod_list.clone();
-            let import_file = find_file(&imp.uri, current_dir, &search_dirs);
-            if let Some(f) = import_file {
-                let jh = spawn(f, Some(referenced_by), ml,
-                               search_dirs.clone(), max_nesting - 1, follow_imports);
-                jhs.push(jh);
-            } else {
-                return vec![Err(ParseError::FileNotFound { file: Path::new(&imp.uri).to_path_buf(), referenced_by: Some(file) })]
+/// File to parse with the chain of files importing it, the file given to `parse_fidls` first.
+struct PendingFile {
+    path: PathBuf,
+    chain: Vec<PathBuf>,
+}
+
+/// File of the current level, `file` is the canonical path of `pending.path`.
+struct Job {
+    pending: PendingFile,
+    file: PathBuf,
+}
+
+impl Job {
+    fn referenced_by(&self) -> Option<PathBuf> {
+        self.pending.chain.last().cloned()
+    }
+}
+
+impl<'a> Traversal<'a> {
+    fn new(fidls: &[PathBuf], search_dirs: &'a [PathBuf], max_nesting: usize, follow_imports: bool,
+           provider: &'a dyn SourceProvider) -> Self {
+        let pending = fidls.iter().map(|f| PendingFile {path: f.clone(), chain: Vec::new()}).collect();
+        Traversal {provider, search_dirs, max_nesting, follow_imports, pending, graph: ImportGraph::default(),
+                   mods: Vec::new(), deployments: Vec::new(), errs: Vec::new()}
+    }
+
+    /// Files to parse next, files already parsed are skipped.
+    fn next_level(&mut self) -> Vec<Job> {
+        let mut jobs = Vec::new();
+        for pending in std::mem::take(&mut self.pending) {
+            let file = match self.provider.canonicalize(&pending.path) {
+                Ok(pt) => pt,
+                Err(error) => {
+                    let referenced_by = pending.chain.last().cloned();
+                    self.errs.push(ParseError::PathError {file: pending.path, referenced_by, error});
+                    continue;
+                }
+            };
+            if self.graph.add_node(file.clone()) {
+                jobs.push(Job {pending, file});
             }
         }
+        jobs
+    }
 
-        let mut result = vec![Ok((module, file.to_path_buf()))];
-        for jh in jhs {
-            let mut r = jh.await.unwrap();
-            result.append(&mut r);
+    /// Takes the result of parsing the file of `job` and queues its imports for the next level.
+    fn complete(&mut self, job: Job, (source, mut errors): (Option<Source>, Vec<ParseError>)) {
+        self.errs.append(&mut errors);
+        match source {
+            Some(Source::Fidl(module)) => {
+                if self.follow_imports {
+                    self.imports(&module.imports, &job);
+                }
+                self.mods.push((module, job.file));
+            },
+            Some(Source::Fdepl(model)) => {
+                if self.follow_imports {
+                    self.imports(&model.imports, &job);
+                }
+                self.deployments.push((model, job.file));
+            },
+            None => {},
         }
-        result
     }
-    else {
-        vec![Ok((module, file.to_path_buf()))]
+
+    /// Resolves the `imports` of a module or deployment model, records them in the graph and queues the files still to
+    /// parse. Imports extending the import chain beyond `max_nesting` hops are reported together
+    /// with the complete chain, missing imports with the paths searched for them.
+    fn imports(&mut self, imports: &[ast::Import], job: &Job) {
+        let file = &job.file;
+        let chain: Vec<PathBuf> = job.pending.chain.iter().cloned().chain(Some(file.clone())).collect();
+        for imp in imports.iter().filter(|i| !i.uri.is_empty()) {
+            let found = match self.provider.resolve(&imp.uri, Some(&job.pending.path), self.search_dirs) {
+                Ok(found) => found,
+                Err(ResolveError::NotFound {searched}) => {
+                    self.errs.push(ParseError::FileNotFound {file: Path::new(&imp.uri).to_path_buf(),
+                                                             referenced_by: Some(file.clone()), searched});
+                    continue;
+                },
+                Err(error) => {
+                    self.errs.push(ParseError::InvalidImport {uri: imp.uri.clone(), referenced_by: Some(file.clone()), error});
+                    continue;
+                }
+            };
+            let Ok(to) = self.provider.canonicalize(&found) else {
+                self.pending.push(PendingFile {path: found, chain: chain.clone()});
+                continue;
+            };
+            self.graph.add_edge(ImportEdge {from: file.clone(), to: to.clone(), namespace: imp.namespace.clone(),
+                                            uri: imp.uri.clone(), span: imp.span.clone()});
+            if self.graph.contains(&to) {
+                continue;
+            }
+            if chain.len() > self.max_nesting {
+                let mut import_chain = chain.clone();
+                import_chain.push(to.clone());
+                self.errs.push(ParseError::MaxImportNestingReached {file: to, referenced_by: Some(file.clone()),
+                                                                    chain: import_chain});
+                continue;
+            }
+            self.pending.push(PendingFile {path: found, chain: chain.clone()});
+        }
+    }
+
+    fn finish(mut self) -> Compiled {
+        self.errs.extend(self.graph.cycles().into_iter().map(|cycle| ParseError::ImportCycle {cycle}));
+        self.graph.sort_modules(&mut self.mods);
+        self.graph.sort_modules(&mut self.deployments);
+        (self.mods, self.deployments, self.errs, self.graph)
     }
 }
 
-fn spawn(filepath: PathBuf, referenced_by: Option<PathBuf>, mod_list: Arc<Mutex<Vec<PathBuf>>>,
-    search_dirs: Vec<PathBuf>, max_nesting: usize, follow_imports: bool)
-        -> JoinHandle<Vec<Result<(ast::Module, PathBuf), ParseError>>>
+/// Reads a single FIDL file from `provider` and parses it without following its imports. Returns
+/// the module, if the file could be read, and the errors found.
+pub fn parse_single_fidl(provider: &dyn SourceProvider, file: PathBuf, referenced_by: Option<PathBuf>,
+                         mode: ParseMode) -> (Option<ast::Module>, Vec<ParseError>)
 {
-    tokio::spawn(async move {
-        parse_single_fidl(filepath, referenced_by, mod_list, search_dirs,
-                          max_nesting -1, follow_imports).await
-    })
-}
-
-fn find_file(uri: &str, current_dir: Option<&Path>, search_dirs: &Vec<PathBuf>)
-        -> Option<PathBuf> {
-    let uri_path = Path::new(uri);
-    if uri_path.is_absolute() {
-        if uri_path.is_file() {
-            return Some(uri_path.to_path_buf())
-        }
-        return None;
+    parse_file(provider, file, referenced_by, mode)
+}
+
+/// Reads and parses `file` as deployment model if it has the extension `fdepl`, as FIDL module
+/// otherwise.
+fn parse_source(provider: &dyn SourceProvider, file: PathBuf, referenced_by: Option<PathBuf>, mode: ParseMode)
+        -> (Option<Source>, Vec<ParseError>)
+{
+    if file.extension() != Some("fdepl".as_ref()) {
+        let (module, errors) = parse_file(provider, file, referenced_by, mode);
+        return (module.map(Source::Fidl), errors);
     }
+    let text = match provider.read(&file) {
+        Ok(r) => r,
+        Err(error) => return (None, vec![ParseError::IoError {file, referenced_by, error}])
+    };
+    let file_arc: Arc<Path> = Arc::from(file.as_path());
+    match deployment::parse_fdepl(&text, Some(&file_arc), mode) {
+        Ok((_, model)) => (Some(Source::Fdepl(model)), Vec::new()),
+        Err(diagnostic) => (None, vec![ParseError::SyntaxError {file, referenced_by, diagnostic}]),
+    }
+}
+
+fn parse_file(provider: &dyn SourceProvider, file: PathBuf, referenced_by: Option<PathBuf>, mode: ParseMode)
+        -> (Option<ast::Module>, Vec<ParseError>)
+{
+    let text = match provider.read(&file) {
+        Ok(r) => r,
+        Err(error) => return (None, vec![ParseError::IoError {file, referenced_by, error}])
+    };
+
+    let file_arc: Arc<Path> = Arc::from(file.as_path());
+    let (module, diagnostics) = match mode {
+        ParseMode::Strict => parser::parse_fidl_recovering(&text, Some(&file_arc)),
+        ParseMode::Lenient => match parser::parse_fidl(&text, Some(&file_arc), mode) {
+            Ok(m) => (m.1, Vec::new()),
+            // broken package or imports, reported as in strict mode
+            Err(_) => parser::parse_fidl_recovering(&text, Some(&file_arc)),
+        },
+    };
+    let errors = diagnostics.into_iter()
+        .map(|diagnostic| ParseError::SyntaxError {file: file.clone(), referenced_by: referenced_by.clone(), diagnostic: Box::new(diagnostic)})
+        .collect();
+    (Some(module), errors)
+}
 
-    if let Some(cdir) = current_dir {
-        let f1 = cdir.join(uri_path);
-        if f1.is_file() {
-            return Some(f1);
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::source::InMemory;
+
+    fn parse(sources: &InMemory, fidls: &[&str], search_dirs: &[&str])
+            -> (Vec<(ast::Module, PathBuf)>, Vec<ParseError>, ImportGraph) {
+        let fidls: Vec<PathBuf> = fidls.iter().map(PathBuf::from).collect();
+        let search_dirs: Vec<PathBuf> = search_dirs.iter().map(PathBuf::from).collect();
+        parse_fidls_sync_with_provider(&fidls, &search_dirs, 256, true, ParseMode::Strict, false, sources)
+    }
+
+    #[test]
+    fn test_in_memory_imports() {
+        let sources = InMemory::new()
+            .with("api/Service.fidl", "package api\nimport types.* from \"Types.fidl\"\nimport api.* from \"./Local.fidl\"\ninterface Service { }")
+            .with("api/Local.fidl", "package api\nimport types.* from \"../types/Types.fidl\"\ntypeCollection Local { }")
+            .with("types/Types.fidl", "package types\ntypeCollection Types { }");
+        let (modules, errors, graph) = parse(&sources, &["api/Service.fidl"], &["types"]);
+        assert!(errors.is_empty(), "{:?}", errors);
+        let files: Vec<_> = modules.iter().map(|(_, f)| f.to_str().unwrap()).collect();
+        assert_eq!(files, vec!["types/Types.fidl", "api/Local.fidl", "api/Service.fidl"]);
+        assert_eq!(graph.edges().len(), 3);
+    }
+
+    #[test]
+    fn test_in_memory_errors() {
+        let sources = InMemory::new()
+            .with("A.fidl", "package a\nimport b.* from \"B.fidl\"\ninterface A { method m { } ")
+            .with("B.fidl", "package b\nimport a.* from \"A.fidl\"\nimport c.* from \"C.fidl\"");
+        let (modules, errors, _) = parse(&sources, &["A.fidl", "Unknown.fidl"], &["dir"]);
+        assert_eq!(modules.len(), 2);
+        assert_eq!(errors.len(), 4, "{:?}", errors);
+        assert!(matches!(&errors[0], ParseError::PathError {file, ..} if file.ends_with("Unknown.fidl")));
+        assert!(matches!(&errors[1], ParseError::SyntaxError {file, ..} if file.ends_with("A.fidl")));
+        match &errors[2] {
+            ParseError::FileNotFound {file, referenced_by, searched} => {
+                assert_eq!(file, Path::new("C.fidl"));
+                assert_eq!(referenced_by.as_deref(), Some(Path::new("B.fidl")));
+                assert_eq!(searched, &vec![PathBuf::from("C.fidl"), PathBuf::from("dir/C.fidl")]);
+            },
+            e => panic!("unexpected error {:?}", e),
         }
+        assert!(matches!(&errors[3], ParseError::ImportCycle {cycle} if cycle.len() == 2));
+    }
+
+    #[test]
+    fn test_single_fidl() {
+        let sources = InMemory::new().with("A.fidl", "package a\nimport b.* from \"B.fidl\"\ninterface A { }");
+        let (module, errors) = parse_single_fidl(&sources, PathBuf::from("A.fidl"), None, ParseMode::Strict);
+        assert!(errors.is_empty());
+        assert_eq!(module.map(|m| m.interfaces.len()), Some(1));
+
+        let (module, errors) = parse_single_fidl(&sources, PathBuf::from("B.fidl"), Some(PathBuf::from("A.fidl")),
+            ParseMode::Strict);
+        assert!(module.is_none());
+        assert!(matches!(&errors[..], [ParseError::IoError {referenced_by: Some(_), ..}]));
+    }
+
+    #[test]
+    fn test_lenient() {
+        let sources = InMemory::new()
+            .with("A.fidl", "package a\ninterface A { }\ninterface B { methd x { } }")
+            .with("B.fidl", "packge b\ninterface B { }");
+        let fidls = [PathBuf::from("A.fidl"), PathBuf::from("B.fidl")];
+        let (modules, errors, _) = parse_fidls_sync_with_provider(&fidls, &[], 256, true, ParseMode::Lenient,
+            false, &sources);
+        assert_eq!(modules.len(), 2);
+        assert_eq!(modules[0].0.interfaces.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(), vec!["A"]);
+        assert_eq!(modules[1].0.interfaces.len(), 1);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(matches!(&errors[0], ParseError::SyntaxError {file, ..} if file.ends_with("B.fidl")));
     }
 
-    for dir in search_dirs {
-        let f = dir.join(uri_path);
-        if f.is_file() {
-            return Some(f);
+    #[test]
+    fn test_import_uris() {
+        let sources = InMemory::new()
+            .with("/ws/api/Service.fidl", "package api
+                import types.* from \"classpath:/types/Types.fidl\"
+                import common.* from \"platform:/resource/common/Common.fidl\"
+                import remote.* from \"http://example.com/Remote.fidl\"
+                interface Service { }")
+            .with("/lib/types/Types.fidl", "package types")
+            .with("/ws/common/Common.fidl", "package common")
+            .with_project("common", "/ws/common");
+        let fidls = [PathBuf::from("/ws/api/Service.fidl")];
+        let (modules, errors, _) = parse_fidls_sync_with_provider(&fidls, &[PathBuf::from("/lib")], 256, true,
+                                                                  ParseMode::Strict, false, &sources);
+        assert_eq!(modules.len(), 3);
+        assert_eq!(errors.len(), 1);
+        match &errors[0] {
+            ParseError::InvalidImport {uri, error, ..} => {
+                assert_eq!(uri, "http://example.com/Remote.fidl");
+                assert_eq!(error.to_string(), "unknown URI scheme `http`");
+            },
+            e => panic!("unexpected error {:?}", e),
         }
     }
-    None
 }
@@ -0,0 +1,256 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+//! Detection of cyclic definitions: inheritance loops of structs, unions, enumerations and
+//! interfaces, interfaces managing each other, typedefs resolving to themselves and structs or
+//! unions containing themselves by value.
+
+use super::ast;
+use super::model::{Field, InterfaceId, Model, TypeId, TypeKind, TypeRef};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::PathBuf;
+
+/// The relation forming a cycle.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CycleKind {
+    /// `extends` of structs and enumerations, base type of unions
+    TypeInheritance,
+
+    /// typedef resolving to itself
+    TypeDef,
+
+    /// struct or union containing itself by value (not via array or map)
+    Containment,
+
+    /// `extends` of interfaces
+    InterfaceInheritance,
+
+    /// `manages` of interfaces
+    Manages,
+}
+
+impl fmt::Display for CycleKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CycleKind::TypeInheritance => "cyclic type inheritance",
+            CycleKind::TypeDef => "cyclic typedef",
+            CycleKind::Containment => "type contains itself by value",
+            CycleKind::InterfaceInheritance => "cyclic interface inheritance",
+            CycleKind::Manages => "cyclic `manages` relation",
+        })
+    }
+}
+
+/// Element of a cycle: a definition referring to the next element of the cycle.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CycleElement {
+    /// Fully qualified name of the definition
+    pub fqn: String,
+    pub file: PathBuf,
+
+    /// Location of the whole definition, not of its reference to the next element
+    pub span: ast::Span,
+}
+
+/// Cycle found in the model. The last element of `path` refers to the first one.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CycleError {
+    pub kind: CycleKind,
+    pub path: Vec<CycleElement>,
+}
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let first = &self.path[0];
+        let names: Vec<&str> = self.path.iter().chain(Some(first)).map(|e| e.fqn.as_str()).collect();
+        write!(f, "{}:{}:{}: error: {}: {}", first.file.display(), first.span.line, first.span.column,
+               self.kind, names.join(" -> "))?;
+        for element in &self.path {
+            write!(f, "\n  `{}` at {}:{}:{}", element.fqn, element.file.display(), element.span.line, element.span.column)?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds all cycles of the model. Each cycle is reported once, starting with the element
+/// defined first.
+pub fn find_cycles(model: &Model) -> Vec<CycleError> {
+    let types: Vec<TypeId> = model.types().map(|(id, _)| id).collect();
+    let interfaces: Vec<InterfaceId> = model.interfaces().map(|(id, _)| id).collect();
+    let type_element = |i: usize| {
+        let tp = &model[types[i]];
+        CycleElement{ fqn: tp.fqn.clone(), file: tp.file.clone(), span: tp.span.clone() }
+    };
+    let interface_element = |i: usize| {
+        let interface = &model[interfaces[i]];
+        CycleElement{ fqn: interface.fqn.clone(), file: interface.file.clone(), span: interface.span.clone() }
+    };
+
+    let mut errors = Vec::new();
+    let mut report = |kind, cycles: Vec<Vec<usize>>, element: &dyn Fn(usize) -> CycleElement| {
+        for cycle in cycles {
+            errors.push(CycleError{ kind, path: cycle.into_iter().map(element).collect() });
+        }
+    };
+
+    report(CycleKind::TypeInheritance,
+           cycles(types.len(), |i| model.base_type(types[i]).map(TypeId::index).into_iter().collect()),
+           &type_element);
+    report(CycleKind::TypeDef,
+           cycles(types.len(), |i| match &model[types[i]].kind {
+               TypeKind::TypeDef{ actual_type: TypeRef::Type(id), .. }
+                   if matches!(model[*id].kind, TypeKind::TypeDef{ .. }) => vec![id.index()],
+               _ => vec![],
+           }),
+           &type_element);
+    report(CycleKind::Containment,
+           cycles(types.len(), |i| contained(model, types[i]).into_iter().map(TypeId::index).collect()),
+           &type_element);
+
+    report(CycleKind::InterfaceInheritance,
+           cycles(interfaces.len(), |i| model[interfaces[i]].base.map(InterfaceId::index).into_iter().collect()),
+           &interface_element);
+    report(CycleKind::Manages,
+           cycles(interfaces.len(), |i| model[interfaces[i]].manages.iter().map(|id| id.index()).collect()),
+           &interface_element);
+    errors
+}
+
+/// Structs and unions a struct or union contains by value, including the fields of its bases.
+fn contained(model: &Model, id: TypeId) -> Vec<TypeId> {
+    if !matches!(model[id].kind, TypeKind::Struct{ .. } | TypeKind::Union{ .. }) {
+        return Vec::new();
+    }
+    model.all_fields(id).into_iter()
+        .filter(|field| !field.array)
+        .filter_map(|field| by_value(model, field))
+        .collect()
+}
+
+/// The struct or union a field holds by value, typedefs of arrays break the containment.
+fn by_value(model: &Model, field: &Field) -> Option<TypeId> {
+    let mut current = &field.type_ref;
+    let mut seen = Vec::new();
+    while let TypeRef::Type(id) = current {
+        match &model[*id].kind {
+            TypeKind::TypeDef{ actual_type, array: false } if !seen.contains(id) => {
+                seen.push(*id);
+                current = actual_type;
+            },
+            TypeKind::Struct{ .. } | TypeKind::Union{ .. } => return Some(*id),
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Finds the cycles of a directed graph by depth first search. Cycles sharing an edge with an
+/// already reported cycle may be missed, each node set is reported at most once.
+pub(crate) fn cycles(count: usize, edges: impl Fn(usize) -> Vec<usize>) -> Vec<Vec<usize>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State { New, Active, Done }
+
+    fn visit(node: usize, edges: &dyn Fn(usize) -> Vec<usize>, state: &mut [State], stack: &mut Vec<usize>,
+             found: &mut Vec<Vec<usize>>, reported: &mut HashSet<Vec<usize>>) {
+        state[node] = State::Active;
+        stack.push(node);
+        for next in edges(node) {
+            match state[next] {
+                State::New => visit(next, edges, state, stack, found, reported),
+                State::Active => {
+                    let start = stack.iter().position(|n| *n == next).unwrap();
+                    let mut cycle = stack[start..].to_vec();
+                    let mut key = cycle.clone();
+                    key.sort_unstable();
+                    if reported.insert(key) {
+                        // start the cycle with the element defined first
+                        let first = cycle.iter().enumerate().min_by_key(|(_, n)| **n).unwrap().0;
+                        cycle.rotate_left(first);
+                        found.push(cycle);
+                    }
+                },
+                State::Done => {},
+            }
+        }
+        stack.pop();
+        state[node] = State::Done;
+    }
+
+    let mut state = vec![State::New; count];
+    let mut found = Vec::new();
+    let mut reported = HashSet::new();
+    for node in 0..count {
+        if state[node] == State::New {
+            visit(node, &edges, &mut state, &mut Vec::new(), &mut found, &mut reported);
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser;
+
+    fn find(text: &str) -> Vec<String> {
+        let modules = vec![(parser::parse_module(text).unwrap().1, PathBuf::from("c.fidl"))];
+        let (model, _) = Model::build(&modules);
+        find_cycles(&model).iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_no_cycles() {
+        assert_eq!(find("package a
+            typeCollection T {
+                struct Node { Node[] children  Children more  Base base }
+                array Children of Node
+                typedef Nodes is Node[]
+                struct Tree { Nodes nodes  Map m }
+                map Map { String to Tree }
+                struct Base { UInt8 x }
+                struct Derived extends Base { Base b }
+            }
+            interface A { }
+            interface B extends A manages A { }"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_inheritance() {
+        assert_eq!(find("package a
+typeCollection T {
+    struct A extends B { }
+    struct B extends A { }
+    enumeration E extends E { X }
+}
+interface I extends J { }
+interface J extends K { }
+interface K extends I manages K { }"), vec![
+            "c.fidl:3:5: error: cyclic type inheritance: a.T.A -> a.T.B -> a.T.A\n  `a.T.A` at c.fidl:3:5\n  `a.T.B` at c.fidl:4:5",
+            "c.fidl:5:5: error: cyclic type inheritance: a.T.E -> a.T.E\n  `a.T.E` at c.fidl:5:5",
+            "c.fidl:7:1: error: cyclic interface inheritance: a.I -> a.J -> a.K -> a.I\n  `a.I` at c.fidl:7:1\n  `a.J` at c.fidl:8:1\n  `a.K` at c.fidl:9:1",
+            "c.fidl:9:1: error: cyclic `manages` relation: a.K -> a.K\n  `a.K` at c.fidl:9:1",
+        ]);
+    }
+
+    #[test]
+    fn test_typedef_and_containment() {
+        assert_eq!(find("package a
+typeCollection T {
+    typedef A is B
+    typedef B is A
+    struct S { U u }
+    union U { UInt8 x  Alias s }
+    typedef Alias is S
+    struct Base { Derived d }
+    struct Derived extends Base { }
+}"), vec![
+            "c.fidl:3:5: error: cyclic typedef: a.T.A -> a.T.B -> a.T.A\n  `a.T.A` at c.fidl:3:5\n  `a.T.B` at c.fidl:4:5",
+            "c.fidl:5:5: error: type contains itself by value: a.T.S -> a.T.U -> a.T.S\n  `a.T.S` at c.fidl:5:5\n  `a.T.U` at c.fidl:6:5",
+            "c.fidl:9:5: error: type contains itself by value: a.T.Derived -> a.T.Derived\n  `a.T.Derived` at c.fidl:9:5",
+        ]);
+    }
+}
@@ -0,0 +1,250 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+//! Abstract syntax tree of FRANCA deployment files (FDEPL).
+
+use crate::ast::{Import, Span};
+
+/// FDModel: ('package' name=FQN)? imports+=Import* (specifications+=FDSpecification | deployments+=FDRootElement)*
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DeploymentModel {
+    pub package: Option<String>,
+    pub imports: Vec<Import>,
+    pub specifications: Vec<Specification>,
+    pub definitions: Vec<Definition>,
+
+    /// Location of the model in the source
+    pub span: Span,
+}
+
+/// FDSpecification: 'specification' name=FQN ('extends' base=[FDSpecification|FQN])? '{' declarations+=FDDeclaration* '}'
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Specification {
+    pub name: String,
+    pub extends: Option<String>,
+    pub declarations: Vec<Declaration>,
+    pub span: Span,
+}
+
+/// FDDeclaration: 'for' host=FDPropertyHost '{' properties+=FDPropertyDecl* '}'
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Declaration {
+    /// Element kind the properties apply to, e.g. `interfaces`, `methods` or `strings`
+    pub host: String,
+    pub properties: Vec<PropertyDecl>,
+    pub span: Span,
+}
+
+/// FDPropertyDecl: name=ID ':' type=FDTypeRef ('(' flags+=FDPropertyFlag (',' flags+=FDPropertyFlag)* ')')? ';'
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PropertyDecl {
+    pub name: String,
+    pub type_ref: PropertyType,
+    pub array: bool,
+    pub optional: bool,
+    pub default: Option<Value>,
+    pub span: Span,
+}
+
+impl PropertyDecl {
+    /// A property without default value which is not optional must be set in each deployment.
+    pub fn is_mandatory(&self) -> bool {
+        !self.optional && self.default.is_none()
+    }
+}
+
+/// FDTypeRef: (complex=FDEnumType | predefined=FDPredefinedTypeId) (array?='[]')?
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PropertyType {
+    Integer,
+    String,
+    Boolean,
+    Interface,
+    Instance,
+
+    /// FDEnumType: '{' enumerators+=FDEnumerator (',' enumerators+=FDEnumerator)* '}'
+    Enumeration(Vec<String>),
+}
+
+/// FDComplexValue: single=FDValue | array=FDValueArray
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Value {
+    Integer(i64),
+    String(String),
+    Boolean(bool),
+
+    /// Enumerator of an enumeration property or name of an interface or instance
+    Reference(String),
+
+    /// FDValueArray: '{' values+=FDValue (',' values+=FDValue)* '}'
+    Array(Vec<Value>),
+}
+
+/// FDProperty: decl=[FDPropertyDecl] '=' value=FDComplexValue
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Property {
+    pub name: String,
+    pub value: Value,
+    pub span: Span,
+}
+
+/// FDRootElement: FDInterface | FDTypes | FDProvider
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Definition {
+    Interface(InterfaceDeployment),
+    TypeCollection(TypeCollectionDeployment),
+    Provider(ProviderDeployment),
+}
+
+impl Definition {
+    /// Fully qualified name of the specification the definition uses.
+    pub fn specification(&self) -> &str {
+        match self {
+            Definition::Interface(d) => &d.specification,
+            Definition::TypeCollection(d) => &d.specification,
+            Definition::Provider(d) => &d.specification,
+        }
+    }
+
+    pub fn span(&self) -> &Span {
+        match self {
+            Definition::Interface(d) => &d.span,
+            Definition::TypeCollection(d) => &d.span,
+            Definition::Provider(d) => &d.span,
+        }
+    }
+}
+
+/// FDInterface: 'define' spec=[FDSpecification|FQN] 'for' 'interface' target=[FInterface|FQN] ('as' name=ID)?
+///     '{' properties=FDPropertySet (attributes+=FDAttribute | methods+=FDMethod | broadcasts+=FDBroadcast |
+///     types+=FDTypeDefinition)* '}'
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct InterfaceDeployment {
+    pub specification: String,
+    pub target: String,
+    pub name: Option<String>,
+    pub properties: Vec<Property>,
+    pub attributes: Vec<ElementDeployment>,
+    pub methods: Vec<MethodDeployment>,
+    pub broadcasts: Vec<BroadcastDeployment>,
+    pub types: Vec<TypeDeployment>,
+    pub span: Span,
+}
+
+/// FDTypes: 'define' spec=[FDSpecification|FQN] 'for' 'typeCollection' target=[FTypeCollection|FQN] ('as' name=ID)?
+///     '{' properties=FDPropertySet types+=FDTypeDefinition* '}'
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TypeCollectionDeployment {
+    pub specification: String,
+    pub target: String,
+    pub name: Option<String>,
+    pub properties: Vec<Property>,
+    pub types: Vec<TypeDeployment>,
+    pub span: Span,
+}
+
+/// FDProvider: 'define' spec=[FDSpecification|FQN] 'for' 'provider' 'as' name=ID
+///     '{' properties=FDPropertySet instances+=FDInstance* '}'
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ProviderDeployment {
+    pub specification: String,
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub instances: Vec<InstanceDeployment>,
+    pub span: Span,
+}
+
+/// FDInstance: 'instance' target=[FDInterface|FQN] ('as' name=ID)? '{' properties=FDPropertySet '}'
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InstanceDeployment {
+    pub target: String,
+    pub name: Option<String>,
+    pub properties: Vec<Property>,
+    pub span: Span,
+}
+
+/// Deployment of an element without nested elements: attribute, argument, struct or union field,
+/// enumerator, array and typedef.
+/// e.g. FDArgument: target=[FArgument] '{' properties=FDPropertySet '}'
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ElementDeployment {
+    pub target: String,
+    pub properties: Vec<Property>,
+    pub span: Span,
+}
+
+/// FDMethod: 'method' target=[FMethod|FQNWithSelector] '{' properties=FDPropertySet
+///     ('in' '{' inArguments=FDArgumentList '}')? ('out' '{' outArguments=FDArgumentList '}')? '}'
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MethodDeployment {
+    /// Method name, followed by `:selector` for overloaded methods
+    pub target: String,
+    pub properties: Vec<Property>,
+    pub in_args: Vec<ElementDeployment>,
+    pub out_args: Vec<ElementDeployment>,
+    pub span: Span,
+}
+
+/// FDBroadcast: 'broadcast' target=[FBroadcast|FQNWithSelector] '{' properties=FDPropertySet
+///     ('out' '{' outArguments=FDArgumentList '}')? '}'
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BroadcastDeployment {
+    pub target: String,
+    pub properties: Vec<Property>,
+    pub out_args: Vec<ElementDeployment>,
+    pub span: Span,
+}
+
+/// FDTypeDefinition: FDArray | FDStruct | FDUnion | FDEnumeration | FDTypedef | FDMap
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TypeDeployment {
+    /// 'array' target=[FArrayType] '{' properties=FDPropertySet '}'
+    Array(ElementDeployment),
+
+    /// 'typedef' target=[FTypeDef] '{' properties=FDPropertySet '}'
+    TypeDef(ElementDeployment),
+
+    /// 'struct' target=[FStructType] '{' properties=FDPropertySet fields+=FDField* '}'
+    Struct{ target: String, properties: Vec<Property>, fields: Vec<ElementDeployment>, span: Span },
+
+    /// 'union' target=[FUnionType] '{' properties=FDPropertySet fields+=FDField* '}'
+    Union{ target: String, properties: Vec<Property>, fields: Vec<ElementDeployment>, span: Span },
+
+    /// 'enumeration' target=[FEnumerationType] '{' properties=FDPropertySet enumerators+=FDEnumValue* '}'
+    Enumeration{ target: String, properties: Vec<Property>, enumerators: Vec<ElementDeployment>, span: Span },
+
+    /// 'map' target=[FMapType] '{' properties=FDPropertySet ('key' '{' key=FDPropertySet '}')?
+    ///     ('value' '{' value=FDPropertySet '}')? '}'
+    Map{ target: String, properties: Vec<Property>, key: Vec<Property>, value: Vec<Property>, span: Span },
+}
+
+impl TypeDeployment {
+    /// Name of the deployed type.
+    pub fn target(&self) -> &str {
+        match self {
+            TypeDeployment::Array(d) | TypeDeployment::TypeDef(d) => &d.target,
+            TypeDeployment::Struct{ target, .. } | TypeDeployment::Union{ target, .. } |
+            TypeDeployment::Enumeration{ target, .. } | TypeDeployment::Map{ target, .. } => target,
+        }
+    }
+
+    /// Properties set for the type itself.
+    pub fn properties(&self) -> &[Property] {
+        match self {
+            TypeDeployment::Array(d) | TypeDeployment::TypeDef(d) => &d.properties,
+            TypeDeployment::Struct{ properties, .. } | TypeDeployment::Union{ properties, .. } |
+            TypeDeployment::Enumeration{ properties, .. } | TypeDeployment::Map{ properties, .. } => properties,
+        }
+    }
+
+    pub fn span(&self) -> &Span {
+        match self {
+            TypeDeployment::Array(d) | TypeDeployment::TypeDef(d) => &d.span,
+            TypeDeployment::Struct{ span, .. } | TypeDeployment::Union{ span, .. } |
+            TypeDeployment::Enumeration{ span, .. } | TypeDeployment::Map{ span, .. } => span,
+        }
+    }
+}
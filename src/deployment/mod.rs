@@ -0,0 +1,16 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+//! FRANCA deployment files (FDEPL): deployment specifications declaring the properties of FIDL
+//! elements and definitions assigning property values to interfaces, type collections and
+//! providers.
+
+pub mod ast;
+pub mod parser;
+pub mod validator;
+pub mod someip;
+
+pub use parser::parse_fdepl;
@@ -0,0 +1,518 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+//! Parser for FRANCA deployment files (FDEPL).
+
+use nom::{
+    branch::alt,
+    character::complete::char,
+    combinator::{cut, map, map_opt, map_res, recognize},
+    error::context,
+    multi::{fold_many0, many0, separated_list1},
+    sequence::{pair, preceded, tuple},
+};
+use super::ast::*;
+use crate::ast::Import;
+use crate::diagnostic::Diagnostic;
+use crate::parser::{diagnostic_with, located, parse_fqn, parse_identifier, parse_import, parse_integer, parse_package,
+                    parse_string, Input, PResult, ParseMode};
+use crate::util::{keyword, option, ws0};
+use std::path::Path;
+use std::sync::Arc;
+
+/// 'import' importURI=STRING, deployments import FIDL and FDEPL files without namespace
+fn parse_import_uri(input: Input) -> PResult<Import> {
+    let (r, (v, span)) = located(tuple((keyword("import"), parse_string, ws0)))(input)?;
+    Ok((r, Import{ uri: v.1, namespace: "".to_string(), span }))
+}
+
+/// Signed decimal, hexadecimal or binary integer.
+fn parse_signed_integer(input: Input) -> PResult<i64> {
+    map_res(pair(option(char('-')), parse_integer),
+            |(sign, value)| i64::try_from(value).map(|v| if sign.is_some() { -v } else { v }))(input)
+}
+
+/// FDValue: INTEGER | STRING | BOOLEAN | [FEnumerator] | [FInterface|FQN] | [FDInterfaceInstance|FQN]
+fn parse_value(input: Input) -> PResult<Value> {
+    let (r, v) = tuple((alt((
+        map(parse_string, Value::String),
+        map(parse_signed_integer, Value::Integer),
+        map(parse_fqn, |fqn: Input| match *fqn.fragment() {
+            "true" => Value::Boolean(true),
+            "false" => Value::Boolean(false),
+            reference => Value::Reference(reference.to_string()),
+        }),
+    )), ws0))(input)?;
+    Ok((r, v.0))
+}
+
+/// FDComplexValue: single=FDValue | array=FDValueArray
+fn parse_complex_value(input: Input) -> PResult<Value> {
+    alt((
+        map(tuple((char('{'), ws0, separated_list1(pair(char(','), ws0), parse_value), ws0, char('}'), ws0)),
+            |v| Value::Array(v.2)),
+        parse_value,
+    ))(input)
+}
+
+/// FDProperty: decl=[FDPropertyDecl] '=' value=FDComplexValue
+fn parse_property(input: Input) -> PResult<Property> {
+    let (r, (v, span)) = located(tuple((
+        parse_identifier, ws0, char('='), ws0, cut(parse_complex_value), ws0
+    )))(input)?;
+    Ok((r, Property{ name: v.0.to_string(), value: v.4, span }))
+}
+
+/// FDPropertySet: items+=FDProperty*
+fn parse_properties(input: Input) -> PResult<Vec<Property>> {
+    many0(parse_property)(input)
+}
+
+/// FDTypeRef: (complex=FDEnumType | predefined=FDPredefinedTypeId) (array?='[]')?
+fn parse_property_type(input: Input) -> PResult<(PropertyType, bool)> {
+    let (r, v) = tuple((
+        alt((
+            map(tuple((char('{'), ws0, separated_list1(pair(char(','), ws0), pair(parse_identifier, ws0)),
+                       char('}'))),
+                |v| PropertyType::Enumeration(v.2.into_iter().map(|e| e.0.to_string()).collect())),
+            map_opt(parse_identifier, |id: Input| match *id.fragment() {
+                "Integer" => Some(PropertyType::Integer),
+                "String" => Some(PropertyType::String),
+                "Boolean" => Some(PropertyType::Boolean),
+                "Interface" => Some(PropertyType::Interface),
+                "Instance" => Some(PropertyType::Instance),
+                _ => None,
+            }),
+        )),
+        ws0, option(tuple((char('['), ws0, char(']')))), ws0
+    ))(input)?;
+    Ok((r, (v.0, v.2.is_some())))
+}
+
+/// FDPropertyFlag: optional?='optional' | 'default:' default=FDComplexValue
+enum PropertyFlag {
+    Optional,
+    Default(Value),
+}
+
+fn parse_property_flag(input: Input) -> PResult<PropertyFlag> {
+    alt((
+        map(keyword("optional"), |_| PropertyFlag::Optional),
+        map(tuple((keyword("default"), char(':'), ws0, parse_complex_value)), |v| PropertyFlag::Default(v.3)),
+    ))(input)
+}
+
+/// FDPropertyDecl: name=ID ':' type=FDTypeRef ('(' flags+=FDPropertyFlag (',' flags+=FDPropertyFlag)* ')')? ';'
+fn parse_property_decl(input: Input) -> PResult<PropertyDecl> {
+    let (r, (v, span)) = located(tuple((
+        parse_identifier, ws0, char(':'), ws0, cut(tuple((
+        parse_property_type,
+        option(tuple((char('('), ws0, separated_list1(pair(char(','), ws0), parse_property_flag), ws0,
+                      char(')'), ws0))),
+        char(';'), ws0)))
+    )))(input)?;
+    let ((type_ref, array), flags) = (v.4.0, v.4.1.map(|f| f.2).unwrap_or_default());
+    let mut decl = PropertyDecl{ name: v.0.to_string(), type_ref, array, optional: false, default: None, span };
+    for flag in flags {
+        match flag {
+            PropertyFlag::Optional => decl.optional = true,
+            PropertyFlag::Default(value) => decl.default = Some(value),
+        }
+    }
+    Ok((r, decl))
+}
+
+/// FDDeclaration: 'for' host=FDPropertyHost '{' properties+=FDPropertyDecl* '}'
+fn parse_declaration(input: Input) -> PResult<Declaration> {
+    let (r, (v, span)) = located(tuple((
+        keyword("for"), cut(tuple((parse_identifier, ws0, char('{'), ws0,
+        many0(parse_property_decl), ws0, char('}'), ws0)))
+    )))(input)?;
+    let v = v.1;
+    Ok((r, Declaration{ host: v.0.to_string(), properties: v.4, span }))
+}
+
+/// FDSpecification: 'specification' name=FQN ('extends' base=[FDSpecification|FQN])? '{' declarations+=FDDeclaration* '}'
+fn parse_specification(input: Input) -> PResult<Specification> {
+    let (r, (v, span)) = located(context("specification", tuple((
+        keyword("specification"), cut(tuple((parse_fqn, ws0,
+        option(tuple((keyword("extends"), parse_fqn, ws0))),
+        char('{'), ws0, many0(parse_declaration), ws0, char('}'), ws0)))
+    ))))(input)?;
+    let v = v.1;
+    Ok((r, Specification{ name: v.0.to_string(), extends: v.2.map(|e| e.1.to_string()), declarations: v.5,
+        span }))
+}
+
+/// Element without nested elements: target=ID '{' properties=FDPropertySet '}'
+fn parse_element(input: Input) -> PResult<ElementDeployment> {
+    let (r, (v, span)) = located(tuple((
+        parse_identifier, ws0, char('{'), ws0, cut(tuple((parse_properties, ws0, char('}'), ws0)))
+    )))(input)?;
+    Ok((r, ElementDeployment{ target: v.0.to_string(), properties: v.4.0, span }))
+}
+
+/// Element introduced by `kind` without nested elements, e.g. 'attribute' target=[FAttribute] '{' ... '}'
+fn parse_keyword_element(kind: &'static str) -> impl FnMut(Input) -> PResult<ElementDeployment> {
+    move |input: Input| {
+        let (r, (v, span)) = located(context(kind, tuple((
+            keyword(kind), cut(tuple((parse_identifier, ws0, char('{'), ws0, parse_properties, ws0,
+            char('}'), ws0)))
+        ))))(input)?;
+        let v = v.1;
+        Ok((r, ElementDeployment{ target: v.0.to_string(), properties: v.4, span }))
+    }
+}
+
+/// ('in' | 'out') '{' arguments+=FDArgument* '}'
+fn parse_argument_list(kind: &'static str) -> impl FnMut(Input) -> PResult<Vec<ElementDeployment>> {
+    move |input: Input| {
+        let (r, v) = tuple((keyword(kind), char('{'), ws0, many0(parse_element), ws0, char('}'), ws0))(input)?;
+        Ok((r, v.3))
+    }
+}
+
+/// Method or broadcast name with optional selector
+fn parse_target_with_selector(input: Input) -> PResult<Input> {
+    recognize(pair(parse_identifier, option(pair(char(':'), parse_identifier))))(input)
+}
+
+/// FDMethod: 'method' target=[FMethod|FQNWithSelector] '{' properties=FDPropertySet
+///     ('in' '{' inArguments=FDArgumentList '}')? ('out' '{' outArguments=FDArgumentList '}')? '}'
+fn parse_method(input: Input) -> PResult<MethodDeployment> {
+    let (r, (v, span)) = located(context("method", tuple((
+        keyword("method"), cut(tuple((parse_target_with_selector, ws0, char('{'), ws0, parse_properties,
+        option(parse_argument_list("in")), option(parse_argument_list("out")),
+        ws0, char('}'), ws0)))
+    ))))(input)?;
+    let v = v.1;
+    Ok((r, MethodDeployment{ target: v.0.to_string(), properties: v.4, in_args: v.5.unwrap_or_default(),
+        out_args: v.6.unwrap_or_default(), span }))
+}
+
+/// FDBroadcast: 'broadcast' target=[FBroadcast|FQNWithSelector] '{' properties=FDPropertySet
+///     ('out' '{' outArguments=FDArgumentList '}')? '}'
+fn parse_broadcast(input: Input) -> PResult<BroadcastDeployment> {
+    let (r, (v, span)) = located(context("broadcast", tuple((
+        keyword("broadcast"), cut(tuple((parse_target_with_selector, ws0, char('{'), ws0, parse_properties,
+        option(parse_argument_list("out")), ws0, char('}'), ws0)))
+    ))))(input)?;
+    let v = v.1;
+    Ok((r, BroadcastDeployment{ target: v.0.to_string(), properties: v.4, out_args: v.5.unwrap_or_default(),
+        span }))
+}
+
+/// Target, properties and nested elements of a struct, union or enumeration deployment.
+type Compound<'a> = (Input<'a>, Vec<Property>, Vec<ElementDeployment>);
+
+/// 'struct', 'union' or 'enumeration' target=ID '{' properties=FDPropertySet elements+=FDElement* '}'
+fn parse_compound(kind: &'static str) -> impl FnMut(Input) -> PResult<(Compound, crate::ast::Span)> {
+    move |input: Input| {
+        let (r, (v, span)) = located(context(kind, tuple((
+            keyword(kind), cut(tuple((parse_identifier, ws0, char('{'), ws0, parse_properties,
+            many0(parse_element), ws0, char('}'), ws0)))
+        ))))(input)?;
+        let v = v.1;
+        Ok((r, ((v.0, v.4, v.5), span)))
+    }
+}
+
+/// 'map' target=[FMapType] '{' properties=FDPropertySet ('key' '{' key=FDPropertySet '}')?
+///     ('value' '{' value=FDPropertySet '}')? '}'
+fn parse_map(input: Input) -> PResult<TypeDeployment> {
+    let (r, (v, span)) = located(context("map", tuple((
+        keyword("map"), cut(tuple((parse_identifier, ws0, char('{'), ws0, parse_properties,
+        option(tuple((keyword("key"), char('{'), ws0, parse_properties, ws0, char('}'), ws0))),
+        option(tuple((keyword("value"), char('{'), ws0, parse_properties, ws0, char('}'), ws0))),
+        ws0, char('}'), ws0)))
+    ))))(input)?;
+    let v = v.1;
+    Ok((r, TypeDeployment::Map{ target: v.0.to_string(), properties: v.4, key: v.5.map(|k| k.3).unwrap_or_default(),
+        value: v.6.map(|k| k.3).unwrap_or_default(), span }))
+}
+
+/// FDTypeDefinition: FDArray | FDStruct | FDUnion | FDEnumeration | FDTypedef | FDMap
+fn parse_type(input: Input) -> PResult<TypeDeployment> {
+    alt((
+        map(parse_keyword_element("array"), TypeDeployment::Array),
+        map(parse_keyword_element("typedef"), TypeDeployment::TypeDef),
+        map(parse_compound("struct"), |((target, properties, fields), span)|
+            TypeDeployment::Struct{ target: target.to_string(), properties, fields, span }),
+        map(parse_compound("union"), |((target, properties, fields), span)|
+            TypeDeployment::Union{ target: target.to_string(), properties, fields, span }),
+        map(parse_compound("enumeration"), |((target, properties, enumerators), span)|
+            TypeDeployment::Enumeration{ target: target.to_string(), properties, enumerators, span }),
+        parse_map,
+    ))(input)
+}
+
+enum InterfaceMember {
+    Attribute(ElementDeployment),
+    Method(MethodDeployment),
+    Broadcast(BroadcastDeployment),
+    Type(TypeDeployment),
+}
+
+fn parse_interface_member(input: Input) -> PResult<InterfaceMember> {
+    alt((
+        map(parse_keyword_element("attribute"), InterfaceMember::Attribute),
+        map(parse_method, InterfaceMember::Method),
+        map(parse_broadcast, InterfaceMember::Broadcast),
+        map(parse_type, InterfaceMember::Type),
+    ))(input)
+}
+
+/// 'as' name=ID
+fn parse_alias(input: Input) -> PResult<String> {
+    let (r, v) = tuple((keyword("as"), parse_identifier, ws0))(input)?;
+    Ok((r, v.1.to_string()))
+}
+
+/// 'interface' target=[FInterface|FQN] ('as' name=ID)? '{' properties=FDPropertySet members* '}'
+fn parse_interface_deployment(input: Input) -> PResult<Definition> {
+    let (r, v) = tuple((
+        keyword("interface"), cut(tuple((parse_fqn, ws0, option(parse_alias), char('{'), ws0, parse_properties,
+        fold_many0(parse_interface_member, InterfaceDeployment::default,
+            |mut deployment, member| {
+                match member {
+                    InterfaceMember::Attribute(a) => deployment.attributes.push(a),
+                    InterfaceMember::Method(m) => deployment.methods.push(m),
+                    InterfaceMember::Broadcast(b) => deployment.broadcasts.push(b),
+                    InterfaceMember::Type(t) => deployment.types.push(t),
+                }
+                deployment
+            }),
+        ws0, char('}'), ws0)))
+    ))(input)?;
+    let v = v.1;
+    Ok((r, Definition::Interface(InterfaceDeployment{ target: v.0.to_string(), name: v.2, properties: v.5,
+        ..v.6 })))
+}
+
+/// 'typeCollection' target=[FTypeCollection|FQN] ('as' name=ID)? '{' properties=FDPropertySet types+=FDTypeDefinition* '}'
+fn parse_type_collection_deployment(input: Input) -> PResult<Definition> {
+    let (r, v) = tuple((
+        keyword("typeCollection"), cut(tuple((parse_fqn, ws0, option(parse_alias), char('{'), ws0,
+        parse_properties, many0(parse_type), ws0, char('}'), ws0)))
+    ))(input)?;
+    let v = v.1;
+    Ok((r, Definition::TypeCollection(TypeCollectionDeployment{ specification: String::new(),
+        target: v.0.to_string(), name: v.2, properties: v.5, types: v.6, span: Default::default() })))
+}
+
+/// FDInstance: 'instance' target=[FDInterface|FQN] ('as' name=ID)? '{' properties=FDPropertySet '}'
+fn parse_instance(input: Input) -> PResult<InstanceDeployment> {
+    let (r, (v, span)) = located(context("instance", tuple((
+        keyword("instance"), cut(tuple((parse_fqn, ws0, option(parse_alias), char('{'), ws0,
+        parse_properties, ws0, char('}'), ws0)))
+    ))))(input)?;
+    let v = v.1;
+    Ok((r, InstanceDeployment{ target: v.0.to_string(), name: v.2, properties: v.5, span }))
+}
+
+/// 'provider' 'as' name=ID '{' properties=FDPropertySet instances+=FDInstance* '}'
+fn parse_provider_deployment(input: Input) -> PResult<Definition> {
+    let (r, v) = tuple((
+        keyword("provider"), cut(tuple((parse_alias, char('{'), ws0, parse_properties, many0(parse_instance),
+        ws0, char('}'), ws0)))
+    ))(input)?;
+    let v = v.1;
+    Ok((r, Definition::Provider(ProviderDeployment{ specification: String::new(), name: v.0, properties: v.3,
+        instances: v.4, span: Default::default() })))
+}
+
+/// FDRootElement: 'define' spec=[FDSpecification|FQN] 'for' (FDInterface | FDTypes | FDProvider)
+fn parse_definition(input: Input) -> PResult<Definition> {
+    let (r, (v, span)) = located(context("define", tuple((
+        keyword("define"), cut(tuple((parse_fqn, keyword("for"),
+        alt((parse_interface_deployment, parse_type_collection_deployment, parse_provider_deployment)))))
+    ))))(input)?;
+    let (specification, mut definition) = (v.1.0.to_string(), v.1.2);
+    match &mut definition {
+        Definition::Interface(d) => { d.specification = specification; d.span = span; },
+        Definition::TypeCollection(d) => { d.specification = specification; d.span = span; },
+        Definition::Provider(d) => { d.specification = specification; d.span = span; },
+    }
+    Ok((r, definition))
+}
+
+enum ModelContent {
+    Specification(Specification),
+    Definition(Definition),
+}
+
+/// Parses a deployment model from located input, spans refer to the file given as extra information.
+pub fn parse_deployment_input(input: Input) -> PResult<DeploymentModel> {
+    let (r, (v, span)) = located(tuple((
+        ws0, option(parse_package), ws0,
+        many0(pair(alt((parse_import, parse_import_uri)), ws0)),
+        fold_many0(alt((map(parse_specification, ModelContent::Specification),
+                        map(parse_definition, ModelContent::Definition))),
+                   || (Vec::new(), Vec::new()),
+                   |(mut specifications, mut definitions), item| {
+                       match item {
+                           ModelContent::Specification(s) => specifications.push(s),
+                           ModelContent::Definition(d) => definitions.push(d),
+                       }
+                       (specifications, definitions)
+                   }),
+        ws0
+    )))(input)?;
+    Ok((r, DeploymentModel{ package: v.1, imports: v.3.into_iter().map(|i| i.0).collect(),
+        specifications: v.4.0, definitions: v.4.1, span }))
+}
+
+/// Parses the FDEPL text read from `file` and returns the unparsed rest together with the model.
+/// In `ParseMode::Strict` the rest is always empty.
+pub fn parse_fdepl<'a>(text: &'a str, file: Option<&'a Arc<Path>>, mode: ParseMode)
+        -> Result<(&'a str, DeploymentModel), Box<Diagnostic>> {
+    let input = Input::new_extra(text, file);
+    match parse_deployment_input(input) {
+        Ok((r, _)) if mode == ParseMode::Strict && !r.fragment().is_empty() =>
+            Err(Box::new(Diagnostic::expected(&r, "`specification`, `define` or end of file"))),
+        Ok((r, m)) => Ok((*r.fragment(), m)),
+        Err(e) => Err(Box::new(diagnostic_with(e, input, describe_context))),
+    }
+}
+
+/// Describes an FDEPL parser context together with the deployed element, e.g. "deployment `a.I`"
+/// for `define a.Spec for interface a.I {`.
+fn describe_context(input: Input, ctx: &str) -> String {
+    let target = match ctx {
+        "specification" | "instance" => preceded(keyword(ctx), parse_fqn)(input).ok().map(|v| v.1),
+        "define" => tuple((keyword(ctx), parse_fqn, keyword("for"), parse_identifier, ws0, option(keyword("as")),
+            parse_fqn))(input).ok().map(|v| v.1.6),
+        // deployed attributes start with the name, FIDL attributes with the type
+        "attribute" => preceded(keyword(ctx), parse_identifier)(input).ok().map(|v| v.1),
+        _ => return crate::diagnostic::describe_context(input, ctx),
+    };
+    let kind = if ctx == "define" { "deployment" } else { ctx };
+    match target {
+        Some(name) => format!("{} `{}`", kind, name),
+        None => kind.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(text: &str) -> DeploymentModel {
+        parse_fdepl(text, None, ParseMode::Strict).unwrap().1
+    }
+
+    #[test]
+    fn test_specification() {
+        let model = parse("package org.example
+specification org.example.Spec extends org.example.Base {
+    for interfaces {
+        ServiceID : Integer ;
+        Reliable : Boolean (default: false);
+        Groups : Integer[] (optional);
+        Encoding : {utf8, utf16le} (default: utf8, optional);
+    }
+    for methods { }
+}");
+        assert_eq!(model.package.as_deref(), Some("org.example"));
+        let spec = &model.specifications[0];
+        assert_eq!(spec.name, "org.example.Spec");
+        assert_eq!(spec.extends.as_deref(), Some("org.example.Base"));
+        assert_eq!(spec.declarations.len(), 2);
+        let props = &spec.declarations[0].properties;
+        assert_eq!(props.len(), 4);
+        assert_eq!((props[0].type_ref.clone(), props[0].is_mandatory()), (PropertyType::Integer, true));
+        assert_eq!(props[1].default, Some(Value::Boolean(false)));
+        assert!(props[2].array && props[2].optional);
+        assert_eq!(props[3].type_ref, PropertyType::Enumeration(vec!["utf8".to_string(), "utf16le".to_string()]));
+        assert_eq!(props[3].default, Some(Value::Reference("utf8".to_string())));
+        assert_eq!(props[3].span.line, 7);
+    }
+
+    #[test]
+    fn test_definitions() {
+        let model = parse(r#"
+import "org.example.Spec.fdepl"
+import "Service.fidl"
+
+define org.example.Spec for interface org.example.Service as Main {
+    ServiceID = 0x1234
+    Groups = { 1, -2 }
+    attribute status { Notifier = "event" }
+    method get:v2 {
+        MethodID = 1
+        in { id { Width = 4 } }
+        out { result { } }
+    }
+    broadcast changed { out { value { } } }
+    struct Point { Packed = true  x { Bits = 8 } y { } }
+    enumeration Color { red { } }
+    map Table { key { Width = 2 } value { } }
+    array Points { }
+}
+
+define org.example.Spec for typeCollection org.example.Types {
+    typedef Id { }
+}
+
+define org.example.Spec for provider as Server {
+    Host = "localhost"
+    instance org.example.Main as First { InstanceID = 1 }
+}
+"#);
+        assert_eq!(model.imports.iter().map(|i| i.uri.as_str()).collect::<Vec<_>>(),
+                   vec!["org.example.Spec.fdepl", "Service.fidl"]);
+        assert_eq!(model.definitions.len(), 3);
+
+        let Definition::Interface(interface) = &model.definitions[0] else { panic!() };
+        assert_eq!((interface.specification.as_str(), interface.target.as_str(), interface.name.as_deref()),
+                   ("org.example.Spec", "org.example.Service", Some("Main")));
+        assert_eq!(interface.properties[0].value, Value::Integer(0x1234));
+        assert_eq!(interface.properties[1].value, Value::Array(vec![Value::Integer(1), Value::Integer(-2)]));
+        assert_eq!(interface.attributes[0].properties[0].value, Value::String("event".to_string()));
+        assert_eq!(interface.methods[0].target, "get:v2");
+        assert_eq!(interface.methods[0].in_args[0].target, "id");
+        assert_eq!(interface.methods[0].out_args.len(), 1);
+        assert_eq!(interface.broadcasts[0].out_args[0].target, "value");
+        assert_eq!(interface.types.len(), 4);
+        assert!(matches!(&interface.types[0], TypeDeployment::Struct{ fields, .. } if fields.len() == 2));
+        assert!(matches!(&interface.types[2], TypeDeployment::Map{ key, .. } if key.len() == 1));
+        assert_eq!(interface.span.line, 5);
+
+        let Definition::TypeCollection(types) = &model.definitions[1] else { panic!() };
+        assert_eq!(types.types[0].target(), "Id");
+
+        let Definition::Provider(provider) = &model.definitions[2] else { panic!() };
+        assert_eq!(provider.name, "Server");
+        assert_eq!(provider.instances[0].target, "org.example.Main");
+        assert_eq!(provider.instances[0].name.as_deref(), Some("First"));
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let error = parse_fdepl("define a.Spec for interface a.I {
+    method m { MethodID = }
+}", None, ParseMode::Strict).unwrap_err();
+        assert_eq!(error.span.line, 2);
+        assert!(error.to_string().contains("while parsing method `m`"), "{}", error);
+        assert!(error.to_string().contains("while parsing deployment `a.I`"), "{}", error);
+
+        let error = parse_fdepl("define a.Spec for interface a.I {\n    attribute speed { Id = }\n}", None,
+            ParseMode::Strict).unwrap_err();
+        assert_eq!(error.context.iter().map(|c| c.0.as_str()).collect::<Vec<_>>(),
+                   vec!["attribute `speed`", "deployment `a.I`"]);
+
+        let error = parse_fdepl("define a.Spec for provider as P {\n    instance a.I { Id = }\n}", None,
+            ParseMode::Strict).unwrap_err();
+        assert_eq!(error.context.iter().map(|c| c.0.as_str()).collect::<Vec<_>>(),
+                   vec!["instance `a.I`", "deployment `P`"]);
+
+        let error = parse_fdepl("specification a.Spec {\n    for attributes { }\n", None, ParseMode::Strict).unwrap_err();
+        assert_eq!(error.context.iter().map(|c| c.0.as_str()).collect::<Vec<_>>(), vec!["specification `a.Spec`"]);
+
+        let error = parse_fdepl("specification a.Spec { }\ninterface I { }", None, ParseMode::Strict).unwrap_err();
+        assert!(error.to_string().contains("expected `specification`, `define` or end of file"), "{}", error);
+    }
+}
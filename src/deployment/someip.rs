@@ -0,0 +1,475 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+//! Typed view of the SOME/IP deployment properties (`SomeIpServiceID`, `SomeIpMethodID`, ...)
+//! of interfaces, their attributes, methods, broadcasts and arguments and of provider instances.
+//! Properties not set in a deployment take the default of the specification, properties without
+//! default the SOME/IP default (e.g. UTF-8 strings, 4 byte length fields).
+
+use super::ast::*;
+use super::validator::{declarations, specifications, with_selector};
+use crate::ast;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Encoding of strings in SOME/IP messages, FDEPL enumerators `utf8`, `utf16le` and `utf16be`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum StringEncoding {
+    #[default]
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// Serialization of strings and arrays of an attribute or argument.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SomeIpEncoding {
+    /// `SomeIpStringEncoding`
+    pub string_encoding: StringEncoding,
+
+    /// `SomeIpStringLengthWidth`: size of the length field of strings in bytes
+    pub string_length_width: u8,
+
+    /// `SomeIpArrayLengthWidth`: size of the length field of arrays in bytes
+    pub array_length_width: u8,
+}
+
+impl Default for SomeIpEncoding {
+    fn default() -> Self {
+        SomeIpEncoding{ string_encoding: StringEncoding::Utf8, string_length_width: 4, array_length_width: 4 }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SomeIpArgumentDeployment {
+    pub name: String,
+    pub encoding: SomeIpEncoding,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SomeIpAttributeDeployment {
+    pub name: String,
+
+    /// `SomeIpGetterID`
+    pub getter_id: Option<u16>,
+
+    /// `SomeIpSetterID`
+    pub setter_id: Option<u16>,
+
+    /// `SomeIpNotifierID`
+    pub notifier_id: Option<u16>,
+
+    /// `SomeIpEventGroups` of the notifier
+    pub event_groups: Vec<u16>,
+
+    /// `SomeIpReliable`: TCP instead of UDP
+    pub reliable: bool,
+    pub encoding: SomeIpEncoding,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SomeIpMethodDeployment {
+    /// Method name, followed by `:selector` for overloaded methods
+    pub name: String,
+
+    /// `SomeIpMethodID`
+    pub method_id: u16,
+
+    /// `SomeIpReliable`: TCP instead of UDP
+    pub reliable: bool,
+    pub in_args: Vec<SomeIpArgumentDeployment>,
+    pub out_args: Vec<SomeIpArgumentDeployment>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SomeIpBroadcastDeployment {
+    /// Broadcast name, followed by `:selector` for overloaded broadcasts
+    pub name: String,
+
+    /// `SomeIpEventID`
+    pub event_id: u16,
+
+    /// `SomeIpEventGroups`
+    pub event_groups: Vec<u16>,
+
+    /// `SomeIpReliable`: TCP instead of UDP
+    pub reliable: bool,
+    pub out_args: Vec<SomeIpArgumentDeployment>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SomeIpInterfaceDeployment {
+    /// Fully qualified name of the interface
+    pub interface: String,
+
+    /// `SomeIpServiceID`
+    pub service_id: u16,
+    pub attributes: Vec<SomeIpAttributeDeployment>,
+    pub methods: Vec<SomeIpMethodDeployment>,
+    pub broadcasts: Vec<SomeIpBroadcastDeployment>,
+}
+
+impl SomeIpInterfaceDeployment {
+    pub fn attribute(&self, attribute: &ast::Attribute) -> Option<&SomeIpAttributeDeployment> {
+        self.attributes.iter().find(|a| a.name == attribute.name)
+    }
+
+    pub fn method(&self, method: &ast::Method) -> Option<&SomeIpMethodDeployment> {
+        let name = with_selector(&method.name, &method.selector);
+        self.methods.iter().find(|m| m.name == name)
+    }
+
+    pub fn broadcast(&self, broadcast: &ast::Broadcast) -> Option<&SomeIpBroadcastDeployment> {
+        let name = with_selector(&broadcast.name, &broadcast.selector);
+        self.broadcasts.iter().find(|b| b.name == name)
+    }
+}
+
+/// Instance of an interface offered by a provider.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SomeIpInstanceDeployment {
+    /// Name of the provider
+    pub provider: String,
+
+    /// Fully qualified name of the interface
+    pub interface: String,
+    pub name: Option<String>,
+
+    /// `SomeIpInstanceID`
+    pub instance_id: u16,
+}
+
+/// Error found while reading the SOME/IP properties of a deployment.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SomeIpError {
+    /// Property without default which is needed for the element, e.g. the `SomeIpMethodID` of a
+    /// method. The element is left out of the deployment view.
+    MissingProperty{ property: &'static str, element: String, file: PathBuf, span: ast::Span },
+
+    /// Value of the wrong type or out of range, e.g. a service ID above 0xFFFF.
+    InvalidValue{ property: &'static str, element: String, expected: &'static str, file: PathBuf, span: ast::Span },
+}
+
+impl fmt::Display for SomeIpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SomeIpError::MissingProperty{ property, element, file, span } =>
+                write!(f, "{}:{}:{}: error: property `{}` is not set for {}", file.display(), span.line,
+                       span.column, property, element),
+            SomeIpError::InvalidValue{ property, element, expected, file, span } =>
+                write!(f, "{}:{}:{}: error: invalid value of property `{}` of {}, expected {}", file.display(),
+                       span.line, span.column, property, element, expected),
+        }
+    }
+}
+
+/// SOME/IP deployment of all interfaces and provider instances of a set of deployment models.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct SomeIpDeployment {
+    interfaces: Vec<SomeIpInterfaceDeployment>,
+    instances: Vec<SomeIpInstanceDeployment>,
+}
+
+impl SomeIpDeployment {
+    /// Reads the SOME/IP properties of all interface and provider definitions. Elements missing
+    /// a mandatory ID are left out and reported together with invalid values.
+    pub fn build(deployments: &[(DeploymentModel, PathBuf)]) -> (SomeIpDeployment, Vec<SomeIpError>) {
+        let specifications = specifications(deployments);
+        let mut builder = Builder{ specifications: &specifications, errors: Vec::new() };
+        let mut result = SomeIpDeployment::default();
+        for (deployment, file) in deployments {
+            for definition in &deployment.definitions {
+                let specification = specifications.get(definition.specification()).copied();
+                match definition {
+                    Definition::Interface(d) =>
+                        result.interfaces.extend(builder.interface(specification, file, d)),
+                    Definition::Provider(d) =>
+                        result.instances.extend(d.instances.iter().filter_map(|i| builder.instance(specification, file, d, i))),
+                    Definition::TypeCollection(_) => {},
+                }
+            }
+        }
+        (result, builder.errors)
+    }
+
+    pub fn interfaces(&self) -> &[SomeIpInterfaceDeployment] {
+        &self.interfaces
+    }
+
+    /// Deployment of the interface with the fully qualified name `fqn`.
+    pub fn interface(&self, fqn: &str) -> Option<&SomeIpInterfaceDeployment> {
+        self.interfaces.iter().find(|i| i.interface == fqn)
+    }
+
+    /// Deployment of `interface` defined in a module of `package`.
+    pub fn interface_of(&self, package: &str, interface: &ast::Interface) -> Option<&SomeIpInterfaceDeployment> {
+        self.interface(&format!("{}.{}", package, interface.name))
+    }
+
+    pub fn instances(&self) -> &[SomeIpInstanceDeployment] {
+        &self.instances
+    }
+
+    /// Instances of the interface with the fully qualified name `fqn`.
+    pub fn instances_of<'a>(&'a self, fqn: &'a str) -> impl Iterator<Item = &'a SomeIpInstanceDeployment> {
+        self.instances.iter().filter(move |i| i.interface == fqn)
+    }
+}
+
+struct Builder<'s, 'a> {
+    specifications: &'s HashMap<&'a str, &'a Specification>,
+    errors: Vec<SomeIpError>,
+}
+
+/// Properties of one deployed element together with the declarations applying to it.
+struct Properties<'a> {
+    properties: &'a [Property],
+    declarations: Vec<&'a PropertyDecl>,
+    element: String,
+    file: &'a Path,
+    span: &'a ast::Span,
+}
+
+impl<'s, 'a> Builder<'s, 'a> {
+    fn properties(&self, specification: Option<&'a Specification>, hosts: &[&str], properties: &'a [Property],
+                  element: String, file: &'a Path, span: &'a ast::Span) -> Properties<'a> {
+        let declarations = specification
+            .map(|s| declarations(self.specifications, s, hosts))
+            .unwrap_or_default();
+        Properties{ properties, declarations, element, file, span }
+    }
+
+    fn interface(&mut self, specification: Option<&'a Specification>, file: &'a Path, d: &'a InterfaceDeployment)
+            -> Option<SomeIpInterfaceDeployment> {
+        let props = self.properties(specification, &["interfaces"], &d.properties,
+                                    format!("interface `{}`", d.target), file, &d.span);
+        let service_id = self.required_id(&props, "SomeIpServiceID");
+
+        let mut attributes = Vec::new();
+        for a in &d.attributes {
+            let props = self.properties(specification, &["attributes", "strings", "arrays"], &a.properties,
+                                        format!("attribute `{}.{}`", d.target, a.target), file, &a.span);
+            attributes.push(SomeIpAttributeDeployment{
+                name: a.target.clone(),
+                getter_id: self.id(&props, "SomeIpGetterID"),
+                setter_id: self.id(&props, "SomeIpSetterID"),
+                notifier_id: self.id(&props, "SomeIpNotifierID"),
+                event_groups: self.ids(&props, "SomeIpEventGroups"),
+                reliable: self.boolean(&props, "SomeIpReliable"),
+                encoding: self.encoding(&props),
+            });
+        }
+
+        let mut methods = Vec::new();
+        for m in &d.methods {
+            let props = self.properties(specification, &["methods"], &m.properties,
+                                        format!("method `{}.{}`", d.target, m.target), file, &m.span);
+            let method_id = self.required_id(&props, "SomeIpMethodID");
+            let reliable = self.boolean(&props, "SomeIpReliable");
+            let in_args = self.arguments(specification, file, &props.element, &m.in_args);
+            let out_args = self.arguments(specification, file, &props.element, &m.out_args);
+            if let Some(method_id) = method_id {
+                methods.push(SomeIpMethodDeployment{ name: m.target.clone(), method_id, reliable, in_args, out_args });
+            }
+        }
+
+        let mut broadcasts = Vec::new();
+        for b in &d.broadcasts {
+            let props = self.properties(specification, &["broadcasts"], &b.properties,
+                                        format!("broadcast `{}.{}`", d.target, b.target), file, &b.span);
+            let event_id = self.required_id(&props, "SomeIpEventID");
+            let event_groups = self.ids(&props, "SomeIpEventGroups");
+            let reliable = self.boolean(&props, "SomeIpReliable");
+            let out_args = self.arguments(specification, file, &props.element, &b.out_args);
+            if let Some(event_id) = event_id {
+                broadcasts.push(SomeIpBroadcastDeployment{ name: b.target.clone(), event_id, event_groups, reliable,
+                    out_args });
+            }
+        }
+
+        Some(SomeIpInterfaceDeployment{ interface: d.target.clone(), service_id: service_id?, attributes, methods,
+            broadcasts })
+    }
+
+    fn arguments(&mut self, specification: Option<&'a Specification>, file: &'a Path, owner: &str,
+                 args: &'a [ElementDeployment]) -> Vec<SomeIpArgumentDeployment> {
+        args.iter()
+            .map(|arg| {
+                let props = self.properties(specification, &["arguments", "strings", "arrays"], &arg.properties,
+                                            format!("argument `{}` of {}", arg.target, owner), file, &arg.span);
+                SomeIpArgumentDeployment{ name: arg.target.clone(), encoding: self.encoding(&props) }
+            })
+            .collect()
+    }
+
+    fn instance(&mut self, specification: Option<&'a Specification>, file: &'a Path, provider: &ProviderDeployment,
+                instance: &'a InstanceDeployment) -> Option<SomeIpInstanceDeployment> {
+        let props = self.properties(specification, &["instances"], &instance.properties,
+                                    format!("instance of `{}`", instance.target), file, &instance.span);
+        Some(SomeIpInstanceDeployment{ provider: provider.name.clone(), interface: instance.target.clone(),
+            name: instance.name.clone(), instance_id: self.required_id(&props, "SomeIpInstanceID")? })
+    }
+
+    fn encoding(&mut self, props: &Properties) -> SomeIpEncoding {
+        let default = SomeIpEncoding::default();
+        let string_encoding = match props.value("SomeIpStringEncoding") {
+            None => default.string_encoding,
+            Some(Value::Reference(e)) if e == "utf8" => StringEncoding::Utf8,
+            Some(Value::Reference(e)) if e == "utf16le" => StringEncoding::Utf16Le,
+            Some(Value::Reference(e)) if e == "utf16be" => StringEncoding::Utf16Be,
+            Some(_) => {
+                self.invalid(props, "SomeIpStringEncoding", "one of `utf8`, `utf16le`, `utf16be`");
+                default.string_encoding
+            },
+        };
+        SomeIpEncoding{
+            string_encoding,
+            string_length_width: self.width(props, "SomeIpStringLengthWidth").unwrap_or(default.string_length_width),
+            array_length_width: self.width(props, "SomeIpArrayLengthWidth").unwrap_or(default.array_length_width),
+        }
+    }
+
+    /// Optional ID, `None` if neither set nor defaulted.
+    fn id(&mut self, props: &Properties, property: &'static str) -> Option<u16> {
+        match props.value(property) {
+            None => None,
+            Some(Value::Integer(id)) if u16::try_from(*id).is_ok() => Some(*id as u16),
+            Some(_) => {
+                self.invalid(props, property, "Integer between 0 and 0xFFFF");
+                None
+            },
+        }
+    }
+
+    /// ID the element cannot do without, a missing ID is reported.
+    fn required_id(&mut self, props: &Properties, property: &'static str) -> Option<u16> {
+        if props.value(property).is_none() {
+            self.errors.push(SomeIpError::MissingProperty{ property, element: props.element.clone(),
+                file: props.file.to_path_buf(), span: props.span.clone() });
+        }
+        self.id(props, property)
+    }
+
+    fn ids(&mut self, props: &Properties, property: &'static str) -> Vec<u16> {
+        match props.value(property) {
+            None => Vec::new(),
+            Some(Value::Array(values)) if values.iter().all(|v| matches!(v, Value::Integer(id) if u16::try_from(*id).is_ok())) =>
+                values.iter().filter_map(|v| match v { Value::Integer(id) => Some(*id as u16), _ => None }).collect(),
+            Some(_) => {
+                self.invalid(props, property, "array of Integers between 0 and 0xFFFF");
+                Vec::new()
+            },
+        }
+    }
+
+    /// Boolean property, `false` if neither set nor defaulted.
+    fn boolean(&mut self, props: &Properties, property: &'static str) -> bool {
+        match props.value(property) {
+            None => false,
+            Some(Value::Boolean(value)) => *value,
+            Some(_) => {
+                self.invalid(props, property, "Boolean");
+                false
+            },
+        }
+    }
+
+    /// Width of a length field in bytes: 0 (no length field), 1, 2 or 4.
+    fn width(&mut self, props: &Properties, property: &'static str) -> Option<u8> {
+        match props.value(property) {
+            None => None,
+            Some(Value::Integer(width @ (0 | 1 | 2 | 4))) => Some(*width as u8),
+            Some(_) => {
+                self.invalid(props, property, "0, 1, 2 or 4");
+                None
+            },
+        }
+    }
+
+    fn invalid(&mut self, props: &Properties, property: &'static str, expected: &'static str) {
+        let span = props.properties.iter().find(|p| p.name == property).map_or(props.span, |p| &p.span);
+        self.errors.push(SomeIpError::InvalidValue{ property, element: props.element.clone(), expected,
+            file: props.file.to_path_buf(), span: span.clone() });
+    }
+}
+
+impl<'a> Properties<'a> {
+    /// Value set for the element or the default of the specification.
+    fn value(&self, name: &str) -> Option<&'a Value> {
+        self.properties.iter().find(|p| p.name == name).map(|p| &p.value)
+            .or_else(|| self.declarations.iter().find(|d| d.name == name).and_then(|d| d.default.as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::deployment::parse_fdepl;
+    use crate::parser::ParseMode;
+
+    fn build(text: &str) -> (SomeIpDeployment, Vec<String>) {
+        let deployments = vec![(parse_fdepl(text, None, ParseMode::Strict).unwrap().1, PathBuf::from("s.fdepl"))];
+        let (deployment, errors) = SomeIpDeployment::build(&deployments);
+        (deployment, errors.iter().map(|e| e.to_string()).collect())
+    }
+
+    #[test]
+    fn test_defaults() {
+        let (deployment, errors) = build("specification s.SomeIp {
+    for methods { SomeIpMethodID : Integer ; SomeIpReliable : Boolean (default: true); }
+    for arguments { SomeIpStringEncoding : {utf8, utf16le, utf16be} (default: utf16be); }
+    for broadcasts { SomeIpEventGroups : Integer[] (default: {1, 2}); }
+}
+define s.SomeIp for interface a.I {
+    SomeIpServiceID = 0x1234
+    attribute x { SomeIpNotifierID = 0x8001 SomeIpArrayLengthWidth = 2 }
+    method m:v2 {
+        SomeIpMethodID = 7
+        in { name { } text { SomeIpStringEncoding = utf8 } }
+    }
+    broadcast b { SomeIpEventID = 0x8002 SomeIpReliable = false }
+}
+define s.SomeIp for provider as P {
+    instance a.I as Main { SomeIpInstanceID = 3 }
+}");
+        assert_eq!(errors, Vec::<String>::new());
+        let interface = deployment.interface("a.I").unwrap();
+        assert_eq!(interface.service_id, 0x1234);
+        assert_eq!(interface.attributes[0].notifier_id, Some(0x8001));
+        assert_eq!(interface.attributes[0].getter_id, None);
+        assert_eq!(interface.attributes[0].encoding, SomeIpEncoding{ array_length_width: 2, ..Default::default() });
+        let method = &interface.methods[0];
+        assert_eq!((method.name.as_str(), method.method_id, method.reliable), ("m:v2", 7, true));
+        assert_eq!(method.in_args[0].encoding.string_encoding, StringEncoding::Utf16Be);
+        assert_eq!(method.in_args[1].encoding.string_encoding, StringEncoding::Utf8);
+        let broadcast = &interface.broadcasts[0];
+        assert_eq!((broadcast.event_id, broadcast.reliable), (0x8002, false));
+        assert_eq!(broadcast.event_groups, vec![1, 2]);
+        let instance = deployment.instances_of("a.I").next().unwrap();
+        assert_eq!((instance.provider.as_str(), instance.name.as_deref(), instance.instance_id), ("P", Some("Main"), 3));
+    }
+
+    #[test]
+    fn test_errors() {
+        let (deployment, errors) = build("define s.SomeIp for interface a.I {
+    SomeIpServiceID = 0x10000
+}
+define s.SomeIp for interface a.J {
+    SomeIpServiceID = 1
+    method m { SomeIpReliable = 1 }
+    attribute x { SomeIpStringEncoding = latin1 }
+}");
+        assert_eq!(errors, vec![
+            "s.fdepl:2:5: error: invalid value of property `SomeIpServiceID` of interface `a.I`, expected Integer between 0 and 0xFFFF",
+            "s.fdepl:7:19: error: invalid value of property `SomeIpStringEncoding` of attribute `a.J.x`, expected one of `utf8`, `utf16le`, `utf16be`",
+            "s.fdepl:6:5: error: property `SomeIpMethodID` is not set for method `a.J.m`",
+            "s.fdepl:6:16: error: invalid value of property `SomeIpReliable` of method `a.J.m`, expected Boolean",
+        ]);
+        assert!(deployment.interface("a.I").is_none());
+        assert!(deployment.interface("a.J").unwrap().methods.is_empty());
+    }
+}
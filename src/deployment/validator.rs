@@ -0,0 +1,524 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+//! Checks of deployment definitions against their specification and the FIDL model: deployed
+//! elements must exist, properties must be declared for the element kind, values must match
+//! the declared type and mandatory properties must be set.
+
+use super::ast::*;
+use crate::ast::Span;
+use crate::model::{Model, TypeId, TypeKind, TypeRef};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Error found while validating deployments. `fidl` is the location of the deployed FIDL element
+/// (or of the FIDL definition searched for an unknown element), if there is one.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DeploymentError {
+    /// `define` using a specification which is not defined in any deployment file.
+    UnknownSpecification{ name: String, file: PathBuf, span: Span },
+
+    /// Deployment of a FIDL element which does not exist, e.g. a method missing in the interface.
+    /// `scope` describes where the element was searched, e.g. "interface `a.I`".
+    UnknownElement{ kind: &'static str, name: String, scope: String, file: PathBuf, span: Span,
+        fidl: Option<(PathBuf, Span)> },
+
+    /// Property not declared by the specification for the kind of the deployed element.
+    UnknownProperty{ property: String, element: String, specification: String, file: PathBuf, span: Span,
+        fidl: Option<(PathBuf, Span)> },
+
+    /// Property value not matching the declared type.
+    InvalidValue{ property: String, element: String, expected: String, file: PathBuf, span: Span,
+        fidl: Option<(PathBuf, Span)> },
+
+    /// Mandatory property which is not set for the element. `span` is the deployment of the
+    /// element or, if the element is not deployed at all, of the enclosing definition.
+    MissingProperty{ property: String, element: String, file: PathBuf, span: Span,
+        fidl: Option<(PathBuf, Span)> },
+}
+
+impl fmt::Display for DeploymentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (file, span) = match self {
+            DeploymentError::UnknownSpecification{ file, span, .. } | DeploymentError::UnknownElement{ file, span, .. } |
+            DeploymentError::UnknownProperty{ file, span, .. } | DeploymentError::InvalidValue{ file, span, .. } |
+            DeploymentError::MissingProperty{ file, span, .. } => (file, span),
+        };
+        write!(f, "{}:{}:{}: error: ", file.display(), span.line, span.column)?;
+        let (described, fidl) = match self {
+            DeploymentError::UnknownSpecification{ name, .. } =>
+                return write!(f, "unknown deployment specification `{}`", name),
+            DeploymentError::UnknownElement{ kind, name, scope, fidl, .. } => {
+                write!(f, "{} `{}` not found in {}", kind, name, scope)?;
+                (scope, fidl)
+            },
+            DeploymentError::UnknownProperty{ property, element, specification, fidl, .. } => {
+                write!(f, "property `{}` is not declared for {} by specification `{}`", property, element,
+                       specification)?;
+                (element, fidl)
+            },
+            DeploymentError::InvalidValue{ property, element, expected, fidl, .. } => {
+                write!(f, "invalid value of property `{}` of {}, expected {}", property, element, expected)?;
+                (element, fidl)
+            },
+            DeploymentError::MissingProperty{ property, element, fidl, .. } => {
+                write!(f, "mandatory property `{}` is not set for {}", property, element)?;
+                (element, fidl)
+            },
+        };
+        if let Some((file, span)) = fidl {
+            write!(f, "\n  {} defined at {}:{}:{}", described, file.display(), span.line, span.column)?;
+        }
+        Ok(())
+    }
+}
+
+/// Validates the definitions of all deployment models against their specifications and the
+/// FIDL elements of `model`. Specifications may be defined in any of the deployment models.
+pub fn validate(deployments: &[(DeploymentModel, PathBuf)], model: &Model) -> Vec<DeploymentError> {
+    let mut validator = Validator{ model, specifications: specifications(deployments), errors: Vec::new() };
+    for (deployment, file) in deployments {
+        for definition in &deployment.definitions {
+            let Some(specification) = validator.specifications.get(definition.specification()).copied() else {
+                validator.errors.push(DeploymentError::UnknownSpecification{
+                    name: definition.specification().to_string(), file: file.clone(), span: definition.span().clone() });
+                continue;
+            };
+            let context = Context{ specification, file };
+            match definition {
+                Definition::Interface(d) => validator.interface(context, d),
+                Definition::TypeCollection(d) => validator.type_collection(context, d),
+                Definition::Provider(d) => validator.provider(context, d),
+            }
+        }
+    }
+    validator.errors
+}
+
+/// Specification and file of the definition being validated.
+#[derive(Clone, Copy)]
+struct Context<'a> {
+    specification: &'a Specification,
+    file: &'a Path,
+}
+
+/// FIDL element a deployment applies to.
+struct Target {
+    /// Description for messages, e.g. "method `a.I.get`"
+    name: String,
+
+    /// Property hosts of the specification applying to the element, e.g. `methods`
+    hosts: Vec<&'static str>,
+    fidl: Option<(PathBuf, Span)>,
+}
+
+struct Validator<'a> {
+    model: &'a Model,
+    specifications: HashMap<&'a str, &'a Specification>,
+    errors: Vec<DeploymentError>,
+}
+
+impl<'a> Validator<'a> {
+    fn interface(&mut self, context: Context, deployment: &InterfaceDeployment) {
+        let Some(id) = self.model.find_interface(&deployment.target) else {
+            self.unknown(context, "interface", &deployment.target, "the FIDL model", &deployment.span, None);
+            return;
+        };
+        let model = self.model;
+        let interface = &model[id];
+        let scope = format!("interface `{}`", interface.fqn);
+        let location = Some((interface.file.clone(), interface.span.clone()));
+        let at = |span: &Span| Some((interface.file.clone(), span.clone()));
+        self.properties(context, &deployment.properties, &deployment.span,
+                        &Target{ name: scope.clone(), hosts: vec!["interfaces"], fidl: location.clone() });
+
+        for attribute in &interface.attributes {
+            let target = Target{ name: format!("attribute `{}.{}`", interface.fqn, attribute.name),
+                hosts: self.hosts("attributes", &attribute.type_ref), fidl: at(&attribute.span) };
+            let deployed = deployment.attributes.iter().find(|a| a.target == attribute.name);
+            self.deployed(context, deployed.map(|a| (&a.properties[..], &a.span)), &deployment.span, &target);
+        }
+        for a in deployment.attributes.iter().filter(|a| !interface.attributes.iter().any(|i| i.name == a.target)) {
+            self.unknown(context, "attribute", &a.target, &scope, &a.span, location.clone());
+        }
+
+        for method in &interface.methods {
+            let name = with_selector(&method.name, &method.selector);
+            let target = Target{ name: format!("method `{}.{}`", interface.fqn, name), hosts: vec!["methods"],
+                fidl: at(&method.span) };
+            let deployed = deployment.methods.iter().find(|m| m.target == name);
+            self.deployed(context, deployed.map(|m| (&m.properties[..], &m.span)), &deployment.span, &target);
+            let span = deployed.map_or(&deployment.span, |m| &m.span);
+            let (in_args, out_args) = deployed.map_or((&[][..], &[][..]), |m| (&m.in_args[..], &m.out_args[..]));
+            self.arguments(context, &target.name, &interface.file, &method.in_args, in_args, span);
+            self.arguments(context, &target.name, &interface.file, &method.out_args, out_args, span);
+        }
+        for m in deployment.methods.iter()
+                .filter(|m| !interface.methods.iter().any(|i| with_selector(&i.name, &i.selector) == m.target)) {
+            self.unknown(context, "method", &m.target, &scope, &m.span, location.clone());
+        }
+
+        for broadcast in &interface.broadcasts {
+            let name = with_selector(&broadcast.name, &broadcast.selector);
+            let target = Target{ name: format!("broadcast `{}.{}`", interface.fqn, name), hosts: vec!["broadcasts"],
+                fidl: at(&broadcast.span) };
+            let deployed = deployment.broadcasts.iter().find(|b| b.target == name);
+            self.deployed(context, deployed.map(|b| (&b.properties[..], &b.span)), &deployment.span, &target);
+            let span = deployed.map_or(&deployment.span, |b| &b.span);
+            let out_args = deployed.map_or(&[][..], |b| &b.out_args[..]);
+            self.arguments(context, &target.name, &interface.file, &broadcast.out_args, out_args, span);
+        }
+        for b in deployment.broadcasts.iter()
+                .filter(|b| !interface.broadcasts.iter().any(|i| with_selector(&i.name, &i.selector) == b.target)) {
+            self.unknown(context, "broadcast", &b.target, &scope, &b.span, location.clone());
+        }
+
+        self.types(context, &scope, location, &interface.types, &deployment.types, &deployment.span);
+    }
+
+    fn type_collection(&mut self, context: Context, deployment: &TypeCollectionDeployment) {
+        let model = self.model;
+        let Some(type_collection) = model.type_collections().iter().find(|tc| tc.fqn == deployment.target) else {
+            self.unknown(context, "type collection", &deployment.target, "the FIDL model", &deployment.span, None);
+            return;
+        };
+        let scope = format!("type collection `{}`", type_collection.fqn);
+        let location = Some((type_collection.file.clone(), type_collection.span.clone()));
+        self.properties(context, &deployment.properties, &deployment.span,
+                        &Target{ name: scope.clone(), hosts: vec!["type_collections"], fidl: location.clone() });
+        self.types(context, &scope, location, &type_collection.types, &deployment.types, &deployment.span);
+    }
+
+    fn provider(&mut self, context: Context, deployment: &ProviderDeployment) {
+        let name = format!("provider `{}`", deployment.name);
+        self.properties(context, &deployment.properties, &deployment.span,
+                        &Target{ name: name.clone(), hosts: vec!["providers"], fidl: None });
+        for instance in &deployment.instances {
+            let Some(id) = self.model.find_interface(&instance.target) else {
+                self.unknown(context, "interface", &instance.target, &name, &instance.span, None);
+                continue;
+            };
+            let interface = &self.model[id];
+            let target = Target{
+                name: format!("instance `{}` of `{}`", instance.name.as_deref().unwrap_or(&interface.name), interface.fqn),
+                hosts: vec!["instances"], fidl: Some((interface.file.clone(), interface.span.clone())) };
+            self.properties(context, &instance.properties, &instance.span, &target);
+        }
+    }
+
+    /// Validates the deployment of the arguments of a method or broadcast.
+    fn arguments(&mut self, context: Context, owner: &str, file: &Path, args: &[crate::model::Argument],
+                 deployments: &[ElementDeployment], span: &Span) {
+        for arg in args {
+            let target = Target{ name: format!("argument `{}` of {}", arg.name, owner),
+                hosts: self.hosts("arguments", &arg.type_ref), fidl: Some((file.to_path_buf(), arg.span.clone())) };
+            let deployed = deployments.iter().find(|d| d.target == arg.name);
+            self.deployed(context, deployed.map(|d| (&d.properties[..], &d.span)), span, &target);
+        }
+        for d in deployments.iter().filter(|d| !args.iter().any(|a| a.name == d.target)) {
+            self.unknown(context, "argument", &d.target, owner, &d.span, None);
+        }
+    }
+
+    /// Validates the deployment of the types of an interface or type collection.
+    fn types(&mut self, context: Context, scope: &str, location: Option<(PathBuf, Span)>, types: &[TypeId],
+             deployments: &[TypeDeployment], span: &Span) {
+        let model = self.model;
+        for id in types {
+            let tp = &model[*id];
+            let kind = type_kind(&tp.kind);
+            let deployed = deployments.iter().find(|d| d.target() == tp.name && deployment_kind(d) == kind);
+            let target = Target{ name: format!("{} `{}`", kind, tp.fqn), hosts: vec![host(kind)],
+                fidl: Some((tp.file.clone(), tp.span.clone())) };
+            self.deployed(context, deployed.map(|d| (d.properties(), d.span())), span, &target);
+            let span = deployed.map_or(span, |d| d.span());
+            let at = |span: &Span| Some((tp.file.clone(), span.clone()));
+
+            match (&tp.kind, deployed) {
+                (TypeKind::Struct{ fields, .. } | TypeKind::Union{ fields, .. }, deployed) => {
+                    let host = if kind == "struct" { "struct_fields" } else { "union_fields" };
+                    let deployments = match deployed {
+                        Some(TypeDeployment::Struct{ fields, .. } | TypeDeployment::Union{ fields, .. }) => &fields[..],
+                        _ => &[],
+                    };
+                    for field in fields {
+                        let field_target = Target{ name: format!("field `{}` of {}", field.name, target.name),
+                            hosts: self.hosts(host, &field.type_ref), fidl: at(&field.span) };
+                        let deployed = deployments.iter().find(|d| d.target == field.name);
+                        self.deployed(context, deployed.map(|d| (&d.properties[..], &d.span)), span, &field_target);
+                    }
+                    for d in deployments.iter().filter(|d| !fields.iter().any(|f| f.name == d.target)) {
+                        self.unknown(context, "field", &d.target, &target.name, &d.span, target.fidl.clone());
+                    }
+                },
+                (TypeKind::Enumeration{ enumerators, .. }, deployed) => {
+                    let deployments = match deployed {
+                        Some(TypeDeployment::Enumeration{ enumerators, .. }) => &enumerators[..],
+                        _ => &[],
+                    };
+                    for enumerator in enumerators {
+                        let enumerator_target = Target{
+                            name: format!("enumerator `{}` of {}", enumerator.name, target.name),
+                            hosts: vec!["enumerators"], fidl: at(&enumerator.span) };
+                        let deployed = deployments.iter().find(|d| d.target == enumerator.name);
+                        self.deployed(context, deployed.map(|d| (&d.properties[..], &d.span)), span,
+                                      &enumerator_target);
+                    }
+                    for d in deployments.iter().filter(|d| !enumerators.iter().any(|e| e.name == d.target)) {
+                        self.unknown(context, "enumerator", &d.target, &target.name, &d.span, target.fidl.clone());
+                    }
+                },
+                (TypeKind::Map{ key_type, value_type }, deployed) => {
+                    let (key, value) = match deployed {
+                        Some(TypeDeployment::Map{ key, value, .. }) => (&key[..], &value[..]),
+                        _ => (&[][..], &[][..]),
+                    };
+                    self.properties(context, key, span, &Target{ name: format!("key of {}", target.name),
+                        hosts: self.hosts("map_keys", key_type), fidl: target.fidl.clone() });
+                    self.properties(context, value, span, &Target{ name: format!("value of {}", target.name),
+                        hosts: self.hosts("map_values", value_type), fidl: target.fidl.clone() });
+                },
+                _ => {},
+            }
+        }
+        for d in deployments {
+            if !types.iter().any(|id| model[*id].name == d.target() && type_kind(&model[*id].kind) == deployment_kind(d)) {
+                self.unknown(context, deployment_kind(d), d.target(), scope, d.span(), location.clone());
+            }
+        }
+    }
+
+    /// Validates the properties of an element which may not be deployed at all, mandatory
+    /// properties of such an element are reported at `span`.
+    fn deployed(&mut self, context: Context, deployed: Option<(&[Property], &Span)>, span: &Span, target: &Target) {
+        let (properties, span) = deployed.unwrap_or((&[], span));
+        self.properties(context, properties, span, target);
+    }
+
+    fn properties(&mut self, context: Context, properties: &[Property], span: &Span, target: &Target) {
+        let declarations = declarations(&self.specifications, context.specification, &target.hosts);
+        for property in properties {
+            match declarations.iter().find(|d| d.name == property.name) {
+                None => self.errors.push(DeploymentError::UnknownProperty{
+                    property: property.name.clone(), element: target.name.clone(),
+                    specification: context.specification.name.clone(), file: context.file.to_path_buf(),
+                    span: property.span.clone(), fidl: target.fidl.clone() }),
+                Some(declaration) if !self.matches(declaration, &property.value) =>
+                    self.errors.push(DeploymentError::InvalidValue{
+                        property: property.name.clone(), element: target.name.clone(),
+                        expected: expected(declaration), file: context.file.to_path_buf(),
+                        span: property.span.clone(), fidl: target.fidl.clone() }),
+                Some(_) => {},
+            }
+        }
+        let mut checked: Vec<&str> = Vec::new();
+        for declaration in &declarations {
+            if checked.contains(&declaration.name.as_str()) {
+                continue;
+            }
+            checked.push(&declaration.name);
+            if declaration.is_mandatory() && !properties.iter().any(|p| p.name == declaration.name) {
+                self.errors.push(DeploymentError::MissingProperty{
+                    property: declaration.name.clone(), element: target.name.clone(),
+                    file: context.file.to_path_buf(), span: span.clone(), fidl: target.fidl.clone() });
+            }
+        }
+    }
+
+    fn unknown(&mut self, context: Context, kind: &'static str, name: &str, scope: &str, span: &Span,
+               fidl: Option<(PathBuf, Span)>) {
+        self.errors.push(DeploymentError::UnknownElement{ kind, name: name.to_string(), scope: scope.to_string(),
+            file: context.file.to_path_buf(), span: span.clone(), fidl });
+    }
+
+    /// `host` followed by the hosts of the type of a typed element, e.g. `strings` for a string
+    /// argument.
+    fn hosts(&self, host: &'static str, type_ref: &TypeRef) -> Vec<&'static str> {
+        let mut hosts = vec![host];
+        hosts.extend_from_slice(match self.model.resolve_typedefs(type_ref) {
+            TypeRef::String => &["strings"][..],
+            TypeRef::Int8 | TypeRef::UInt8 | TypeRef::Int16 | TypeRef::UInt16 | TypeRef::Int32 | TypeRef::UInt32 |
+            TypeRef::Int64 | TypeRef::UInt64 | TypeRef::IntegerInterval(..) => &["integers", "numbers"],
+            TypeRef::Float | TypeRef::Double => &["floats", "numbers"],
+            TypeRef::Boolean => &["booleans"],
+            TypeRef::ByteBuffer => &["byte_buffers"],
+            _ => &[],
+        });
+        hosts
+    }
+
+    fn matches(&self, declaration: &PropertyDecl, value: &Value) -> bool {
+        match value {
+            Value::Array(values) => declaration.array && values.iter().all(|v| self.matches_type(&declaration.type_ref, v)),
+            value => !declaration.array && self.matches_type(&declaration.type_ref, value),
+        }
+    }
+
+    fn matches_type(&self, type_ref: &PropertyType, value: &Value) -> bool {
+        match (type_ref, value) {
+            (PropertyType::Integer, Value::Integer(_)) | (PropertyType::String, Value::String(_)) |
+            (PropertyType::Boolean, Value::Boolean(_)) | (PropertyType::Instance, Value::Reference(_)) => true,
+            (PropertyType::Interface, Value::Reference(name)) => self.model.find_interface(name).is_some(),
+            (PropertyType::Enumeration(enumerators), Value::Reference(name)) => enumerators.contains(name),
+            _ => false,
+        }
+    }
+}
+
+/// Specifications of the deployment models by name, the first definition of a name wins.
+pub(crate) fn specifications(deployments: &[(DeploymentModel, PathBuf)]) -> HashMap<&str, &Specification> {
+    let mut specifications = HashMap::new();
+    for (deployment, _) in deployments {
+        for specification in &deployment.specifications {
+            specifications.entry(specification.name.as_str()).or_insert(specification);
+        }
+    }
+    specifications
+}
+
+/// Property declarations of `specification` and its bases for the given hosts, declarations of
+/// the derived specification first.
+pub(crate) fn declarations<'a>(specifications: &HashMap<&str, &'a Specification>, specification: &'a Specification,
+                               hosts: &[&str]) -> Vec<&'a PropertyDecl> {
+    let mut declarations = Vec::new();
+    let mut seen: Vec<&str> = Vec::new();
+    let mut current = Some(specification);
+    while let Some(spec) = current.filter(|s| !seen.contains(&s.name.as_str())) {
+        seen.push(&spec.name);
+        declarations.extend(spec.declarations.iter()
+            .filter(|d| hosts.contains(&d.host.as_str()))
+            .flat_map(|d| &d.properties));
+        current = spec.extends.as_deref().and_then(|base| specifications.get(base).copied());
+    }
+    declarations
+}
+
+/// Name of a method or broadcast as used in deployments, `name:selector` for overloaded ones.
+pub(crate) fn with_selector(name: &str, selector: &Option<String>) -> String {
+    match selector {
+        Some(selector) => format!("{}:{}", name, selector),
+        None => name.to_string(),
+    }
+}
+
+fn type_kind(kind: &TypeKind) -> &'static str {
+    match kind {
+        TypeKind::TypeDef{ .. } => "typedef",
+        TypeKind::Array{ .. } => "array",
+        TypeKind::Struct{ .. } => "struct",
+        TypeKind::Union{ .. } => "union",
+        TypeKind::Map{ .. } => "map",
+        TypeKind::Enumeration{ .. } => "enumeration",
+    }
+}
+
+fn deployment_kind(deployment: &TypeDeployment) -> &'static str {
+    match deployment {
+        TypeDeployment::TypeDef(_) => "typedef",
+        TypeDeployment::Array(_) => "array",
+        TypeDeployment::Struct{ .. } => "struct",
+        TypeDeployment::Union{ .. } => "union",
+        TypeDeployment::Map{ .. } => "map",
+        TypeDeployment::Enumeration{ .. } => "enumeration",
+    }
+}
+
+/// Property host of the types of a kind.
+fn host(kind: &str) -> &'static str {
+    match kind {
+        "typedef" => "typedefs",
+        "array" => "arrays",
+        "struct" => "structs",
+        "union" => "unions",
+        "map" => "maps",
+        _ => "enumerations",
+    }
+}
+
+/// Description of the values allowed for a property.
+fn expected(declaration: &PropertyDecl) -> String {
+    let single = match &declaration.type_ref {
+        PropertyType::Integer => "Integer".to_string(),
+        PropertyType::String => "String".to_string(),
+        PropertyType::Boolean => "Boolean".to_string(),
+        PropertyType::Interface => "interface name".to_string(),
+        PropertyType::Instance => "instance name".to_string(),
+        PropertyType::Enumeration(enumerators) => {
+            let names: Vec<String> = enumerators.iter().map(|e| format!("`{}`", e)).collect();
+            format!("one of {}", names.join(", "))
+        },
+    };
+    if declaration.array { format!("array of {}", single) } else { single }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::deployment::parse_fdepl;
+    use crate::parser::{self, ParseMode};
+
+    const SPEC: &str = "specification s.Spec {
+    for interfaces { ServiceID : Integer ; }
+    for methods { MethodID : Integer ; Reliable : Boolean (default: false); }
+    for arguments { Encoding : {utf8, utf16} (optional); }
+    for strings { MaxLength : Integer (optional); }
+    for struct_fields { Width : Integer[] (optional); }
+}";
+
+    fn check(fdepl: &str) -> Vec<String> {
+        let fidl = "package a
+interface I {
+    method get {
+        in { String name  UInt8 id }
+    }
+    struct S { UInt8 x }
+}";
+        let modules = vec![(parser::parse_module(fidl).unwrap().1, PathBuf::from("I.fidl"))];
+        let (model, _) = Model::build(&modules);
+        let deployments = vec![
+            (parse_fdepl(SPEC, None, ParseMode::Strict).unwrap().1, PathBuf::from("Spec.fdepl")),
+            (parse_fdepl(fdepl, None, ParseMode::Strict).unwrap().1, PathBuf::from("I.fdepl")),
+        ];
+        validate(&deployments, &model).iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        assert_eq!(check("define s.Spec for interface a.I {
+    ServiceID = 1
+    method get {
+        MethodID = 2
+        in { name { Encoding = utf16 MaxLength = 20 } }
+    }
+    struct S { x { Width = { 1, 2 } } }
+}"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(check("define s.Spec for interface a.I {
+    ServiceID = \"one\"
+    method get {
+        Reliable = true
+        in { id { MaxLength = 3 } name { Encoding = latin1 } }
+    }
+    method set { }
+    struct S { y { } }
+}
+define s.Other for interface a.I { }
+define s.Spec for interface a.J { ServiceID = 1 }"), vec![
+            "I.fdepl:2:5: error: invalid value of property `ServiceID` of interface `a.I`, expected Integer\n  interface `a.I` defined at I.fidl:2:1",
+            "I.fdepl:3:5: error: mandatory property `MethodID` is not set for method `a.I.get`\n  method `a.I.get` defined at I.fidl:3:5",
+            "I.fdepl:5:42: error: invalid value of property `Encoding` of argument `name` of method `a.I.get`, expected one of `utf8`, `utf16`\n  argument `name` of method `a.I.get` defined at I.fidl:4:14",
+            "I.fdepl:5:19: error: property `MaxLength` is not declared for argument `id` of method `a.I.get` by specification `s.Spec`\n  argument `id` of method `a.I.get` defined at I.fidl:4:27",
+            "I.fdepl:7:5: error: method `set` not found in interface `a.I`\n  interface `a.I` defined at I.fidl:2:1",
+            "I.fdepl:8:16: error: field `y` not found in struct `a.I.S`\n  struct `a.I.S` defined at I.fidl:6:5",
+            "I.fdepl:10:1: error: unknown deployment specification `s.Other`",
+            "I.fdepl:11:1: error: interface `a.J` not found in the FIDL model",
+        ]);
+    }
+}
@@ -0,0 +1,258 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+use super::ast;
+use super::parser::{self, Input};
+use nom::error::{ErrorKind, VerboseError, VerboseErrorKind};
+use std::fmt;
+
+/// Syntax error found while parsing a FIDL file.
+/// The `Display` implementation renders the diagnostic in the style of rustc, e.g.
+/// ```text
+/// error: expected `}` to close interface `VehicleStatus`, found `methd`
+///   --> vehicle.fidl:12:5
+///    |
+/// 12 |     methd getSpeed { }
+///    |     ^^^^^
+///    = note: while parsing interface `VehicleStatus` starting at 9:1
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Diagnostic {
+    /// Human readable description of the error
+    pub message: String,
+
+    /// What the parser expected at the error location, e.g. "`}`" or "identifier"
+    pub expected: String,
+
+    /// Text found at the error location, `None` at the end of the input
+    pub found: Option<String>,
+
+    /// Location of the error
+    pub span: ast::Span,
+
+    /// Source line containing the error
+    pub source_line: String,
+
+    /// Definitions enclosing the error location, innermost first, e.g. "method `connect`"
+    pub context: Vec<(String, ast::Span)>,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic from the error of a failed FIDL parser.
+    pub fn from_error(error: &VerboseError<Input>) -> Diagnostic {
+        Diagnostic::from_error_with(error, describe_context)
+    }
+
+    /// Creates a diagnostic from the error of a failed parser, `describe` names the parser
+    /// contexts, e.g. "interface `Foo`" for the context "interface" at `interface Foo {`.
+    pub fn from_error_with(error: &VerboseError<Input>, describe: fn(Input, &str) -> String) -> Diagnostic {
+        let context: Vec<(String, ast::Span)> = error.errors.iter()
+            .filter_map(|(input, kind)| match kind {
+                VerboseErrorKind::Context(ctx) => Some((describe(*input, ctx), location(input, 0))),
+                _ => None,
+            })
+            .collect();
+
+        let (input, expected) = match error.errors.first() {
+            Some((input, VerboseErrorKind::Char(c))) => (Some(input), format!("`{}`", c)),
+            Some((input, VerboseErrorKind::Nom(kind))) => (Some(input), describe_kind(kind).to_string()),
+            Some((input, VerboseErrorKind::Context(ctx))) => (Some(input), describe(*input, ctx)),
+            None => (None, "valid FIDL".to_string()),
+        };
+        let closing = matches!(error.errors.first(), Some((_, VerboseErrorKind::Char('}' | ')' | ']'))));
+        Diagnostic::build(input, expected, context, closing)
+    }
+
+    /// Creates a diagnostic telling that `expected` was expected at the beginning of `input`.
+    pub fn expected(input: &Input, expected: &str) -> Diagnostic {
+        Diagnostic::build(Some(input), expected.to_string(), Vec::new(), false)
+    }
+
+    fn build(input: Option<&Input>, expected: String, context: Vec<(String, ast::Span)>, closing: bool)
+            -> Diagnostic {
+        let found = input.and_then(|i| found_token(i.fragment()));
+
+        let mut message = format!("expected {}", expected);
+        if let Some((ctx, _)) = context.first() {
+            message += if closing { " to close " } else { " in " };
+            message += ctx;
+        }
+        match &found {
+            Some(token) => message += &format!(", found `{}`", token),
+            None => message += ", found end of file",
+        }
+
+        let len = found.as_ref().map_or(0, |t| t.len());
+        let span = input.map_or_else(ast::Span::default, |i| location(i, len));
+        let source_line = input.map_or_else(String::new, |i| {
+            String::from_utf8_lossy(i.get_line_beginning()).trim_end_matches('\r').to_string()
+        });
+        Diagnostic{ message, expected, found, span, source_line, context }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line = self.span.line.to_string();
+        let gutter = " ".repeat(line.len());
+        writeln!(f, "error: {}", self.message)?;
+        match &self.span.file {
+            Some(file) => writeln!(f, "{}--> {}:{}:{}", gutter, file.display(), line, self.span.column)?,
+            None => writeln!(f, "{}--> {}:{}", gutter, line, self.span.column)?,
+        }
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", line, self.source_line)?;
+        // keep tabs so the caret lines up with the source line
+        let indent: String = self.source_line.chars().take(self.span.column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let carets = self.found.as_ref().map_or(1, |t| t.chars().count().max(1));
+        write!(f, "{} | {}{}", gutter, indent, "^".repeat(carets))?;
+        for (ctx, span) in &self.context {
+            write!(f, "\n{} = note: while parsing {} starting at {}:{}", gutter, ctx, span.line, span.column)?;
+        }
+        Ok(())
+    }
+}
+
+/// Location of `len` bytes at the beginning of `input`.
+fn location(input: &Input, len: usize) -> ast::Span {
+    ast::Span {
+        file: input.extra.cloned(),
+        offset: input.location_offset(),
+        len,
+        line: input.location_line(),
+        column: input.get_utf8_column(),
+    }
+}
+
+/// The token at the beginning of `text`: a word, a string or a single character.
+fn found_token(text: &str) -> Option<String> {
+    let first = text.chars().next()?;
+    let token = if first.is_alphanumeric() || first == '_' {
+        text.split(|c: char| !(c.is_alphanumeric() || c == '_')).next().unwrap_or_default()
+    } else {
+        &text[..first.len_utf8()]
+    };
+    Some(token.to_string())
+}
+
+/// Describes a FIDL parser context like "interface" together with the name of the definition.
+pub(crate) fn describe_context(input: Input, ctx: &str) -> String {
+    let kind = match ctx {
+        "typeCollection" => "type collection",
+        "const" => "constant",
+        other => other,
+    };
+    match parser::definition_name(input, ctx) {
+        Some(name) => format!("{} `{}`", kind, name),
+        None => kind.to_string(),
+    }
+}
+
+fn describe_kind(kind: &ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::Alpha | ErrorKind::AlphaNumeric => "identifier",
+        ErrorKind::Digit | ErrorKind::HexDigit => "number",
+        ErrorKind::MapRes => "number in range",
+        ErrorKind::Verify => "keyword",
+        ErrorKind::Eof => "end of file",
+        ErrorKind::TakeUntil => "end of comment or annotation",
+        ErrorKind::Many1 | ErrorKind::Many1Count => "at least one element",
+        _ => "valid FIDL",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_unclosed_interface() {
+        let text = "package a.b\n\ninterface VehicleStatus {\n    attribute Int8 speed\n    methd getSpeed { }\n}\n";
+        let file: Arc<Path> = Arc::from(Path::new("vehicle.fidl"));
+        let diag = parser::parse_fidl(text, Some(&file), parser::ParseMode::Strict).unwrap_err();
+        assert_eq!(diag.message, "expected `}` to close interface `VehicleStatus`, found `methd`");
+        assert_eq!(diag.expected, "`}`");
+        assert_eq!((diag.span.line, diag.span.column, diag.span.len), (5, 5, 5));
+        assert_eq!(diag.source_line, "    methd getSpeed { }");
+        assert_eq!(diag.context.len(), 1);
+        assert_eq!(diag.to_string(), "\
+error: expected `}` to close interface `VehicleStatus`, found `methd`
+ --> vehicle.fidl:5:5
+  |
+5 |     methd getSpeed { }
+  |     ^^^^^
+  = note: while parsing interface `VehicleStatus` starting at 3:1");
+    }
+
+    #[test]
+    fn test_nested_context() {
+        let text = "package a\ninterface I {\n  method m {\n    in { UInt8 }\n  }\n}";
+        let diag = parser::parse_fidl(text, None, parser::ParseMode::Strict).unwrap_err();
+        assert_eq!(diag.message, "expected `}` to close method `m`, found `in`");
+        assert_eq!(diag.context.iter().map(|c| c.0.as_str()).collect::<Vec<_>>(),
+                   vec!["method `m`", "interface `I`"]);
+        assert_eq!((diag.span.line, diag.span.column), (4, 5));
+
+        let diag = parser::parse_fidl("package a\ntypeCollection { const UInt8 X = }", None, parser::ParseMode::Strict).unwrap_err();
+        assert_eq!(diag.context.iter().map(|c| c.0.as_str()).collect::<Vec<_>>(),
+                   vec!["constant `X`", "type collection"]);
+    }
+
+    #[test]
+    fn test_interval_bound() {
+        let text = "package a\ntypeCollection { typedef T is Integer(0, 999999999999999999999999999999999999999999) }";
+        let diag = parser::parse_fidl(text, None, parser::ParseMode::Strict).unwrap_err();
+        assert_eq!(diag.message, "expected number in range in typedef `T`, found `999999999999999999999999999999999999999999`");
+        assert_eq!((diag.span.line, diag.span.column), (2, 42));
+
+        let diag = parser::parse_fidl("package a\ntypeCollection { typedef T is Integer(0, max) }", None,
+            parser::ParseMode::Strict).unwrap_err();
+        assert_eq!(diag.message, "expected number in typedef `T`, found `max`");
+    }
+
+    #[test]
+    fn test_version_overflow() {
+        let text = "package a\ninterface I {\n  version { major 99999999999 minor 1 }\n}";
+        let diag = parser::parse_fidl(text, None, parser::ParseMode::Strict).unwrap_err();
+        assert_eq!(diag.message, "expected number in range in version, found `99999999999`");
+        assert_eq!((diag.span.line, diag.span.column), (3, 19));
+        assert_eq!(diag.context.iter().map(|c| c.0.as_str()).collect::<Vec<_>>(), vec!["version", "interface `I`"]);
+    }
+
+    #[test]
+    fn test_end_of_file() {
+        let diag = parser::parse_fidl("package a\ninterface I {\n  attribute UInt8 x\n", None, parser::ParseMode::Strict).unwrap_err();
+        assert_eq!(diag.message, "expected `}` to close interface `I`, found end of file");
+        assert_eq!(diag.found, None);
+        assert!(diag.to_string().contains("4 | \n  | ^"));
+    }
+
+    #[test]
+    fn test_trailing_input() {
+        let text = "package a\ninterface A { }\ninterfac B { }\n";
+        let diag = parser::parse_fidl(text, None, parser::ParseMode::Strict).unwrap_err();
+        assert_eq!(diag.message, "expected `interface`, `typeCollection` or end of file, found `interfac`");
+        assert_eq!((diag.span.line, diag.span.column, diag.span.len), (3, 1, 8));
+
+        let (rest, module) = parser::parse_fidl(text, None, parser::ParseMode::Lenient).unwrap();
+        assert_eq!(rest, "interfac B { }\n");
+        assert_eq!(module.interfaces.len(), 1);
+
+        let text = "package a\ninterface A { }\ntypeCollection T { }\ninterface B { methd x { } }\ninterface C { }";
+        let (rest, module) = parser::parse_fidl(text, None, parser::ParseMode::Lenient).unwrap();
+        assert_eq!(rest, "interface B { methd x { } }\ninterface C { }");
+        assert_eq!(module.interfaces.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(), vec!["A"]);
+        assert_eq!(module.type_collections.len(), 1);
+        assert_eq!(module.span.len, "package a\ninterface A { }\ntypeCollection T { }".len());
+
+        let diag = parser::parse_fidl("packge a\ninterface A { }", None, parser::ParseMode::Lenient).unwrap_err();
+        assert_eq!(diag.found.as_deref(), Some("packge"));
+    }
+}
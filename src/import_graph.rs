@@ -0,0 +1,153 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+//! Dependency graph of FIDL and FDEPL files built by the compiler while following imports.
+
+use super::ast;
+use super::cycles;
+use std::path::{Path, PathBuf};
+
+/// Import of `to` by `from`, both are canonical file paths.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ImportEdge {
+    pub from: PathBuf,
+    pub to: PathBuf,
+
+    /// Imported namespace, empty if the full module is imported
+    pub namespace: String,
+
+    /// URI as written in the import
+    pub uri: String,
+
+    /// Location of the import in `from`
+    pub span: ast::Span,
+}
+
+/// Graph with the parsed files as nodes and their imports as edges.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ImportGraph {
+    nodes: Vec<PathBuf>,
+    edges: Vec<ImportEdge>,
+}
+
+impl ImportGraph {
+    /// Adds `file` to the graph, returns `false` if it was already added.
+    pub(crate) fn add_node(&mut self, file: PathBuf) -> bool {
+        if self.nodes.contains(&file) {
+            return false;
+        }
+        self.nodes.push(file);
+        true
+    }
+
+    /// `true` if `file` was already added to the graph.
+    pub fn contains(&self, file: &Path) -> bool {
+        self.nodes.iter().any(|n| n == file)
+    }
+
+    pub(crate) fn add_edge(&mut self, edge: ImportEdge) {
+        self.edges.push(edge);
+    }
+
+    /// Parsed files, sorted by path.
+    pub fn nodes(&self) -> Vec<&Path> {
+        let mut nodes: Vec<&Path> = self.nodes.iter().map(PathBuf::as_path).collect();
+        nodes.sort();
+        nodes
+    }
+
+    pub fn edges(&self) -> &[ImportEdge] {
+        &self.edges
+    }
+
+    /// Imports of `file` in the order of the source.
+    pub fn imports_of<'a>(&'a self, file: &'a Path) -> impl Iterator<Item = &'a ImportEdge> {
+        self.edges.iter().filter(move |e| e.from == file)
+    }
+
+    /// Import cycles, each as list of files where the last file imports the first one.
+    pub fn cycles(&self) -> Vec<Vec<PathBuf>> {
+        let nodes = self.nodes();
+        cycles::cycles(nodes.len(), |i| self.successors(&nodes, i))
+            .into_iter()
+            .map(|cycle| cycle.into_iter().map(|i| nodes[i].to_path_buf()).collect())
+            .collect()
+    }
+
+    /// All files, each file after the files it imports. Files of an import cycle are ordered by
+    /// their first visit.
+    pub fn topological_order(&self) -> Vec<PathBuf> {
+        fn visit(node: usize, graph: &ImportGraph, nodes: &[&Path], visited: &mut [bool], order: &mut Vec<PathBuf>) {
+            visited[node] = true;
+            for next in graph.successors(nodes, node) {
+                if !visited[next] {
+                    visit(next, graph, nodes, visited, order);
+                }
+            }
+            order.push(nodes[node].to_path_buf());
+        }
+
+        let nodes = self.nodes();
+        let mut visited = vec![false; nodes.len()];
+        let mut order = Vec::new();
+        for node in 0..nodes.len() {
+            if !visited[node] {
+                visit(node, self, &nodes, &mut visited, &mut order);
+            }
+        }
+        order
+    }
+
+    /// Sorts modules (FIDL modules or deployment models) by the topological order of their files,
+    /// modules of files unknown to the graph go last.
+    pub fn sort_modules<T>(&self, modules: &mut [(T, PathBuf)]) {
+        let order = self.topological_order();
+        modules.sort_by_key(|(_, file)| order.iter().position(|f| f == file).unwrap_or(order.len()));
+    }
+
+    fn successors(&self, nodes: &[&Path], node: usize) -> Vec<usize> {
+        self.imports_of(nodes[node])
+            .filter_map(|e| nodes.iter().position(|n| *n == e.to))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn graph(edges: &[(&str, &str)]) -> ImportGraph {
+        let mut graph = ImportGraph::default();
+        for (from, to) in edges {
+            graph.add_node(PathBuf::from(from));
+            graph.add_node(PathBuf::from(to));
+            graph.add_edge(ImportEdge{ from: PathBuf::from(from), to: PathBuf::from(to), namespace: String::new(),
+                uri: to.to_string(), span: ast::Span::default() });
+        }
+        graph
+    }
+
+    fn names(files: &[PathBuf]) -> Vec<&str> {
+        files.iter().map(|f| f.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_diamond() {
+        let graph = graph(&[("a", "b"), ("a", "c"), ("b", "d"), ("c", "d")]);
+        assert!(graph.cycles().is_empty());
+        assert_eq!(names(&graph.topological_order()), vec!["d", "b", "c", "a"]);
+        assert_eq!(graph.imports_of(Path::new("a")).count(), 2);
+    }
+
+    #[test]
+    fn test_cycle() {
+        let graph = graph(&[("a", "b"), ("b", "c"), ("c", "b"), ("c", "d")]);
+        let cycles = graph.cycles();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(names(&cycles[0]), vec!["b", "c"]);
+        assert_eq!(names(&graph.topological_order()), vec!["d", "c", "b", "a"]);
+    }
+}
@@ -0,0 +1,126 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+//! JSON representation of the result of `compiler::parse_fidls`, requires the `serde` feature.
+//!
+//! ```json
+//! {
+//!   "modules": [ { "file": "/path/Service.fidl", "module": { "package": "org.example", ... } } ],
+//!   "errors": [ { "kind": "SyntaxError", "file": "/path/Broken.fidl", "referenced_by": null,
+//!                 "line": 3, "column": 5, "message": "..." } ]
+//! }
+//! ```
+//!
+//! AST nodes are serialized with the field names of `ast`. Enums are objects with a `kind` field
+//! naming the variant. Enums with struct and unit variants only (`Type`, `MethodErrorSpec`,
+//! `Trigger`, `Operator`) have the fields next to `kind`, e.g. `{ "kind": "Struct", "name":
+//! "Point", ... }`. Enums with tuple variants (`TypeRef`, `AnnotationKind`, `InitializerExpression`,
+//! `Expression`, `Literal`, `Statement`) have the payload of each variant in `value`:
+//! `{ "kind": "Derived", "value": "Types.Point" }`, `{ "kind": "IntegerInterval", "value": [0, null] }`,
+//! `{ "kind": "Unary", "value": { "op": ..., "operand": ... } }`. The kind of an annotation is
+//! flattened into the annotation: `{ "kind": "Author", "text": "..." }`, `{ "kind": "Custom",
+//! "value": "todo", "text": "..." }`. Versions are `[major, minor]` arrays, spans are
+//! `{ "offset", "len", "line", "column" }` without the file.
+
+use super::ast;
+use super::compiler::ParseError;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Parsed modules and errors, serializes to the JSON shape described above.
+#[derive(Serialize, Debug)]
+pub struct ParseResult<'a> {
+    pub modules: Vec<FileModule<'a>>,
+    pub errors: Vec<FileError>,
+}
+
+/// Module with the file it was parsed from.
+#[derive(Serialize, Debug)]
+pub struct FileModule<'a> {
+    pub file: &'a Path,
+    pub module: &'a ast::Module,
+}
+
+/// `ParseError` flattened to the kind of error, the file and location it refers to and the
+/// complete error message.
+#[derive(Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct FileError {
+    /// Name of the `ParseError` variant, e.g. `FileNotFound`
+    pub kind: &'static str,
+    pub file: Option<PathBuf>,
+    pub referenced_by: Option<PathBuf>,
+
+    /// Location of syntax errors
+    pub line: Option<u32>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl From<&ParseError> for FileError {
+    fn from(error: &ParseError) -> Self {
+        let (kind, file, referenced_by) = match error {
+            ParseError::IoError{ file, referenced_by, .. } => ("IoError", Some(file), referenced_by),
+            ParseError::PathError{ file, referenced_by, .. } => ("PathError", Some(file), referenced_by),
+            ParseError::MaxImportNestingReached{ file, referenced_by, .. } =>
+                ("MaxImportNestingReached", Some(file), referenced_by),
+            ParseError::FileNotFound{ file, referenced_by, .. } => ("FileNotFound", Some(file), referenced_by),
+            ParseError::SyntaxError{ file, referenced_by, .. } => ("SyntaxError", Some(file), referenced_by),
+            ParseError::InvalidImport{ referenced_by, .. } => ("InvalidImport", None, referenced_by),
+            ParseError::ImportCycle{ cycle } => ("ImportCycle", cycle.first(), &None),
+        };
+        let (line, column) = match error {
+            ParseError::SyntaxError{ diagnostic, .. } => (Some(diagnostic.span.line), Some(diagnostic.span.column)),
+            _ => (None, None),
+        };
+        FileError{ kind, file: file.cloned(), referenced_by: referenced_by.clone(), line, column,
+            message: error.to_string() }
+    }
+}
+
+impl<'a> ParseResult<'a> {
+    pub fn new(modules: &'a [(ast::Module, PathBuf)], errors: &[ParseError]) -> Self {
+        ParseResult{
+            modules: modules.iter().map(|(module, file)| FileModule{ file, module }).collect(),
+            errors: errors.iter().map(FileError::from).collect(),
+        }
+    }
+}
+
+/// Serializes the modules and errors returned by `compiler::parse_fidls` as pretty printed JSON.
+pub fn to_json(modules: &[(ast::Module, PathBuf)], errors: &[ParseError]) -> String {
+    // the AST has no maps with non-string keys, nothing can fail
+    serde_json::to_string_pretty(&ParseResult::new(modules, errors)).expect("AST serializes to JSON")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::parse_module;
+    use serde_json::json;
+
+    #[test]
+    fn test_shape() {
+        let module = parse_module("package a <** @author: me @todo: more **> typeCollection T { version { major 1 minor 0 }
+            typedef Id is Integer(0, maxInt) typedef Big is Integer(-5, 100000000000000000000)
+            const Boolean b = !true }").unwrap().1;
+        let value = serde_json::to_value(&module).unwrap();
+        let tc = &value["type_collections"][0];
+        assert_eq!(tc["version"], json!([1, 0]));
+        assert_eq!(tc["annotation"]["entries"], json!([{ "kind": "Author", "text": "me" },
+            { "kind": "Custom", "value": "todo", "text": "more" }]));
+        assert_eq!(tc["types"][0]["kind"], "TypeDef");
+        assert_eq!(tc["types"][0]["actual_type"], json!({ "kind": "IntegerInterval", "value": [0, null] }));
+        assert_eq!(tc["types"][1]["actual_type"]["value"], json!([-5, "100000000000000000000"]));
+        assert_eq!(tc["constants"][0]["type_ref"], json!({ "kind": "Boolean" }));
+        assert_eq!(tc["constants"][0]["value"], json!({ "kind": "Expression", "value":
+            { "kind": "Unary", "value": { "op": { "kind": "Negation" }, "operand": { "kind": "Literal", "value":
+                { "kind": "Boolean", "value": true } } } } }));
+        assert!(tc["span"].get("file").is_none());
+
+        let parsed: ast::Module = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, module);
+    }
+}
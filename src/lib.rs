@@ -6,4 +6,15 @@
 pub mod util;
 pub mod ast;
 pub mod parser;
+pub mod diagnostic;
+pub mod linker;
+pub mod model;
+pub mod validator;
+pub mod cycles;
+pub mod import_graph;
+pub mod source;
+pub mod deployment;
+pub mod printer;
 pub mod compiler;
+#[cfg(feature = "serde")]
+pub mod json;
@@ -0,0 +1,527 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+use super::ast;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Kind of a named FRANCA definition.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SymbolKind {
+    Interface,
+    TypeDef,
+    Array,
+    Struct,
+    Union,
+    Map,
+    Enumeration,
+    Constant,
+}
+
+impl SymbolKind {
+    /// Whether the kind is a type which can be used in a type reference.
+    pub fn is_type(&self) -> bool {
+        !matches!(self, SymbolKind::Interface | SymbolKind::Constant)
+    }
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SymbolKind::Interface => "interface",
+            SymbolKind::TypeDef => "typedef",
+            SymbolKind::Array => "array",
+            SymbolKind::Struct => "struct",
+            SymbolKind::Union => "union",
+            SymbolKind::Map => "map",
+            SymbolKind::Enumeration => "enumeration",
+            SymbolKind::Constant => "constant",
+        })
+    }
+}
+
+/// AST node of a named definition.
+#[derive(Clone, Copy, Debug)]
+pub enum Definition<'a> {
+    Interface(&'a ast::Interface),
+    Type(&'a ast::Type),
+    Constant(&'a ast::Constant),
+}
+
+/// Named definition in the symbol table.
+#[derive(Clone, Debug)]
+pub struct Symbol<'a> {
+    /// Fully qualified name, e.g. `a.b.MyTypes.MyStruct`
+    pub fqn: String,
+
+    pub definition: Definition<'a>,
+
+    /// Index of the defining module in the module list
+    pub module: usize,
+
+    /// Fully qualified name of the enclosing interface or type collection, `None` for interfaces
+    pub container: Option<String>,
+}
+
+impl<'a> Symbol<'a> {
+    pub fn kind(&self) -> SymbolKind {
+        match self.definition {
+            Definition::Interface(_) => SymbolKind::Interface,
+            Definition::Constant(_) => SymbolKind::Constant,
+            Definition::Type(ast::Type::TypeDef{ .. }) => SymbolKind::TypeDef,
+            Definition::Type(ast::Type::Array{ .. }) => SymbolKind::Array,
+            Definition::Type(ast::Type::Struct{ .. }) => SymbolKind::Struct,
+            Definition::Type(ast::Type::Union{ .. }) => SymbolKind::Union,
+            Definition::Type(ast::Type::Map{ .. }) => SymbolKind::Map,
+            Definition::Type(ast::Type::Enumeration{ .. }) => SymbolKind::Enumeration,
+        }
+    }
+
+    /// Location of the definition.
+    pub fn span(&self) -> &'a ast::Span {
+        match self.definition {
+            Definition::Interface(interface) => &interface.span,
+            Definition::Type(tp) => tp.span(),
+            Definition::Constant(constant) => &constant.span,
+        }
+    }
+}
+
+/// Place where a name is used: the module and the enclosing interface or type collection.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Scope {
+    /// Index of the module in the module list
+    pub module: usize,
+
+    /// Fully qualified name of the enclosing interface or type collection
+    pub container: Option<String>,
+}
+
+/// Reasons why a name could not be resolved.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ResolveError {
+    /// No definition found
+    Unresolved,
+
+    /// The name refers to several definitions (fully qualified names)
+    Ambiguous(Vec<String>),
+}
+
+/// Error found while linking a set of modules.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LinkError {
+    /// No definition for the name `name` of the expected kind (e.g. "type" or "interface")
+    Unresolved{ name: String, expected: String, file: PathBuf, span: ast::Span },
+
+    /// The name refers to several definitions
+    Ambiguous{ name: String, candidates: Vec<String>, file: PathBuf, span: ast::Span },
+
+    /// The name refers to a definition of the wrong kind, e.g. a struct extending a union
+    WrongKind{ name: String, fqn: String, expected: String, found: SymbolKind, file: PathBuf, span: ast::Span },
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (file, span) = match self {
+            LinkError::Unresolved{ file, span, .. } | LinkError::Ambiguous{ file, span, .. } |
+            LinkError::WrongKind{ file, span, .. } => (file, span),
+        };
+        write!(f, "{}:{}:{}: error: ", file.display(), span.line, span.column)?;
+        match self {
+            LinkError::Unresolved{ name, expected, .. } =>
+                write!(f, "cannot resolve {} `{}`", expected, name),
+            LinkError::Ambiguous{ name, candidates, .. } =>
+                write!(f, "`{}` is ambiguous, candidates are {}", name, candidates.join(", ")),
+            LinkError::WrongKind{ name, fqn, expected, found, .. } =>
+                write!(f, "`{}` refers to {} `{}` but {} expected", name, found, fqn, expected),
+        }
+    }
+}
+
+/// Global symbol table over a set of modules, keyed by fully qualified name.
+/// The fully qualified name of an interface is `package.Interface`, of types and constants
+/// `package.Interface.Name` or `package.TypeCollection.Name` (`package.Name` for anonymous type
+/// collections).
+pub struct SymbolTable<'a> {
+    modules: &'a [(ast::Module, PathBuf)],
+    symbols: HashMap<String, Vec<Symbol<'a>>>,
+}
+
+impl<'a> SymbolTable<'a> {
+    pub fn new(modules: &'a [(ast::Module, PathBuf)]) -> SymbolTable<'a> {
+        let mut table = SymbolTable{ modules, symbols: HashMap::new() };
+        for (index, (module, _)) in modules.iter().enumerate() {
+            for interface in &module.interfaces {
+                let fqn = format!("{}.{}", module.package, interface.name);
+                table.add(Symbol{ fqn: fqn.clone(), definition: Definition::Interface(interface),
+                    module: index, container: None });
+                table.add_members(index, &fqn, &interface.types, &interface.constants);
+            }
+            for tc in &module.type_collections {
+                let fqn = match &tc.name {
+                    Some(name) => format!("{}.{}", module.package, name),
+                    None => module.package.clone(),
+                };
+                table.add_members(index, &fqn, &tc.types, &tc.constants);
+            }
+        }
+        table
+    }
+
+    fn add_members(&mut self, module: usize, container: &str, types: &'a [ast::Type], constants: &'a [ast::Constant]) {
+        for tp in types {
+            self.add(Symbol{ fqn: format!("{}.{}", container, tp.name()), definition: Definition::Type(tp),
+                module, container: Some(container.to_string()) });
+        }
+        for constant in constants {
+            self.add(Symbol{ fqn: format!("{}.{}", container, constant.name),
+                definition: Definition::Constant(constant), module, container: Some(container.to_string()) });
+        }
+    }
+
+    fn add(&mut self, symbol: Symbol<'a>) {
+        self.symbols.entry(symbol.fqn.clone()).or_default().push(symbol);
+    }
+
+    /// The modules the table was built from.
+    pub fn modules(&self) -> &'a [(ast::Module, PathBuf)] {
+        self.modules
+    }
+
+    /// All definitions with the fully qualified name `fqn`, more than one if it is defined twice.
+    pub fn get(&self, fqn: &str) -> &[Symbol<'a>] {
+        self.symbols.get(fqn).map_or(&[], |v| v.as_slice())
+    }
+
+    /// Iterates over all symbols in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &Symbol<'a>> {
+        self.symbols.values().flatten()
+    }
+
+    /// Resolves `name` used in `scope`. Candidates are searched in this order, the first step
+    /// yielding a definition determines the result:
+    /// 1. the enclosing interface or type collection, then the base interfaces of an interface one
+    ///    after the other, so a derived interface shadows the definitions of its base interfaces,
+    /// 2. the package of the module,
+    /// 3. the namespaces imported by the module (`import a.b.* from ...` makes `a.b.X` visible as
+    ///    `X`, `import a.b.C from ...` makes `a.b.C.X` visible as `C.X`),
+    /// 4. the name as fully qualified name.
+    pub fn resolve(&self, scope: &Scope, name: &str) -> Result<&Symbol<'a>, ResolveError> {
+        let module = &self.modules[scope.module].0;
+
+        let mut levels: Vec<Vec<String>> = self.container_chain(scope).iter()
+            .map(|container| vec![format!("{}.{}", container, name)])
+            .collect();
+        levels.push(vec![format!("{}.{}", module.package, name)]);
+        levels.push(module.imports.iter()
+            .filter_map(|import| imported_name(&import.namespace, name))
+            .collect());
+        levels.push(vec![name.to_string()]);
+
+        for candidates in levels {
+            let mut found: Vec<&Symbol<'a>> = Vec::new();
+            for fqn in candidates {
+                for symbol in self.get(&fqn) {
+                    if !found.iter().any(|s| std::ptr::eq(*s, symbol)) {
+                        found.push(symbol);
+                    }
+                }
+            }
+            match found.len() {
+                0 => continue,
+                1 => return Ok(found[0]),
+                _ => return Err(ResolveError::Ambiguous(found.iter().map(|s| s.fqn.clone()).collect())),
+            }
+        }
+        Err(ResolveError::Unresolved)
+    }
+
+    /// The container of `scope` followed by the base interfaces if the container is an interface.
+    fn container_chain(&self, scope: &Scope) -> Vec<String> {
+        let mut chain = Vec::new();
+        let mut current = scope.container.clone();
+        while let Some(container) = current.take() {
+            if chain.contains(&container) {
+                break;
+            }
+            let base = self.get(&container).iter().find_map(|symbol| match symbol.definition {
+                Definition::Interface(ast::Interface{ extends: Some(base), .. }) => Some((symbol.module, base)),
+                _ => None,
+            });
+            if let Some((module, base)) = base {
+                let base_scope = Scope{ module, container: None };
+                current = self.resolve(&base_scope, base).ok().map(|base| base.fqn.clone());
+            }
+            chain.push(container);
+        }
+        chain
+    }
+}
+
+/// Fully qualified name of `name` if it is visible through the import of `namespace`.
+fn imported_name(namespace: &str, name: &str) -> Option<String> {
+    if namespace.is_empty() {
+        return None;
+    }
+    if let Some(prefix) = namespace.strip_suffix(".*") {
+        return Some(format!("{}.{}", prefix, name));
+    }
+    let last = namespace.rsplit('.').next().unwrap_or(namespace);
+    if name == last || name.starts_with(&format!("{}.", last)) {
+        Some(format!("{}{}", namespace, &name[last.len()..]))
+    } else {
+        None
+    }
+}
+
+/// Resolves all references between the given modules: derived type references, struct, union and
+/// enumeration bases, method error references and the `extends` and `manages` references of
+/// interfaces. Returns the symbol table and an error for each reference which cannot be resolved.
+pub fn link(modules: &[(ast::Module, PathBuf)]) -> (SymbolTable<'_>, Vec<LinkError>) {
+    let table = SymbolTable::new(modules);
+    let mut linker = Linker{ table: &table, errors: Vec::new() };
+    for (index, (module, file)) in modules.iter().enumerate() {
+        linker.link_module(index, module, file);
+    }
+    let errors = linker.errors;
+    (table, errors)
+}
+
+/// Expected kind of a referenced definition.
+#[derive(Clone, Copy)]
+enum Expected {
+    Type,
+    Kind(SymbolKind),
+}
+
+impl Expected {
+    fn matches(&self, kind: SymbolKind) -> bool {
+        match self {
+            Expected::Type => kind.is_type(),
+            Expected::Kind(k) => *k == kind,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Expected::Type => "type".to_string(),
+            Expected::Kind(k) => k.to_string(),
+        }
+    }
+}
+
+struct Linker<'t, 'a> {
+    table: &'t SymbolTable<'a>,
+    errors: Vec<LinkError>,
+}
+
+impl<'t, 'a> Linker<'t, 'a> {
+    fn link_module(&mut self, index: usize, module: &ast::Module, file: &Path) {
+        let module_scope = Scope{ module: index, container: None };
+        for interface in &module.interfaces {
+            if let Some(base) = &interface.extends {
+                self.check(&module_scope, file, base, Expected::Kind(SymbolKind::Interface), &interface.span);
+            }
+            for managed in interface.manages.iter().flatten() {
+                self.check(&module_scope, file, managed, Expected::Kind(SymbolKind::Interface), &interface.span);
+            }
+
+            let scope = Scope{ module: index, container: Some(format!("{}.{}", module.package, interface.name)) };
+            for attribute in &interface.attributes {
+                self.type_ref(&scope, file, &attribute.type_ref, &attribute.span);
+            }
+            for method in &interface.methods {
+                for arg in method.in_args.iter().chain(&method.out_args) {
+                    self.type_ref(&scope, file, &arg.type_ref, &arg.span);
+                }
+                match &method.error {
+                    Some(ast::MethodErrorSpec::Reference{ fqn, .. }) =>
+                        self.check(&scope, file, fqn, Expected::Kind(SymbolKind::Enumeration), &method.span),
+                    Some(ast::MethodErrorSpec::EnumerationBody{ extends: Some(base), .. }) =>
+                        self.enum_base(&scope, file, base, &method.span),
+                    _ => {},
+                }
+            }
+            for broadcast in &interface.broadcasts {
+                for arg in &broadcast.out_args {
+                    self.type_ref(&scope, file, &arg.type_ref, &arg.span);
+                }
+            }
+            self.types(&scope, file, &interface.types);
+            self.constants(&scope, file, &interface.constants);
+            if let Some(contract) = &interface.contract {
+                for declaration in &contract.variables {
+                    self.type_ref(&scope, file, &declaration.type_ref, &interface.span);
+                }
+            }
+        }
+        for tc in &module.type_collections {
+            let container = match &tc.name {
+                Some(name) => format!("{}.{}", module.package, name),
+                None => module.package.clone(),
+            };
+            let scope = Scope{ module: index, container: Some(container) };
+            self.types(&scope, file, &tc.types);
+            self.constants(&scope, file, &tc.constants);
+        }
+    }
+
+    fn types(&mut self, scope: &Scope, file: &Path, types: &[ast::Type]) {
+        for tp in types {
+            match tp {
+                ast::Type::TypeDef{ actual_type, span, .. } => self.type_ref(scope, file, actual_type, span),
+                ast::Type::Array{ element_type, span, .. } => self.type_ref(scope, file, element_type, span),
+                ast::Type::Struct{ extends, fields, span, .. } => {
+                    if let Some(base) = extends {
+                        self.check(scope, file, base, Expected::Kind(SymbolKind::Struct), span);
+                    }
+                    for field in fields {
+                        self.type_ref(scope, file, &field.type_ref, &field.span);
+                    }
+                },
+                ast::Type::Union{ base_type, fields, span, .. } => {
+                    if let Some(base) = base_type {
+                        self.check(scope, file, base, Expected::Kind(SymbolKind::Union), span);
+                    }
+                    for field in fields {
+                        self.type_ref(scope, file, &field.type_ref, &field.span);
+                    }
+                },
+                ast::Type::Map{ key_type, value_type, span, .. } => {
+                    self.type_ref(scope, file, key_type, span);
+                    self.type_ref(scope, file, value_type, span);
+                },
+                ast::Type::Enumeration{ base_type: Some(base), span, .. } => self.enum_base(scope, file, base, span),
+                ast::Type::Enumeration{ .. } => {},
+            }
+        }
+    }
+
+    fn constants(&mut self, scope: &Scope, file: &Path, constants: &[ast::Constant]) {
+        for constant in constants {
+            self.type_ref(scope, file, &constant.type_ref, &constant.span);
+        }
+    }
+
+    fn type_ref(&mut self, scope: &Scope, file: &Path, type_ref: &ast::TypeRef, span: &ast::Span) {
+        if let ast::TypeRef::Derived(name) = type_ref {
+            self.check(scope, file, name, Expected::Type, span);
+        }
+    }
+
+    fn enum_base(&mut self, scope: &Scope, file: &Path, base: &ast::TypeRef, span: &ast::Span) {
+        if let ast::TypeRef::Derived(name) = base {
+            self.check(scope, file, name, Expected::Kind(SymbolKind::Enumeration), span);
+        }
+    }
+
+    fn check(&mut self, scope: &Scope, file: &Path, name: &str, expected: Expected, span: &ast::Span) {
+        let error = match self.table.resolve(scope, name) {
+            Ok(symbol) if expected.matches(symbol.kind()) => return,
+            Ok(symbol) => LinkError::WrongKind{ name: name.to_string(), fqn: symbol.fqn.clone(),
+                expected: expected.describe(), found: symbol.kind(), file: file.to_path_buf(), span: span.clone() },
+            Err(ResolveError::Unresolved) => LinkError::Unresolved{ name: name.to_string(),
+                expected: expected.describe(), file: file.to_path_buf(), span: span.clone() },
+            Err(ResolveError::Ambiguous(candidates)) => LinkError::Ambiguous{ name: name.to_string(),
+                candidates, file: file.to_path_buf(), span: span.clone() },
+        };
+        self.errors.push(error);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser;
+
+    fn modules(texts: &[&str]) -> Vec<(ast::Module, PathBuf)> {
+        texts.iter().enumerate()
+            .map(|(i, text)| (parser::parse_module(text).unwrap().1, PathBuf::from(format!("m{}.fidl", i))))
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mods = modules(&["package a.b
+            import c.d.* from \"c.fidl\"
+            import e.Types from \"e.fidl\"
+            typeCollection Local { typedef Id is UInt8 }
+            interface Base { struct Inherited { UInt8 x } }
+            interface I extends Base {
+                attribute Local.Id id
+                attribute Inherited inherited
+                attribute Shared shared
+                attribute Types.Other other
+                attribute c.d.Shared absolute
+            }",
+            "package c.d\ntypeCollection { struct Shared { } }",
+            "package e\ntypeCollection Types { union Other { } }",
+        ]);
+        let table = SymbolTable::new(&mods);
+        let scope = Scope{ module: 0, container: Some("a.b.I".to_string()) };
+        let fqn = |name| table.resolve(&scope, name).map(|s| s.fqn.clone());
+        assert_eq!(fqn("Local.Id"), Ok("a.b.Local.Id".to_string()));
+        assert_eq!(fqn("Inherited"), Ok("a.b.Base.Inherited".to_string()));
+        assert_eq!(fqn("Shared"), Ok("c.d.Shared".to_string()));
+        assert_eq!(fqn("Types.Other"), Ok("e.Types.Other".to_string()));
+        assert_eq!(fqn("c.d.Shared"), Ok("c.d.Shared".to_string()));
+        assert_eq!(fqn("Other"), Err(ResolveError::Unresolved));
+        assert_eq!(table.resolve(&scope, "Base").map(|s| s.kind()), Ok(SymbolKind::Interface));
+
+        let (_, errors) = link(&mods);
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_shadowing() {
+        let mods = modules(&["package a
+            interface A { struct S { UInt8 a } struct Base { } }
+            interface B extends A { struct S { UInt16 b } attribute S s }
+            interface C extends B { attribute S s attribute Base base }",
+        ]);
+        let table = SymbolTable::new(&mods);
+        let fqn = |container: &str, name| table.resolve(&Scope{ module: 0, container: Some(container.to_string()) }, name)
+            .map(|s| s.fqn.clone());
+        assert_eq!(fqn("a.B", "S"), Ok("a.B.S".to_string()));
+        assert_eq!(fqn("a.C", "S"), Ok("a.B.S".to_string()));
+        assert_eq!(fqn("a.C", "Base"), Ok("a.A.Base".to_string()));
+        assert_eq!(fqn("a.A", "S"), Ok("a.A.S".to_string()));
+
+        let (_, errors) = link(&mods);
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_link_errors() {
+        let mods = modules(&["package a
+            import x.* from \"x.fidl\"
+            import y.* from \"y.fidl\"
+            typeCollection T {
+                union U { }
+                struct S extends U { Missing m }
+                enumeration E extends S { A }
+            }
+            interface I extends T.S manages J, K {
+                method m { in { Dup d } error T.U }
+            }
+            interface J { }",
+            "package x\ntypeCollection { struct Dup { } }",
+            "package y\ntypeCollection { struct Dup { } }",
+        ]);
+        let (_, errors) = link(&mods);
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages, vec![
+            "m0.fidl:9:13: error: `T.S` refers to struct `a.T.S` but interface expected",
+            "m0.fidl:9:13: error: cannot resolve interface `K`",
+            "m0.fidl:10:33: error: `Dup` is ambiguous, candidates are x.Dup, y.Dup",
+            "m0.fidl:10:17: error: `T.U` refers to union `a.T.U` but enumeration expected",
+            "m0.fidl:6:17: error: `U` refers to union `a.T.U` but struct expected",
+            "m0.fidl:6:38: error: cannot resolve type `Missing`",
+            "m0.fidl:7:17: error: `S` refers to struct `a.T.S` but enumeration expected",
+        ]);
+    }
+}
@@ -0,0 +1,304 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+//! `fipa` command line tool: checks, formats and dumps FRANCA IDL files and lists their imports.
+//! Exits with 0 on success, 1 if errors were found (or files are not formatted with
+//! `fmt --check`) and 2 on invalid arguments.
+
+use fipa::{compiler, cycles, deployment, model::Model, parser::{self, ParseMode}, printer, validator};
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+};
+
+const USAGE: &str = "\
+usage: fipa <command> [options] <files>...
+
+commands:
+    check    parse, link and validate FIDL and FDEPL files and their imports
+    fmt      reformat FIDL files in place
+    dump     print the AST of FIDL files
+    deps     list FIDL and FDEPL files with the files they import, transitively
+
+options:
+    -I <dir>             search imported files in <dir>, may be given more than once
+    --max-nesting <n>    maximum length of an import chain (default 256)
+    --strict             check: require a version for each interface
+    --check              fmt: list files which are not formatted instead of rewriting them
+    --indent <n|tab>     fmt: indent by <n> spaces or by tabs (default 4)
+    --json, --debug      dump: output format (default debug), JSON needs the `serde` feature
+    -h, --help           print this help
+";
+
+const MAX_IMPORT_NESTING: usize = 256;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Command {
+    Check,
+    Fmt,
+    Dump,
+    Deps,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Format {
+    Debug,
+    Json,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Args {
+    command: Command,
+    files: Vec<PathBuf>,
+    search_dirs: Vec<PathBuf>,
+    max_nesting: usize,
+    strict: bool,
+    check: bool,
+    indent: printer::Indent,
+    format: Format,
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        },
+        Err(message) => {
+            eprint!("error: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        },
+    };
+    let ok = match args.command {
+        Command::Check => check(&args),
+        Command::Fmt => fmt(&args),
+        Command::Dump => dump(&args),
+        Command::Deps => deps(&args),
+    };
+    if ok { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
+
+/// Parses the command line arguments without the program name, `None` if help was asked for.
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut args = args.into_iter();
+    let command = match args.next().as_deref() {
+        Some("check") => Command::Check,
+        Some("fmt") => Command::Fmt,
+        Some("dump") => Command::Dump,
+        Some("deps") => Command::Deps,
+        Some("-h") | Some("--help") => return Ok(None),
+        Some(other) => return Err(format!("unknown command `{}`", other)),
+        None => return Err("missing command".to_string()),
+    };
+    let mut parsed = Args{ command, files: Vec::new(), search_dirs: Vec::new(), max_nesting: MAX_IMPORT_NESTING,
+        strict: false, check: false, indent: printer::Indent::Spaces(4), format: Format::Debug };
+    let mut only_files = false;
+    while let Some(arg) = args.next() {
+        if only_files || !arg.starts_with('-') {
+            parsed.files.push(PathBuf::from(arg));
+            continue;
+        }
+        let mut value = |option: &str| args.next().ok_or_else(|| format!("option `{}` needs a value", option));
+        match arg.as_str() {
+            "--" => only_files = true,
+            "-h" | "--help" => return Ok(None),
+            "-I" => parsed.search_dirs.push(PathBuf::from(value("-I")?)),
+            dir if dir.starts_with("-I") => parsed.search_dirs.push(PathBuf::from(&dir[2..])),
+            "--max-nesting" => {
+                let max = value("--max-nesting")?;
+                parsed.max_nesting = max.parse().map_err(|_| format!("invalid import nesting `{}`", max))?;
+            },
+            "--strict" if command == Command::Check => parsed.strict = true,
+            "--check" if command == Command::Fmt => parsed.check = true,
+            "--indent" if command == Command::Fmt => {
+                parsed.indent = match value("--indent")?.as_str() {
+                    "tab" => printer::Indent::Tab,
+                    n => printer::Indent::Spaces(n.parse().map_err(|_| format!("invalid indentation `{}`", n))?),
+                };
+            },
+            "--json" if command == Command::Dump => parsed.format = Format::Json,
+            "--debug" if command == Command::Dump => parsed.format = Format::Debug,
+            option => return Err(format!("unknown option `{}`", option)),
+        }
+    }
+    if parsed.files.is_empty() {
+        return Err("no files given".to_string());
+    }
+    Ok(Some(parsed))
+}
+
+/// Parses the files and their imports, links and validates the modules and deployments and
+/// reports all errors found.
+fn check(args: &Args) -> bool {
+    // FIDL files are parsed as well, they are told apart from deployments by their extension
+    let (deployments, modules, parse_errors) =
+        compiler::parse_fdepls_sync(&args.files, &args.search_dirs, args.max_nesting, ParseMode::Strict, true);
+    let mut errors: Vec<String> = parse_errors.iter().map(ToString::to_string).collect();
+
+    let (model, link_errors) = Model::build(&modules);
+    errors.extend(link_errors.iter().map(ToString::to_string));
+    let mode = if args.strict { validator::ValidationMode::Strict } else { validator::ValidationMode::Default };
+    errors.extend(validator::validate(&modules, mode).iter().map(ToString::to_string));
+    errors.extend(cycles::find_cycles(&model).iter().map(ToString::to_string));
+    errors.extend(deployment::validator::validate(&deployments, &model).iter().map(ToString::to_string));
+
+    report(&errors)
+}
+
+/// Prints the errors and their number, `true` if there are none.
+fn report(errors: &[String]) -> bool {
+    for error in errors {
+        eprintln!("{}", error);
+    }
+    match errors.len() {
+        0 => true,
+        1 => {
+            eprintln!("1 error");
+            false
+        },
+        n => {
+            eprintln!("{} errors", n);
+            false
+        },
+    }
+}
+
+/// Rejects all files but FIDL files.
+fn fidl_files(args: &Args) -> Result<(), String> {
+    match args.files.iter().find(|f| f.extension() != Some("fidl".as_ref())) {
+        Some(file) => Err(format!("{}: error: not a FIDL file", file.display())),
+        None => Ok(()),
+    }
+}
+
+fn fmt(args: &Args) -> bool {
+    if let Err(error) = fidl_files(args) {
+        return report(&[error]);
+    }
+    let options = printer::PrintOptions{ indent: args.indent, ..Default::default() };
+    let mut errors = Vec::new();
+    let mut formatted = true;
+    for file in &args.files {
+        match format_file(file, &options) {
+            Ok(None) => {},
+            Ok(Some(_)) if args.check => {
+                println!("{}", file.display());
+                formatted = false;
+            },
+            Ok(Some(text)) => if let Err(e) = std::fs::write(file, text) {
+                errors.push(format!("{}: error: {}", file.display(), e));
+            },
+            Err(error) => errors.push(error),
+        }
+    }
+    report(&errors) && formatted
+}
+
+/// Returns the formatted text of `file`, `None` if the file is formatted already.
+fn format_file(file: &Path, options: &printer::PrintOptions) -> Result<Option<String>, String> {
+    let text = std::fs::read_to_string(file).map_err(|e| format!("{}: error: {}", file.display(), e))?;
+    let path: Arc<Path> = file.into();
+    let (_, module) = parser::parse_fidl(&text, Some(&path), ParseMode::Strict).map_err(|d| d.to_string())?;
+    if has_comments(&text) {
+        return Err(format!("{}: error: comments would get lost by formatting", file.display()));
+    }
+    let formatted = printer::print_with(&module, options);
+    Ok(if formatted == text { None } else { Some(formatted) })
+}
+
+/// `true` if the FIDL text has `//` or `/* */` comments outside of strings and annotation blocks.
+/// Comments are not kept in the AST, so formatting would remove them.
+fn has_comments(text: &str) -> bool {
+    let mut rest = text;
+    while let Some(start) = rest.find(['"', '\'', '/', '<']) {
+        let tail = &rest[start..];
+        let end = if tail.starts_with("//") || tail.starts_with("/*") {
+            return true;
+        } else if tail.starts_with("<**") {
+            tail.find("**>").map(|e| e + 3)
+        } else if tail.starts_with(['"', '\'']) {
+            tail[1..].find(&tail[..1]).map(|e| e + 2)
+        } else {
+            Some(1)
+        };
+        match end {
+            Some(end) => rest = &tail[end..],
+            None => return false,
+        }
+    }
+    false
+}
+
+/// Prints the modules of the files, imports are not followed. The JSON output contains the
+/// errors as well.
+fn dump(args: &Args) -> bool {
+    if let Err(error) = fidl_files(args) {
+        return report(&[error]);
+    }
+    if args.format == Format::Json && cfg!(not(feature = "serde")) {
+        return report(&["error: JSON output needs fipa built with the `serde` feature".to_string()]);
+    }
+    let (modules, errors, _) =
+        compiler::parse_fidls_sync(&args.files, &args.search_dirs, args.max_nesting, false, ParseMode::Strict, false);
+    match args.format {
+        #[cfg(feature = "serde")]
+        Format::Json => println!("{}", fipa::json::to_json(&modules, &errors)),
+        _ => for (module, file) in &modules {
+            println!("{}:\n{:#?}", file.display(), module);
+        },
+    }
+    report(&errors.iter().map(ToString::to_string).collect::<Vec<_>>())
+}
+
+/// Prints the files and the files they import transitively, each file after its imports.
+fn deps(args: &Args) -> bool {
+    let (_, errors, graph) =
+        compiler::parse_fidls_sync(&args.files, &args.search_dirs, args.max_nesting, true, ParseMode::Strict, true);
+    for file in graph.topological_order() {
+        println!("{}", file.display());
+    }
+    report(&errors.iter().map(ToString::to_string).collect::<Vec<_>>())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(line: &str) -> Result<Option<Args>, String> {
+        parse_args(line.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = args("check -I dir1 -Idir2 --strict a.fidl -- --b.fdepl").unwrap().unwrap();
+        assert_eq!(parsed.command, Command::Check);
+        assert_eq!(parsed.search_dirs, vec![PathBuf::from("dir1"), PathBuf::from("dir2")]);
+        assert_eq!(parsed.files, vec![PathBuf::from("a.fidl"), PathBuf::from("--b.fdepl")]);
+        assert!(parsed.strict);
+
+        let parsed = args("fmt --check --indent tab a.fidl").unwrap().unwrap();
+        assert!(parsed.check);
+        assert_eq!(parsed.indent, printer::Indent::Tab);
+
+        assert_eq!(args("--help"), Ok(None));
+        assert_eq!(args("dump a.fidl -h"), Ok(None));
+        assert_eq!(args("build a.fidl"), Err("unknown command `build`".to_string()));
+        assert_eq!(args("check --check a.fidl"), Err("unknown option `--check`".to_string()));
+        assert_eq!(args("fmt --indent"), Err("option `--indent` needs a value".to_string()));
+        assert_eq!(args("deps"), Err("no files given".to_string()));
+    }
+
+    #[test]
+    fn test_has_comments() {
+        assert!(has_comments("package a // comment"));
+        assert!(has_comments("package a /* comment */"));
+        assert!(!has_comments("package a import model \"http://x/y.fidl\" <** @see: //x **> typeCollection { }"));
+        assert!(!has_comments("package a import model 'a\"//b' interface I { }"));
+        assert!(!has_comments("package a import model \"unterminated //"));
+    }
+}
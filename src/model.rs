@@ -0,0 +1,627 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+//! Linked representation of a set of FIDL modules.
+//! All definitions are stored in arenas of the `Model` and referenced by `TypeId`, `InterfaceId`
+//! and `ConstantId` handles instead of names.
+
+use super::ast;
+use super::linker::{self, Definition, LinkError, Scope, SymbolTable};
+use std::collections::HashMap;
+use std::ops::Index;
+use std::path::{Path, PathBuf};
+
+/// Handle of a type in the `Model`
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct TypeId(usize);
+
+/// Handle of an interface in the `Model`
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct InterfaceId(usize);
+
+/// Handle of a constant in the `Model`
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ConstantId(usize);
+
+macro_rules! impl_index {
+    ($($id:ident),*) => {$(
+        impl $id {
+            /// Position of the definition in the model, definitions are numbered in the order
+            /// of the modules and their definitions.
+            pub fn index(self) -> usize {
+                self.0
+            }
+        }
+    )*};
+}
+
+impl_index!(TypeId, InterfaceId, ConstantId);
+
+/// Resolved type reference.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TypeRef {
+    /// Type defined in the model
+    Type(TypeId),
+
+    /// Name which could not be resolved to a type, the linker reports an error for it
+    Unresolved(String),
+    Undefined,
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Boolean,
+    String,
+    Float,
+    Double,
+    ByteBuffer,
+    IntegerInterval(Option<i128>, Option<i128>),
+}
+
+/// Struct or union field, method or broadcast argument.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Field {
+    pub annotation: Option<ast::AnnotationBlock>,
+    pub name: String,
+    pub type_ref: TypeRef,
+    pub array: bool,
+    pub span: ast::Span,
+}
+
+/// Method and broadcast arguments have the same properties as fields.
+pub type Argument = Field;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TypeKind {
+    TypeDef{ actual_type: TypeRef, array: bool },
+    Array{ element_type: TypeRef },
+    Struct{ base: Option<TypeId>, polymorphic: bool, fields: Vec<Field> },
+    Union{ base: Option<TypeId>, fields: Vec<Field> },
+    Map{ key_type: TypeRef, value_type: TypeRef },
+    Enumeration{ base: Option<TypeId>, enumerators: Vec<ast::Enumerator> },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Type {
+    /// Fully qualified name, e.g. `a.b.MyTypes.MyStruct`
+    pub fqn: String,
+    pub name: String,
+
+    /// Fully qualified name of the enclosing type collection or interface
+    pub container: String,
+
+    /// Enclosing interface, `None` for types of type collections
+    pub interface: Option<InterfaceId>,
+    pub annotation: Option<ast::AnnotationBlock>,
+    pub public: bool,
+    pub kind: TypeKind,
+
+    /// File the type is defined in
+    pub file: PathBuf,
+    pub span: ast::Span,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Constant {
+    pub fqn: String,
+    pub name: String,
+    pub container: String,
+    pub interface: Option<InterfaceId>,
+    pub annotation: Option<ast::AnnotationBlock>,
+    pub type_ref: TypeRef,
+    pub array: bool,
+    pub value: ast::InitializerExpression,
+    pub file: PathBuf,
+    pub span: ast::Span,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Attribute {
+    pub annotation: Option<ast::AnnotationBlock>,
+    pub name: String,
+    pub type_ref: TypeRef,
+    pub array: bool,
+    pub read_only: bool,
+    pub no_subscription: bool,
+    pub no_read: bool,
+    pub span: ast::Span,
+}
+
+/// Errors a method may return.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MethodError {
+    /// Reference to an enumeration (`error MyErrors`)
+    Reference(TypeRef),
+
+    /// Enumeration defined with the method (`error extends Base { A B }`)
+    Enumeration{ base: Option<TypeId>, enumerators: Vec<ast::Enumerator> },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Method {
+    pub annotation: Option<ast::AnnotationBlock>,
+    pub name: String,
+    pub selector: Option<String>,
+    pub fire_and_forget: bool,
+    pub in_args: Vec<Argument>,
+    pub out_args: Vec<Argument>,
+    pub error: Option<MethodError>,
+    pub span: ast::Span,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Broadcast {
+    pub annotation: Option<ast::AnnotationBlock>,
+    pub name: String,
+    pub selector: Option<String>,
+    pub selective: bool,
+    pub out_args: Vec<Argument>,
+    pub span: ast::Span,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Interface {
+    /// Fully qualified name, e.g. `a.b.MyInterface`
+    pub fqn: String,
+    pub name: String,
+    pub package: String,
+    pub annotation: Option<ast::AnnotationBlock>,
+    pub version: Option<(u32, u32)>,
+    pub base: Option<InterfaceId>,
+    pub manages: Vec<InterfaceId>,
+    pub attributes: Vec<Attribute>,
+    pub methods: Vec<Method>,
+    pub broadcasts: Vec<Broadcast>,
+    pub types: Vec<TypeId>,
+    pub constants: Vec<ConstantId>,
+    pub contract: Option<ast::Contract>,
+
+    /// File the interface is defined in
+    pub file: PathBuf,
+    pub span: ast::Span,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TypeCollection {
+    /// Fully qualified name, the package for anonymous type collections
+    pub fqn: String,
+    pub name: Option<String>,
+    pub package: String,
+    pub annotation: Option<ast::AnnotationBlock>,
+    pub version: Option<(u32, u32)>,
+    pub types: Vec<TypeId>,
+    pub constants: Vec<ConstantId>,
+    pub file: PathBuf,
+    pub span: ast::Span,
+}
+
+/// Linked model of a set of FIDL modules.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Model {
+    types: Vec<Type>,
+    interfaces: Vec<Interface>,
+    constants: Vec<Constant>,
+    type_collections: Vec<TypeCollection>,
+}
+
+impl Index<TypeId> for Model {
+    type Output = Type;
+    fn index(&self, id: TypeId) -> &Type {
+        &self.types[id.0]
+    }
+}
+
+impl Index<InterfaceId> for Model {
+    type Output = Interface;
+    fn index(&self, id: InterfaceId) -> &Interface {
+        &self.interfaces[id.0]
+    }
+}
+
+impl Index<ConstantId> for Model {
+    type Output = Constant;
+    fn index(&self, id: ConstantId) -> &Constant {
+        &self.constants[id.0]
+    }
+}
+
+impl Model {
+    /// Links the modules and builds the model. References which cannot be resolved are kept as
+    /// `TypeRef::Unresolved` or missing bases and reported as link errors.
+    pub fn build(modules: &[(ast::Module, PathBuf)]) -> (Model, Vec<LinkError>) {
+        let (table, errors) = linker::link(modules);
+        (Builder::new(&table).build(), errors)
+    }
+
+    pub fn types(&self) -> impl Iterator<Item = (TypeId, &Type)> {
+        self.types.iter().enumerate().map(|(i, t)| (TypeId(i), t))
+    }
+
+    pub fn interfaces(&self) -> impl Iterator<Item = (InterfaceId, &Interface)> {
+        self.interfaces.iter().enumerate().map(|(i, t)| (InterfaceId(i), t))
+    }
+
+    pub fn constants(&self) -> impl Iterator<Item = (ConstantId, &Constant)> {
+        self.constants.iter().enumerate().map(|(i, t)| (ConstantId(i), t))
+    }
+
+    pub fn type_collections(&self) -> &[TypeCollection] {
+        &self.type_collections
+    }
+
+    /// Finds a type by its fully qualified name.
+    pub fn find_type(&self, fqn: &str) -> Option<TypeId> {
+        self.types().find(|(_, t)| t.fqn == fqn).map(|(id, _)| id)
+    }
+
+    /// Finds an interface by its fully qualified name.
+    pub fn find_interface(&self, fqn: &str) -> Option<InterfaceId> {
+        self.interfaces().find(|(_, i)| i.fqn == fqn).map(|(id, _)| id)
+    }
+
+    /// Finds a constant by its fully qualified name.
+    pub fn find_constant(&self, fqn: &str) -> Option<ConstantId> {
+        self.constants().find(|(_, c)| c.fqn == fqn).map(|(id, _)| id)
+    }
+
+    /// The type followed by its bases (struct, union or enumeration base types), the root last.
+    /// The chain stops before a type repeats.
+    pub fn base_chain(&self, id: TypeId) -> Vec<TypeId> {
+        let mut chain = vec![id];
+        while let Some(base) = self.base_type(*chain.last().unwrap()) {
+            if chain.contains(&base) {
+                break;
+            }
+            chain.push(base);
+        }
+        chain
+    }
+
+    /// Base type of a struct, union or enumeration.
+    pub fn base_type(&self, id: TypeId) -> Option<TypeId> {
+        match &self[id].kind {
+            TypeKind::Struct{ base, .. } | TypeKind::Union{ base, .. } | TypeKind::Enumeration{ base, .. } => *base,
+            _ => None,
+        }
+    }
+
+    /// Fields of a struct or union including the inherited ones, fields of the root base first.
+    pub fn all_fields(&self, id: TypeId) -> Vec<&Field> {
+        self.base_chain(id).iter().rev()
+            .flat_map(|base| match &self[*base].kind {
+                TypeKind::Struct{ fields, .. } | TypeKind::Union{ fields, .. } => fields.as_slice(),
+                _ => &[],
+            })
+            .collect()
+    }
+
+    /// Enumerators of an enumeration including the inherited ones, enumerators of the root first.
+    pub fn all_enumerators(&self, id: TypeId) -> Vec<&ast::Enumerator> {
+        self.base_chain(id).iter().rev()
+            .flat_map(|base| match &self[*base].kind {
+                TypeKind::Enumeration{ enumerators, .. } => enumerators.as_slice(),
+                _ => &[],
+            })
+            .collect()
+    }
+
+    /// Follows typedefs until a type reference which is no typedef is reached. Typedef cycles
+    /// end with the first repeated typedef.
+    pub fn resolve_typedefs<'a>(&'a self, type_ref: &'a TypeRef) -> &'a TypeRef {
+        let mut current = type_ref;
+        let mut seen = Vec::new();
+        while let TypeRef::Type(id) = current {
+            match &self[*id].kind {
+                TypeKind::TypeDef{ actual_type, .. } if !seen.contains(id) => {
+                    seen.push(*id);
+                    current = actual_type;
+                },
+                _ => break,
+            }
+        }
+        current
+    }
+
+    /// The interface followed by its base interfaces, the root last.
+    pub fn interface_chain(&self, id: InterfaceId) -> Vec<InterfaceId> {
+        let mut chain = vec![id];
+        while let Some(base) = self[*chain.last().unwrap()].base {
+            if chain.contains(&base) {
+                break;
+            }
+            chain.push(base);
+        }
+        chain
+    }
+
+    /// Attributes of the interface including the inherited ones with the defining interface,
+    /// attributes of the root interface first.
+    pub fn all_attributes(&self, id: InterfaceId) -> Vec<(InterfaceId, &Attribute)> {
+        self.inherited(id, |i| &i.attributes)
+    }
+
+    /// Methods of the interface including the inherited ones, see `all_attributes`.
+    pub fn all_methods(&self, id: InterfaceId) -> Vec<(InterfaceId, &Method)> {
+        self.inherited(id, |i| &i.methods)
+    }
+
+    /// Broadcasts of the interface including the inherited ones, see `all_attributes`.
+    pub fn all_broadcasts(&self, id: InterfaceId) -> Vec<(InterfaceId, &Broadcast)> {
+        self.inherited(id, |i| &i.broadcasts)
+    }
+
+    fn inherited<T>(&self, id: InterfaceId, members: impl Fn(&Interface) -> &Vec<T>) -> Vec<(InterfaceId, &T)> {
+        self.interface_chain(id).iter().rev()
+            .flat_map(|i| members(&self[*i]).iter().map(move |m| (*i, m)))
+            .collect()
+    }
+}
+
+/// Builds the model from the symbol table of the linker.
+struct Builder<'t, 'a> {
+    table: &'t SymbolTable<'a>,
+    type_ids: HashMap<*const ast::Type, TypeId>,
+    interface_ids: HashMap<*const ast::Interface, InterfaceId>,
+    model: Model,
+}
+
+impl<'t, 'a> Builder<'t, 'a> {
+    fn new(table: &'t SymbolTable<'a>) -> Self {
+        let mut type_ids = HashMap::new();
+        let mut interface_ids = HashMap::new();
+        for (module, _) in table.modules() {
+            for interface in &module.interfaces {
+                let id = InterfaceId(interface_ids.len());
+                interface_ids.insert(interface as *const _, id);
+            }
+            let types = module.interfaces.iter().flat_map(|i| &i.types)
+                .chain(module.type_collections.iter().flat_map(|tc| &tc.types));
+            for tp in types {
+                let id = TypeId(type_ids.len());
+                type_ids.insert(tp as *const _, id);
+            }
+        }
+        Builder{ table, type_ids, interface_ids, model: Model::default() }
+    }
+
+    fn build(mut self) -> Model {
+        let modules = self.table.modules();
+        for (index, (module, file)) in modules.iter().enumerate() {
+            for interface in &module.interfaces {
+                self.interface(index, module, file, interface);
+            }
+        }
+        for (index, (module, file)) in modules.iter().enumerate() {
+            for interface in &module.interfaces {
+                let fqn = format!("{}.{}", module.package, interface.name);
+                let id = self.interface_ids[&(interface as *const _)];
+                let scope = Scope{ module: index, container: Some(fqn.clone()) };
+                let (types, constants) = self.members(&scope, &fqn, Some(id), file, &interface.types, &interface.constants);
+                self.model.interfaces[id.0].types = types;
+                self.model.interfaces[id.0].constants = constants;
+            }
+            for tc in &module.type_collections {
+                let fqn = match &tc.name {
+                    Some(name) => format!("{}.{}", module.package, name),
+                    None => module.package.clone(),
+                };
+                let scope = Scope{ module: index, container: Some(fqn.clone()) };
+                let (types, constants) = self.members(&scope, &fqn, None, file, &tc.types, &tc.constants);
+                self.model.type_collections.push(TypeCollection{ fqn, name: tc.name.clone(),
+                    package: module.package.clone(), annotation: tc.annotation.clone(), version: tc.version,
+                    types, constants, file: file.clone(), span: tc.span.clone() });
+            }
+        }
+        self.model
+    }
+
+    fn interface(&mut self, index: usize, module: &ast::Module, file: &Path, interface: &ast::Interface) {
+        let fqn = format!("{}.{}", module.package, interface.name);
+        let module_scope = Scope{ module: index, container: None };
+        let scope = Scope{ module: index, container: Some(fqn.clone()) };
+        let base = interface.extends.as_ref().and_then(|b| self.interface_id(&module_scope, b));
+        let manages = interface.manages.iter().flatten()
+            .filter_map(|m| self.interface_id(&module_scope, m))
+            .collect();
+        let attributes = interface.attributes.iter().map(|a| Attribute{
+            annotation: a.annotation.clone(), name: a.name.clone(), type_ref: self.type_ref(&scope, &a.type_ref),
+            array: a.array, read_only: a.read_only, no_subscription: a.no_subscription, no_read: a.no_read,
+            span: a.span.clone() }).collect();
+        let methods = interface.methods.iter().map(|m| Method{
+            annotation: m.annotation.clone(), name: m.name.clone(), selector: m.selector.clone(),
+            fire_and_forget: m.fire_and_forget, in_args: self.arguments(&scope, &m.in_args),
+            out_args: self.arguments(&scope, &m.out_args),
+            error: m.error.as_ref().map(|e| match e {
+                ast::MethodErrorSpec::Reference{ fqn, .. } =>
+                    MethodError::Reference(self.type_ref(&scope, &ast::TypeRef::Derived(fqn.clone()))),
+                ast::MethodErrorSpec::EnumerationBody{ extends, enumerators, .. } => MethodError::Enumeration{
+                    base: extends.as_ref().and_then(|b| self.type_id(&scope, b)),
+                    enumerators: enumerators.clone() },
+            }),
+            span: m.span.clone() }).collect();
+        let broadcasts = interface.broadcasts.iter().map(|b| Broadcast{
+            annotation: b.annotation.clone(), name: b.name.clone(), selector: b.selector.clone(),
+            selective: b.selective, out_args: self.arguments(&scope, &b.out_args), span: b.span.clone() }).collect();
+        self.model.interfaces.push(Interface{ fqn, name: interface.name.clone(), package: module.package.clone(),
+            annotation: interface.annotation.clone(), version: interface.version, base, manages, attributes,
+            methods, broadcasts, types: Vec::new(), constants: Vec::new(), contract: interface.contract.clone(),
+            file: file.to_path_buf(), span: interface.span.clone() });
+    }
+
+    fn members(&mut self, scope: &Scope, container: &str, interface: Option<InterfaceId>, file: &Path,
+               types: &[ast::Type], constants: &[ast::Constant]) -> (Vec<TypeId>, Vec<ConstantId>) {
+        let mut type_ids = Vec::new();
+        for tp in types {
+            type_ids.push(TypeId(self.model.types.len()));
+            let (annotation, public) = match tp {
+                ast::Type::TypeDef{ annotation, public, .. } | ast::Type::Array{ annotation, public, .. } |
+                ast::Type::Struct{ annotation, public, .. } | ast::Type::Union{ annotation, public, .. } |
+                ast::Type::Map{ annotation, public, .. } | ast::Type::Enumeration{ annotation, public, .. } =>
+                    (annotation.clone(), *public),
+            };
+            let kind = match tp {
+                ast::Type::TypeDef{ actual_type, array, .. } =>
+                    TypeKind::TypeDef{ actual_type: self.type_ref(scope, actual_type), array: *array },
+                ast::Type::Array{ element_type, .. } =>
+                    TypeKind::Array{ element_type: self.type_ref(scope, element_type) },
+                ast::Type::Struct{ extends, polymorphic, fields, .. } => TypeKind::Struct{
+                    base: extends.as_ref().and_then(|b| self.type_id(scope, &ast::TypeRef::Derived(b.clone()))),
+                    polymorphic: *polymorphic, fields: self.fields(scope, fields) },
+                ast::Type::Union{ base_type, fields, .. } => TypeKind::Union{
+                    base: base_type.as_ref().and_then(|b| self.type_id(scope, &ast::TypeRef::Derived(b.clone()))),
+                    fields: self.fields(scope, fields) },
+                ast::Type::Map{ key_type, value_type, .. } => TypeKind::Map{
+                    key_type: self.type_ref(scope, key_type), value_type: self.type_ref(scope, value_type) },
+                ast::Type::Enumeration{ base_type, enumerators, .. } => TypeKind::Enumeration{
+                    base: base_type.as_ref().and_then(|b| self.type_id(scope, b)),
+                    enumerators: enumerators.clone() },
+            };
+            self.model.types.push(Type{ fqn: format!("{}.{}", container, tp.name()), name: tp.name().to_string(),
+                container: container.to_string(), interface, annotation, public, kind, file: file.to_path_buf(), span: tp.span().clone() });
+        }
+        let mut constant_ids = Vec::new();
+        for constant in constants {
+            constant_ids.push(ConstantId(self.model.constants.len()));
+            self.model.constants.push(Constant{ fqn: format!("{}.{}", container, constant.name),
+                name: constant.name.clone(), container: container.to_string(), interface,
+                annotation: constant.annotation.clone(), type_ref: self.type_ref(scope, &constant.type_ref),
+                array: constant.array, value: constant.value.clone(), file: file.to_path_buf(), span: constant.span.clone() });
+        }
+        (type_ids, constant_ids)
+    }
+
+    fn fields(&self, scope: &Scope, fields: &[ast::Field]) -> Vec<Field> {
+        fields.iter().map(|f| Field{ annotation: f.annotation.clone(), name: f.name.clone(),
+            type_ref: self.type_ref(scope, &f.type_ref), array: f.array, span: f.span.clone() }).collect()
+    }
+
+    fn arguments(&self, scope: &Scope, args: &[ast::Argument]) -> Vec<Argument> {
+        args.iter().map(|a| Field{ annotation: a.annotation.clone(), name: a.name.clone(),
+            type_ref: self.type_ref(scope, &a.type_ref), array: a.array, span: a.span.clone() }).collect()
+    }
+
+    fn type_id(&self, scope: &Scope, type_ref: &ast::TypeRef) -> Option<TypeId> {
+        match self.type_ref(scope, type_ref) {
+            TypeRef::Type(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    fn interface_id(&self, scope: &Scope, name: &str) -> Option<InterfaceId> {
+        match self.table.resolve(scope, name).map(|s| s.definition) {
+            Ok(Definition::Interface(interface)) => self.interface_ids.get(&(interface as *const _)).copied(),
+            _ => None,
+        }
+    }
+
+    fn type_ref(&self, scope: &Scope, type_ref: &ast::TypeRef) -> TypeRef {
+        match type_ref {
+            ast::TypeRef::Derived(name) => match self.table.resolve(scope, name).map(|s| s.definition) {
+                Ok(Definition::Type(tp)) => TypeRef::Type(self.type_ids[&(tp as *const _)]),
+                _ => TypeRef::Unresolved(name.clone()),
+            },
+            ast::TypeRef::Undefined => TypeRef::Undefined,
+            ast::TypeRef::Int8 => TypeRef::Int8,
+            ast::TypeRef::UInt8 => TypeRef::UInt8,
+            ast::TypeRef::Int16 => TypeRef::Int16,
+            ast::TypeRef::UInt16 => TypeRef::UInt16,
+            ast::TypeRef::Int32 => TypeRef::Int32,
+            ast::TypeRef::UInt32 => TypeRef::UInt32,
+            ast::TypeRef::Int64 => TypeRef::Int64,
+            ast::TypeRef::UInt64 => TypeRef::UInt64,
+            ast::TypeRef::Boolean => TypeRef::Boolean,
+            ast::TypeRef::String => TypeRef::String,
+            ast::TypeRef::Float => TypeRef::Float,
+            ast::TypeRef::Double => TypeRef::Double,
+            ast::TypeRef::ByteBuffer => TypeRef::ByteBuffer,
+            ast::TypeRef::IntegerInterval(lower, upper) => TypeRef::IntegerInterval(*lower, *upper),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser;
+
+    fn model(texts: &[&str]) -> (Model, Vec<LinkError>) {
+        let modules: Vec<_> = texts.iter()
+            .map(|text| (parser::parse_module(text).unwrap().1, PathBuf::from("test.fidl")))
+            .collect();
+        Model::build(&modules)
+    }
+
+    #[test]
+    fn test_types() {
+        let (model, errors) = model(&["package a
+            typeCollection T {
+                struct Base { UInt8 x }
+                struct Derived extends Base { Id y  Derived[] children }
+                typedef Id is Alias
+                typedef Alias is UInt32
+                enumeration E1 { A B }
+                enumeration E2 extends E1 { C }
+                map M { Id to Missing }
+            }"]);
+        assert_eq!(errors.len(), 1);
+        let derived = model.find_type("a.T.Derived").unwrap();
+        let base = model.find_type("a.T.Base").unwrap();
+        assert_eq!(model[derived].container, "a.T");
+        assert_eq!(model.base_chain(derived), vec![derived, base]);
+        assert_eq!(model.all_fields(derived).iter().map(|f| f.name.as_str()).collect::<Vec<_>>(),
+                   vec!["x", "y", "children"]);
+        let id = model.find_type("a.T.Id").unwrap();
+        assert_eq!(model.all_fields(derived)[1].type_ref, TypeRef::Type(id));
+        assert_eq!(model.all_fields(derived)[2].type_ref, TypeRef::Type(derived));
+        assert_eq!(model.resolve_typedefs(&TypeRef::Type(id)), &TypeRef::UInt32);
+
+        let e2 = model.find_type("a.T.E2").unwrap();
+        assert_eq!(model.all_enumerators(e2).iter().map(|e| e.name.as_str()).collect::<Vec<_>>(),
+                   vec!["A", "B", "C"]);
+        match &model[model.find_type("a.T.M").unwrap()].kind {
+            TypeKind::Map{ key_type, value_type } => {
+                assert_eq!(key_type, &TypeRef::Type(id));
+                assert_eq!(value_type, &TypeRef::Unresolved("Missing".to_string()));
+            },
+            k => panic!("unexpected kind {:?}", k),
+        }
+        assert_eq!(model.type_collections()[0].types.len(), 7);
+    }
+
+    #[test]
+    fn test_interfaces() {
+        let (model, errors) = model(&[
+            "package a\ninterface Root { attribute UInt8 r  method reset { } }
+             interface Middle extends Root { struct S { } attribute S m  broadcast changed { } }",
+            "package b\nimport a.* from \"a.fidl\"
+             interface Leaf extends Middle manages Root {
+                 attribute Middle.S l
+                 method get { out { Middle.S s } error { FAILED } }
+                 const UInt8 MAX = 10
+             }"]);
+        assert!(errors.is_empty(), "{:?}", errors);
+        let root = model.find_interface("a.Root").unwrap();
+        let middle = model.find_interface("a.Middle").unwrap();
+        let leaf = model.find_interface("b.Leaf").unwrap();
+        assert_eq!(model.interface_chain(leaf), vec![leaf, middle, root]);
+        assert_eq!(model[leaf].manages, vec![root]);
+        assert_eq!(model.all_attributes(leaf).iter().map(|(i, a)| (*i, a.name.as_str())).collect::<Vec<_>>(),
+                   vec![(root, "r"), (middle, "m"), (leaf, "l")]);
+        assert_eq!(model.all_methods(leaf).len(), 2);
+        assert_eq!(model.all_broadcasts(leaf)[0].0, middle);
+
+        let s = model.find_type("a.Middle.S").unwrap();
+        assert_eq!(model[s].interface, Some(middle));
+        assert_eq!(model[leaf].methods[0].out_args[0].type_ref, TypeRef::Type(s));
+        let max = model.find_constant("b.Leaf.MAX").unwrap();
+        assert_eq!(model[leaf].constants, vec![max]);
+        assert_eq!(model[max].type_ref, TypeRef::UInt8);
+    }
+}
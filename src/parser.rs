@@ -6,38 +6,77 @@
 
 use nom::{
     IResult,
-    combinator::{recognize, map},
+    combinator::{recognize, map, map_res, not, cut},
     sequence::{tuple, pair, preceded},
     branch::{alt},
     bytes::complete::{tag, take_while, take, take_until, take_while1},
-    character::complete::{multispace0, multispace1, char, digit1, hex_digit1, alpha1,
-                        alphanumeric1},
-    multi::{fold_many0, fold_many1, many0}
+    character::complete::{char, digit1, hex_digit1, alpha1, alphanumeric1},
+    multi::{fold_many0, fold_many1, many0, separated_list0},
+    error::{context, ErrorKind, VerboseError, VerboseErrorKind},
+    Slice,
 };
-use super::util::{option, keyword};
+use nom_locate::LocatedSpan;
+use super::util::{option, keyword, ws0, ws1};
 use std::str::FromStr;
+use std::path::Path;
+use std::sync::Arc;
 use nom::sequence::terminated;
 
 use super::ast;
+use super::diagnostic::Diagnostic;
+
+/// Parser input: the FIDL text with position tracking and the optional file it was read from.
+pub type Input<'a> = LocatedSpan<&'a str, Option<&'a Arc<Path>>>;
+
+/// Result of the parser functions operating on `Input`. Errors keep the stack of contexts
+/// (e.g. "interface", "method") the parser was in.
+pub type PResult<'a, O> = IResult<Input<'a>, O, VerboseError<Input<'a>>>;
+
+/// Creates the source location of the text between `start` and `end` where `end` is a suffix of
+/// `start`. Trailing whitespace is not part of the span.
+fn span_between(start: &Input, end: &Input) -> ast::Span {
+    let consumed = &start.fragment()[..end.location_offset() - start.location_offset()];
+    ast::Span {
+        file: start.extra.cloned(),
+        offset: start.location_offset(),
+        len: consumed.trim_end().len(),
+        line: start.location_line(),
+        column: start.get_utf8_column(),
+    }
+}
+
+/// Applies `parser` after skipping leading whitespace and comments and returns its output together
+/// with the source location of the recognized text.
+pub(crate) fn located<'a, O, F>(mut parser: F) -> impl FnMut(Input<'a>) -> PResult<'a, (O, ast::Span)>
+    where F: FnMut(Input<'a>) -> PResult<'a, O>
+{
+    move |input: Input<'a>| {
+        let (start, _) = ws0(input)?;
+        let (r, v) = parser(start)?;
+        let span = span_between(&start, &r);
+        Ok((r, (v, span)))
+    }
+}
 
 /// 'package' name=FQN
-fn parse_package(input: &str) -> IResult<&str, String> {
-    let (r, v) = tuple((keyword("package"), parse_fqn, multispace0))(input)?;
+pub(crate) fn parse_package(input: Input) -> PResult<String> {
+    let (r, v) = tuple((keyword("package"), parse_fqn, ws0))(input)?;
     Ok((r, v.1.to_string()))
 }
 
 /// Parse string for a FRANCA identifier which is an XTEXT ID token.
 /// XTEXT: terminal ID: ('^')?('a'..'z'|'A'..'Z'|'_') ('a'..'z'|'A'..'Z'|'_'|'0'..'9')*;
-fn parse_identifier(input: &str) -> IResult<&str, &str> {
+pub(crate) fn parse_identifier(input: Input) -> PResult<Input> {
     recognize(
         pair(
-            alt((alpha1, tag("_"), tag("^"))),
+            // alpha1 last so that a failure is reported as missing identifier
+            alt((tag("_"), tag("^"), alpha1)),
             many0(alt((alphanumeric1, tag("_"))))
         ))(input)
 }
 
-fn quoted_string(c: char) -> impl Fn(&str) -> nom::IResult<&str, String> {
-    move |i: &str| {
+fn quoted_string(c: char) -> impl Fn(Input) -> PResult<String> {
+    move |i: Input| {
         let (r, v) = nom::sequence::tuple(
             ( char(c), take_while(|ch: char| { ch != c})))(i)?;
         let (r1, _) = take(1usize)(r)?;
@@ -45,52 +84,102 @@ fn quoted_string(c: char) -> impl Fn(&str) -> nom::IResult<&str, String> {
     }
 }
 
-fn parse_string(input: &str) -> IResult<&str, String> {
+pub(crate) fn parse_string(input: Input) -> PResult<String> {
     nom::branch::alt((quoted_string('"'), quoted_string('\'') ))(input)
 }
 
-fn parse_fqn(input: &str) -> IResult<&str, &str> {
+pub(crate) fn parse_fqn(input: Input) -> PResult<Input> {
     recognize(pair( parse_identifier, many0( pair(tag("."), parse_identifier) ))) (input)
 }
 
-fn parse_import_model(input: &str) -> IResult<&str, ast::Import> {
-    let (r, v) = tuple(( keyword("import"),  keyword("model"), parse_string ))(input)?;
-    Ok((r, ast::Import{ uri: v.2.to_string(), namespace: "".to_string()}))
+fn parse_import_model(input: Input) -> PResult<ast::Import> {
+    let (r, (v, span)) = located(tuple(( keyword("import"),  keyword("model"), cut(parse_string) )))(input)?;
+    Ok((r, ast::Import{ uri: v.2.to_string(), namespace: "".to_string(), span}))
 }
 
-fn parse_imported_fqn(input: &str) -> IResult<&str, &str> {
+fn parse_imported_fqn(input: Input) -> PResult<Input> {
     terminated(
         recognize(pair(parse_fqn, option( tag(".*")))),
-        multispace0)(input)
+        ws0)(input)
 }
 
-fn parse_import_from(input: &str) -> IResult<&str, ast::Import> {
-    let (r, v) = tuple((
-        keyword("import"), parse_imported_fqn, keyword("from"), parse_string,
-    ))(input)?;
-    Ok((r, ast::Import{ uri: v.3.to_string(), namespace: v.1.to_string()}))
+fn parse_import_from(input: Input) -> PResult<ast::Import> {
+    let (r, (v, span)) = located(tuple((
+        keyword("import"), parse_imported_fqn, keyword("from"), cut(parse_string),
+    )))(input)?;
+    Ok((r, ast::Import{ uri: v.3.to_string(), namespace: v.1.to_string(), span}))
 }
 
-fn parse_import(input: &str) -> IResult<&str, ast::Import> {
+pub(crate) fn parse_import(input: Input) -> PResult<ast::Import> {
     alt((parse_import_from, parse_import_model))(input)
 }
 
-fn parse_annotation(input: &str) -> IResult<&str, Option<String>> {
-    match nom::sequence::tuple((tag("<**"), take_until("**>"), tag("**>"), multispace0,
-    ))(input) as IResult<&str, (&str, &str, &str, &str)> {
-        Ok((r, v))  => Ok((r, Some(v.1.to_string()))),
+fn parse_annotation(input: Input) -> PResult<Option<ast::AnnotationBlock>> {
+    match nom::sequence::tuple((tag("<**"), take_until("**>"), tag("**>"), ws0,
+    ))(input) as PResult<(Input, Input, Input, Input)> {
+        Ok((r, v))  => Ok((r, Some(ast::AnnotationBlock::from(*v.1.fragment())))),
         Err(_)                => Ok((input, None))
     }
 }
 
-fn parse_version(input: &str) -> IResult<&str, Option<(u32, u32)>> {
-    match nom::sequence::tuple((
-        keyword("version"), tag("{"), keyword("major"), digit1, multispace1,
-        keyword("minor"), digit1, multispace0, tag("}"), multispace0
-    ))(input) as IResult<&str, (&str, &str, &str, &str, &str, &str, &str, &str, &str, &str)>  {
-        Ok((r, v)) => Ok((r, Some(( v.3.parse::<u32>().unwrap(), v.6.parse::<u32>().unwrap())))),
-        Err(_) => Ok((input, None))
+/// '@' (('a'..'z')|'-')+ (' '|'\t')* ':'
+pub(crate) fn parse_annotation_tag(input: &str) -> IResult<&str, &str> {
+    let (r, v) = tuple((
+        char('@'), take_while1(|c: char| c.is_ascii_lowercase() || c == '-'),
+        take_while(|c: char| c == ' ' || c == '\t'), char(':')
+    ))(input)?;
+    Ok((r, v.1))
+}
+
+/// Takes the annotation text up to the next tag and resolves the escape sequences
+/// `\b \t \n \f \r \uXXXX \" \' \\ \* \@`. Unknown escape sequences are kept unchanged.
+pub(crate) fn parse_annotation_text(input: &str) -> (&str, String) {
+    let mut text = String::new();
+    let mut rest = input;
+    while !rest.is_empty() && parse_annotation_tag(rest).is_err() {
+        let mut chars = rest.chars();
+        let c = chars.next().unwrap_or_default();
+        if c != '\\' {
+            text.push(c);
+            rest = chars.as_str();
+            continue;
+        }
+        let escaped = match chars.next() {
+            Some('b') => Some('\u{8}'),
+            Some('t') => Some('\t'),
+            Some('n') => Some('\n'),
+            Some('f') => Some('\u{c}'),
+            Some('r') => Some('\r'),
+            Some('u') => {
+                let unicode = chars.as_str().get(..4)
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .and_then(char::from_u32);
+                if unicode.is_some() {
+                    chars = chars.as_str()[4..].chars();
+                }
+                unicode
+            },
+            Some(e) if "\"'\\*@".contains(e) => Some(e),
+            _ => None,
+        };
+        match escaped {
+            Some(e) => { text.push(e); rest = chars.as_str(); },
+            None => { text.push(c); rest = &rest[1..]; },
+        }
     }
+    (rest, text.trim().to_string())
+}
+
+/// Major or minor version number, values beyond `u32` are rejected.
+fn parse_version_number(input: Input) -> PResult<u32> {
+    map_res(digit1, |d: Input| u32::from_str(d.fragment()))(input)
+}
+
+fn parse_version(input: Input) -> PResult<Option<(u32, u32)>> {
+    option(context("version", preceded(keyword("version"), cut(map(tuple((
+        tag("{"), keyword("major"), parse_version_number, ws1,
+        keyword("minor"), parse_version_number, ws0, tag("}"), ws0
+    )), |v| (v.2, v.5))))))(input)
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -104,14 +193,39 @@ enum InterfaceContent {
     Attribute(ast::Attribute),
     Method(ast::Method),
     Broadcast(ast::Broadcast),
-   // Constant(),
+    Constant(ast::Constant),
     Type(ast::Type),
+    Contract(ast::Contract),
 }
 
-fn parse_type_ref(input: &str) -> IResult<&str, ast::TypeRef> {
-    // todo IntegerInterval not recognized
+/// INTERVAL_BOUND: '-'? INT
+fn parse_interval_bound(input: Input) -> PResult<i128> {
+    map_res(recognize(pair(option(char('-')), digit1)), |b: Input| i128::from_str(b.fragment()))(input)
+}
+
+/// FIntegerInterval: 'Integer' ('(' (lowerBound=INTERVAL_BOUND | 'minInt') ','
+///     (upperBound=INTERVAL_BOUND | 'maxInt') ')')?
+/// The 'Integer' keyword itself has already been consumed.
+fn parse_integer_interval(input: Input) -> PResult<ast::TypeRef> {
+    let (r, v) = option(preceded(pair(ws0, char('(')), cut(tuple((
+        ws0,
+        alt((map(tag("minInt"), |_| None), map(parse_interval_bound, Some))), ws0,
+        char(','), ws0,
+        alt((map(tag("maxInt"), |_| None), map(parse_interval_bound, Some))), ws0,
+        char(')')
+    )))))(input)?;
+    Ok(match v {
+        Some(bounds) => (r, ast::TypeRef::IntegerInterval(bounds.1, bounds.5)),
+        None => (r, ast::TypeRef::IntegerInterval(None, None)),
+    })
+}
+
+fn parse_type_ref(input: Input) -> PResult<ast::TypeRef> {
     let (r, v) = parse_fqn(input)?;
-    let tr = match v {
+    if *v.fragment() == "Integer" {
+        return parse_integer_interval(r);
+    }
+    let tr = match *v.fragment() {
         "undefined" => ast::TypeRef::Undefined,
         "Int8" => ast::TypeRef::Int8,
         "UInt8" => ast::TypeRef::UInt8,
@@ -131,21 +245,21 @@ fn parse_type_ref(input: &str) -> IResult<&str, ast::TypeRef> {
     Ok((r, tr))
 }
 
-fn parse_array_specifier(input: &str) -> IResult<&str, bool> {
-    let (r, v) = option(tuple((tag("["), multispace0, tag("]"), multispace0)))
+fn parse_array_specifier(input: Input) -> PResult<bool> {
+    let (r, v) = option(tuple((char('['), ws0, char(']'), ws0)))
         (input)?;
     Ok((r, v.is_some()))
 }
 
-fn parse_attribute(input: &str) -> IResult<&str, InterfaceContent> {
-    let (r, v) = tuple((
-        multispace0,
-        parse_annotation, keyword("attribute"), parse_type_ref, multispace0, parse_array_specifier, multispace0,
-        parse_identifier, multispace0,
+fn parse_attribute(input: Input) -> PResult<InterfaceContent> {
+    let (r, (v, span)) = located(context("attribute", tuple((
+        ws0,
+        parse_annotation, keyword("attribute"), cut(tuple((parse_type_ref, ws0, parse_array_specifier, ws0,
+        parse_identifier, ws0))),
         fold_many0( alt((keyword("readonly"), keyword("noRead"), keyword("noSubscription") )),
             || (false, false, false), |mut sp, v| {
                 // println!("sp {:?} ({})", sp, v);
-                match v {
+                match *v.fragment() {
                     "readonly" => sp.0 = true,
                     "noRead" => sp.1 = true,
                     "noSubscription" => sp.2 = true,
@@ -153,64 +267,424 @@ fn parse_attribute(input: &str) -> IResult<&str, InterfaceContent> {
                 }
                 // println!(" =>sp {:?}", sp);
                 sp
-            } ), multispace0
-    ))(input)?;
+            } ), ws0
+    ))))(input)?;
     Ok((r, InterfaceContent::Attribute(ast::Attribute {
-        annotation: v.1, name: v.7.to_string(), array: v.5, type_ref: v.3, read_only: v.9.0,
-        no_subscription: v.9.2, no_read: v.9.1 })))
+        annotation: v.1, name: v.3.4.to_string(), array: v.3.2, type_ref: v.3.0, read_only: v.4.0,
+        no_subscription: v.4.2, no_read: v.4.1, span })))
 }
 
-fn parse_cs_fqn_list(input: &str) -> IResult<&str, Vec<String>> {
-    let p = fold_many0( tuple((tag(","), multispace0, parse_fqn, multispace0)), || vec!["".to_string()],
+fn parse_cs_fqn_list(input: Input) -> PResult<Vec<String>> {
+    let p = fold_many0( tuple((char(','), ws0, parse_fqn, ws0)), || vec!["".to_string()],
         |mut v, item| { v.push(item.2.to_string()); v });
 
-    nom::combinator::map( tuple((parse_fqn, multispace0, p)),
+    nom::combinator::map( tuple((parse_fqn, ws0, p)),
         |mut item|{ item.2[0] = item.0.to_string(); item.2}) (input)
 }
 
-fn parse_interface(input: &str) -> IResult<&str, ModuleContent> {
-    let (r, v) = nom::sequence::tuple((
-        parse_annotation, keyword("interface"), parse_identifier, multispace0,
-        option( map(preceded(keyword("extends"), parse_fqn), |r| {r.to_string()})), multispace0,
-        option( preceded(keyword("manages"), parse_cs_fqn_list)), multispace0,
-        tag("{"), multispace0, parse_version, multispace0,
-        fold_many0( alt((parse_attribute, parse_type_interf, parse_broadcast, parse_method)),
-                    || (Vec::new(), Vec::new(), Vec::new(), Vec::new()),
-            |(mut attrs, mut types, mut brdcsts, mut mthds), v | {
-                match v {
-                    InterfaceContent::Attribute(attr) => attrs.push(attr),
-                    InterfaceContent::Type(tp) => types.push(tp),
-                    InterfaceContent::Broadcast(bc) => brdcsts.push(bc),
-                    InterfaceContent::Method(mth) => mthds.push(mth)
-                }
-                (attrs, types, brdcsts, mthds)
-            }),
-        multispace0, tag("}"), multispace0
+/// Interface up to and including the opening brace and the version. The returned interface has
+/// no members yet.
+fn parse_interface_header(input: Input) -> PResult<ast::Interface> {
+    let (r, v) = tuple((
+        parse_annotation, keyword("interface"), cut(tuple((parse_identifier, ws0,
+        option( map(preceded(keyword("extends"), parse_fqn), |r| {r.to_string()})), ws0,
+        option( preceded(keyword("manages"), parse_cs_fqn_list)), ws0,
+        char('{'), ws0, parse_version, ws0)))
     ))(input)?;
-    Ok((r, ModuleContent::Interface(
-        ast::Interface{ annotation: v.0, name: v.2.to_string(), version: v.10, attributes: v.12.0,
-            types: v.12.1, broadcasts: v.12.2, methods: v.12.3, extends: v.4, manages: v.6 })))
-}
-
-fn parse_type_collection(input: &str) -> IResult<&str, ModuleContent> {
-    let (r, v) = nom::sequence::tuple((
-        parse_annotation, keyword("typeCollection"),
-        option(parse_identifier), multispace0, tag("{"), multispace0, parse_version, multispace0,
-        fold_many0(parse_type, || Vec::new(),
-        |mut vec, item| { vec.push(item); vec }),
-        multispace0, tag("}"), multispace0)
-    )(input)?;
-    let name = if let Some(str_name) = v.2 { Some(str_name.to_string())} else {None};
-    Ok((r, ModuleContent::TypeCollection(
-        ast:: TypeCollection{ annotation: v.0, name, version: v.6, types: v.8 })))
+    let (annotation, v) = (v.0, v.2);
+    Ok((r, ast::Interface{ annotation, name: v.0.to_string(), version: v.8, attributes: Vec::new(),
+        types: Vec::new(), broadcasts: Vec::new(), methods: Vec::new(), constants: Vec::new(),
+        contract: None, extends: v.2, manages: v.4, span: ast::Span::default() }))
+}
+
+fn parse_interface_member(input: Input) -> PResult<InterfaceContent> {
+    alt((parse_attribute, parse_type_interf, parse_broadcast, parse_method, parse_constant_interf))(input)
+}
+
+/// Parses interface members in the order `parse_interface` accepts them: members, an optional
+/// contract, then types and constants only. Anything else after the contract fails, so error
+/// recovery reports a second or misplaced contract like a strict parse.
+fn ordered_interface_member<'a>() -> impl FnMut(Input<'a>) -> PResult<'a, InterfaceContent> {
+    let mut after_contract = false;
+    move |input: Input<'a>| {
+        if after_contract {
+            return alt((parse_type_interf, parse_constant_interf))(input);
+        }
+        let (r, member) = alt((parse_interface_member, map(parse_contract, InterfaceContent::Contract)))(input)?;
+        after_contract = matches!(member, InterfaceContent::Contract(_));
+        Ok((r, member))
+    }
+}
+
+fn add_interface_member(interface: &mut ast::Interface, member: InterfaceContent) {
+    match member {
+        InterfaceContent::Attribute(attr) => interface.attributes.push(attr),
+        InterfaceContent::Type(tp) => interface.types.push(tp),
+        InterfaceContent::Broadcast(bc) => interface.broadcasts.push(bc),
+        InterfaceContent::Method(mth) => interface.methods.push(mth),
+        InterfaceContent::Constant(cnst) => interface.constants.push(cnst),
+        InterfaceContent::Contract(cntrct) => interface.contract = Some(cntrct),
+    }
 }
 
+fn parse_interface(input: Input) -> PResult<ModuleContent> {
+    let (r, ((mut interface, v), span)) = located(context("interface", pair(
+        parse_interface_header, cut(tuple((
+        many0(parse_interface_member),
+        option(parse_contract),
+        // constant and type definitions after the contract are allowed for backward compatibility
+        many0(alt((parse_type_interf, parse_constant_interf))),
+        ws0, char('}'), ws0)))
+    )))(input)?;
+    for member in v.0.into_iter().chain(v.2) {
+        add_interface_member(&mut interface, member);
+    }
+    interface.contract = v.1;
+    interface.span = span;
+    Ok((r, ModuleContent::Interface(interface)))
+}
+
+/// Type collection up to and including the opening brace and the version. The returned type
+/// collection has no members yet.
+fn parse_type_collection_header(input: Input) -> PResult<ast::TypeCollection> {
+    let (r, v) = tuple((
+        parse_annotation, keyword("typeCollection"), cut(tuple((
+        option(parse_identifier), ws0, char('{'), ws0, parse_version, ws0)))
+    ))(input)?;
+    let (annotation, v) = (v.0, v.2);
+    let name = v.0.map(|str_name| str_name.to_string());
+    Ok((r, ast::TypeCollection{ annotation, name, version: v.4, types: Vec::new(),
+        constants: Vec::new(), span: ast::Span::default() }))
+}
+
+fn parse_type_collection_member(input: Input) -> PResult<InterfaceContent> {
+    alt((parse_type_interf, parse_constant_interf))(input)
+}
+
+fn add_type_collection_member(type_collection: &mut ast::TypeCollection, member: InterfaceContent) {
+    match member {
+        InterfaceContent::Type(tp) => type_collection.types.push(tp),
+        InterfaceContent::Constant(cnst) => type_collection.constants.push(cnst),
+        _ => {},
+    }
+}
+
+fn parse_type_collection(input: Input) -> PResult<ModuleContent> {
+    let (r, ((mut type_collection, v), span)) = located(context("typeCollection", pair(
+        parse_type_collection_header, cut(tuple((
+        many0(parse_type_collection_member),
+        ws0, char('}'), ws0)))
+    )))(input)?;
+    for member in v.0 {
+        add_type_collection_member(&mut type_collection, member);
+    }
+    type_collection.span = span;
+    Ok((r, ModuleContent::TypeCollection(type_collection)))
+}
+
+/// Parses a FIDL module from a string. Spans of the resulting AST carry no file.
+/// Parsing stops in front of text which does not start an `interface` or `typeCollection`, a syntax
+/// error within a definition fails the whole parse. See `parse_fidl` for strict and lenient parsing.
 pub fn parse_module(input: &str) -> IResult<&str, ast::Module> {
-    let (r, v) = nom::sequence::tuple((
-        multispace0, parse_package, multispace0,
-        fold_many0(pair(parse_import, multispace0), || Vec::new(),
+    match parse_module_input(Input::new_extra(input, None)) {
+        Ok((r, m)) => Ok((*r.fragment(), m)),
+        Err(e) => Err(e.map(simple_error)),
+    }
+}
+
+/// How `parse_fidl` treats text following the last definition of a module.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParseMode {
+    /// The whole text must be a module, any remaining input is a syntax error.
+    Strict,
+
+    /// Parsing stops silently in front of the first definition with a syntax error (or text which
+    /// is no definition), the definitions before it and the unparsed rest are returned.
+    Lenient,
+}
+
+/// Parses the FIDL text read from `file` and returns the unparsed rest together with the module.
+/// In `ParseMode::Strict` the rest is always empty, in `ParseMode::Lenient` only errors in the
+/// package and import declarations fail.
+/// Syntax errors are reported as diagnostics with location and context.
+pub fn parse_fidl<'a>(text: &'a str, file: Option<&'a Arc<Path>>, mode: ParseMode)
+        -> Result<(&'a str, ast::Module), Box<Diagnostic>> {
+    if mode == ParseMode::Lenient {
+        return parse_fidl_lenient(text, file);
+    }
+    let input = Input::new_extra(text, file);
+    match parse_module_input(input) {
+        Ok((r, _)) if !r.fragment().is_empty() =>
+            Err(Box::new(Diagnostic::expected(&r, "`interface`, `typeCollection` or end of file"))),
+        Ok((r, m)) => Ok((*r.fragment(), m)),
+        Err(e) => Err(Box::new(diagnostic(e, input))),
+    }
+}
+
+/// Parses with error recovery and keeps the declarations and definitions which end in front of
+/// the first error. The rest starts with the first definition dropped or at the error.
+fn parse_fidl_lenient<'a>(text: &'a str, file: Option<&'a Arc<Path>>)
+        -> Result<(&'a str, ast::Module), Box<Diagnostic>> {
+    let (mut module, diagnostics, header_errors) = recover_module(text, file);
+    let Some(error) = diagnostics.into_iter().next() else {
+        return Ok(("", module));
+    };
+    if header_errors > 0 {
+        return Err(Box::new(error));
+    }
+    let before_error = |span: &ast::Span| span.offset + span.len <= error.span.offset;
+    let stop = module.interfaces.iter().map(|i| &i.span)
+        .chain(module.type_collections.iter().map(|t| &t.span))
+        .filter(|span| !before_error(span))
+        .map(|span| span.offset)
+        .fold(error.span.offset, usize::min);
+    module.interfaces.retain(|i| before_error(&i.span));
+    module.type_collections.retain(|t| before_error(&t.span));
+    module.span.len = text[module.span.offset..stop].trim_end().len();
+    Ok((&text[stop..], module))
+}
+
+/// Keywords starting a definition within a module.
+const MODULE_KEYWORDS: [&str; 2] = ["interface", "typeCollection"];
+
+/// Keywords starting a definition or a member of an interface or type collection.
+const MEMBER_KEYWORDS: [&str; 14] = ["interface", "typeCollection", "attribute", "method", "broadcast",
+    "const", "contract", "public", "typedef", "array", "struct", "union", "map", "enumeration"];
+
+/// Parses the FIDL text read from `file` and continues after syntax errors.
+/// After a broken definition parsing resumes at the next `interface` or `typeCollection`, after a
+/// broken member at the next member keyword (`method`, `attribute`, `broadcast`, type keywords...).
+/// Returns the module with all definitions that could be parsed and a diagnostic for each error.
+pub fn parse_fidl_recovering<'a>(text: &'a str, file: Option<&'a Arc<Path>>)
+        -> (ast::Module, Vec<Diagnostic>) {
+    let (module, diagnostics, _) = recover_module(text, file);
+    (module, diagnostics)
+}
+
+/// Same as `parse_fidl_recovering`, also returns the number of diagnostics for the package and
+/// import declarations, which come first.
+fn recover_module<'a>(text: &'a str, file: Option<&'a Arc<Path>>) -> (ast::Module, Vec<Diagnostic>, usize) {
+    let mut diagnostics = Vec::new();
+    let start = skip_ws(Input::new_extra(text, file));
+
+    let mut rest = start;
+    let package = match parse_package(rest) {
+        Ok((r, package)) => { rest = r; package },
+        Err(e) => {
+            diagnostics.push(diagnostic(e, rest));
+            if !starts_with_keyword(&rest, &["import", "interface", "typeCollection"]) {
+                rest = skip_to_keyword(rest, &["import", "interface", "typeCollection"]);
+            }
+            String::new()
+        }
+    };
+
+    let mut imports = Vec::new();
+    loop {
+        match pair(parse_import, ws0)(rest) {
+            Ok((r, (import, _))) => { imports.push(import); rest = r; },
+            Err(nom::Err::Error(_)) => break,
+            Err(e) => {
+                diagnostics.push(diagnostic(e, rest));
+                rest = skip_to_keyword(rest, &["import", "interface", "typeCollection"]);
+            }
+        }
+    }
+
+    let header_errors = diagnostics.len();
+    let mut interfaces = Vec::new();
+    let mut type_collections = Vec::new();
+    while !rest.fragment().is_empty() {
+        let word = preceded(parse_annotation, alpha1)(rest).map(|(_, w): (_, Input)| *w.fragment());
+        match word {
+            Ok("interface") => {
+                let (r, interface) = recover_definition(rest, "interface", parse_interface_header,
+                    ordered_interface_member(), add_interface_member, &mut diagnostics);
+                interfaces.extend(interface);
+                rest = r;
+            },
+            Ok("typeCollection") => {
+                let (r, type_collection) = recover_definition(rest, "typeCollection",
+                    parse_type_collection_header, parse_type_collection_member,
+                    add_type_collection_member, &mut diagnostics);
+                type_collections.extend(type_collection);
+                rest = r;
+            },
+            _ => {
+                diagnostics.push(Diagnostic::expected(&rest, "`interface`, `typeCollection` or end of file"));
+                rest = skip_to_keyword(rest, &MODULE_KEYWORDS);
+            },
+        }
+    }
+    let module = ast::Module{ package, imports, interfaces, type_collections, span: span_between(&start, &rest) };
+    (module, diagnostics, header_errors)
+}
+
+/// Parses an interface or type collection (`kind`) with the given header and member parsers.
+/// Broken members are reported and skipped up to the next member keyword. Returns the rest of the
+/// input and the definition if at least its header could be parsed.
+fn recover_definition<'a, T, H, M>(input: Input<'a>, kind: &'static str, header: H, mut member: M,
+                                   add: fn(&mut T, InterfaceContent), diagnostics: &mut Vec<Diagnostic>)
+        -> (Input<'a>, Option<T>)
+    where H: FnMut(Input<'a>) -> PResult<'a, T>,
+          M: FnMut(Input<'a>) -> PResult<'a, InterfaceContent>,
+          T: Located
+{
+    let start = skip_ws(input);
+    let (mut rest, mut definition) = match context(kind, header)(start) {
+        Ok(v) => v,
+        Err(e) => {
+            diagnostics.push(diagnostic(e, start));
+            return (skip_to_keyword(start, &MODULE_KEYWORDS), None);
+        }
+    };
+    let mut resynchronized = false;
+    loop {
+        rest = skip_ws(rest);
+        if let Ok((r, _)) = pair(char('}'), ws0)(rest) as PResult<(char, Input)> {
+            rest = r;
+            break;
+        }
+        if rest.fragment().is_empty() || starts_with_keyword(&rest, &MODULE_KEYWORDS) {
+            // the closing brace may have been skipped while resynchronizing
+            if !resynchronized {
+                diagnostics.push(Diagnostic::from_error(&VerboseError{ errors: vec![
+                    (rest, VerboseErrorKind::Char('}')), (start, VerboseErrorKind::Context(kind))] }));
+            }
+            break;
+        }
+        match member(rest) {
+            Ok((r, m)) => {
+                add(&mut definition, m);
+                rest = r;
+                resynchronized = false;
+            },
+            Err(e) => {
+                let mut error = match e {
+                    nom::Err::Failure(error) => error,
+                    _ => VerboseError{ errors: vec![(rest, VerboseErrorKind::Char('}'))] },
+                };
+                error.errors.push((start, VerboseErrorKind::Context(kind)));
+                diagnostics.push(Diagnostic::from_error(&error));
+                rest = skip_to_keyword(rest, &MEMBER_KEYWORDS);
+                resynchronized = true;
+            }
+        }
+    }
+    definition.set_span(span_between(&start, &rest));
+    (rest, Some(definition))
+}
+
+/// Definitions whose span is set after parsing their members.
+trait Located {
+    fn set_span(&mut self, span: ast::Span);
+}
+
+impl Located for ast::Interface {
+    fn set_span(&mut self, span: ast::Span) {
+        self.span = span;
+    }
+}
+
+impl Located for ast::TypeCollection {
+    fn set_span(&mut self, span: ast::Span) {
+        self.span = span;
+    }
+}
+
+fn skip_ws(input: Input) -> Input {
+    ws0(input).map_or(input, |(r, _)| r)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Whether `input` starts with one of `keywords` as a complete word.
+fn starts_with_keyword(input: &Input, keywords: &[&str]) -> bool {
+    let text = *input.fragment();
+    let word = &text[..text.find(|c| !is_word_char(c)).unwrap_or(text.len())];
+    keywords.contains(&word)
+}
+
+/// Skips the first token of `input` and everything up to the next of `keywords` which is not
+/// within a comment, an annotation or a string. Returns the empty rest if there is none.
+fn skip_to_keyword<'a>(input: Input<'a>, keywords: &[&str]) -> Input<'a> {
+    let text = *input.fragment();
+    let mut pos = match text.chars().next() {
+        Some(c) if is_word_char(c) => text.find(|c| !is_word_char(c)).unwrap_or(text.len()),
+        Some(c) => c.len_utf8(),
+        None => 0,
+    };
+    while pos < text.len() {
+        let rest = &text[pos..];
+        let first = rest.chars().next().unwrap_or_default();
+        pos += if rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else if rest.starts_with("/*") {
+            rest.find("*/").map_or(rest.len(), |p| p + 2)
+        } else if rest.starts_with("<**") {
+            rest.find("**>").map_or(rest.len(), |p| p + 3)
+        } else if first == '"' || first == '\'' {
+            rest[1..].find(first).map_or(rest.len(), |p| p + 2)
+        } else if is_word_char(first) {
+            let len = rest.find(|c| !is_word_char(c)).unwrap_or(rest.len());
+            if keywords.contains(&&rest[..len]) {
+                break;
+            }
+            len
+        } else {
+            first.len_utf8()
+        };
+    }
+    input.slice(pos.min(text.len())..)
+}
+
+/// Converts the error of a failed parser which was applied to `input`.
+pub(crate) fn diagnostic<'a>(error: nom::Err<VerboseError<Input<'a>>>, input: Input<'a>) -> Diagnostic {
+    diagnostic_with(error, input, crate::diagnostic::describe_context)
+}
+
+/// Like `diagnostic` with parser contexts described by `describe`, see `Diagnostic::from_error_with`.
+pub(crate) fn diagnostic_with<'a>(error: nom::Err<VerboseError<Input<'a>>>, input: Input<'a>,
+                                  describe: fn(Input, &str) -> String) -> Diagnostic {
+    match error {
+        nom::Err::Error(e) | nom::Err::Failure(e) => Diagnostic::from_error_with(&e, describe),
+        nom::Err::Incomplete(_) => Diagnostic::from_error_with(&VerboseError{
+            errors: vec![(input.slice(input.fragment().len()..), VerboseErrorKind::Nom(ErrorKind::Eof))] }, describe),
+    }
+}
+
+/// Reduces a verbose error to the innermost error without context.
+fn simple_error(error: VerboseError<Input<'_>>) -> nom::error::Error<&str> {
+    match error.errors.first() {
+        Some((input, VerboseErrorKind::Nom(kind))) => nom::error::Error::new(*input.fragment(), *kind),
+        Some((input, VerboseErrorKind::Char(_))) => nom::error::Error::new(*input.fragment(), ErrorKind::Char),
+        Some((input, VerboseErrorKind::Context(_))) => nom::error::Error::new(*input.fragment(), ErrorKind::Fail),
+        None => nom::error::Error::new("", ErrorKind::Fail),
+    }
+}
+
+/// Returns the name of the definition introduced by `kind` (e.g. "interface" or "const") at
+/// the beginning of `input`, if any.
+pub(crate) fn definition_name(input: Input, kind: &str) -> Option<String> {
+    let (r, _) = tuple((ws0, parse_annotation, option(keyword("public")), keyword(kind)))(input).ok()?;
+    let name = match kind {
+        // attributes and constants start with the type
+        "const" | "attribute" => tuple((parse_type_ref, ws0, parse_array_specifier, parse_identifier))(r)
+            .ok()?.1.3,
+        _ => parse_identifier(r).ok()?.1,
+    };
+    Some(name.to_string())
+}
+
+/// Parses a FIDL module from located input, spans refer to the file given as extra information.
+pub fn parse_module_input(input: Input) -> PResult<ast::Module> {
+    let (r, (v, span)) = located(nom::sequence::tuple((
+        ws0, parse_package, ws0,
+        fold_many0(pair(parse_import, ws0), Vec::new,
                    |mut imports: Vec<_>, item|{ imports.push(item.0); imports} ),
-        multispace0,
+        ws0,
         fold_many0(alt((parse_interface, parse_type_collection)), || (Vec::new(), Vec::new()) ,
                    | (mut intfs_vec, mut tc_vec), item| {
                        match item {
@@ -219,239 +693,672 @@ pub fn parse_module(input: &str) -> IResult<&str, ast::Module> {
                        };
                        (intfs_vec, tc_vec)
                    }),
-        multispace0
-    ))(input)?;
-    Ok((r, ast::Module{ package: v.1, imports: v.3, interfaces: v.5.0, type_collections: v.5.1}))
+        ws0
+    )))(input)?;
+    Ok((r, ast::Module{ package: v.1, imports: v.3, interfaces: v.5.0, type_collections: v.5.1, span}))
 }
 
-fn parse_field(input: &str) -> IResult<&str, ast::Field> {
-    let (r, v) = tuple((
-        parse_annotation, multispace0, parse_type_ref, multispace0, parse_array_specifier,
-        parse_identifier, multispace0
-    ))(input)?;
+fn parse_field(input: Input) -> PResult<ast::Field> {
+    let (r, (v, span)) = located(tuple((
+        parse_annotation, ws0, parse_type_ref, ws0, parse_array_specifier,
+        parse_identifier, ws0
+    )))(input)?;
     Ok((r, ast::Field{
-        annotation: v.0, array: v.4, name: v.5.to_string(), type_ref: v.2 }))
+        annotation: v.0, array: v.4, name: v.5.to_string(), type_ref: v.2, span }))
 }
 
-fn parse_argument(input: &str) -> IResult<&str, ast::Argument> {
-    let (r, v) = tuple((
-        parse_annotation, multispace0, parse_type_ref, multispace0, parse_array_specifier,
-        parse_identifier, multispace0
-    ))(input)?;
+fn parse_argument(input: Input) -> PResult<ast::Argument> {
+    let (r, (v, span)) = located(tuple((
+        parse_annotation, ws0, parse_type_ref, ws0, parse_array_specifier,
+        parse_identifier, ws0
+    )))(input)?;
     Ok((r, ast::Argument {
-        annotation: v.0, array: v.4, name: v.5.to_string(), type_ref: v.2 }))
+        annotation: v.0, array: v.4, name: v.5.to_string(), type_ref: v.2, span }))
 }
 
 
-fn parse_typedef(input: &str) -> IResult<&str, ast::Type> {
-    let (r, v) = tuple((
-        parse_annotation, option(keyword("public")), keyword("typedef"),
-        parse_identifier, keyword("is"), parse_type_ref, multispace0,
-        parse_array_specifier, multispace0
-    ))(input)?;
-    Ok((r, ast::Type::TypeDef { annotation: v.0, public: v.1.is_some(), name: v.3.to_string(),
-        actual_type: v.5, array: v.7 }))
+fn parse_typedef(input: Input) -> PResult<ast::Type> {
+    let (r, (v, span)) = located(context("typedef", tuple((
+        parse_annotation, option(keyword("public")), keyword("typedef"), cut(tuple((
+        parse_identifier, keyword("is"), parse_type_ref, ws0,
+        parse_array_specifier, ws0)))
+    ))))(input)?;
+    Ok((r, ast::Type::TypeDef { annotation: v.0, public: v.1.is_some(), name: v.3.0.to_string(),
+        actual_type: v.3.2, array: v.3.4, span }))
 }
 
-fn parse_array_type(input: &str) -> IResult<&str, ast::Type> {
-    let (r, v) = tuple((
-        parse_annotation, option(keyword("public")), keyword("array"),
-        parse_identifier, keyword("of"), parse_type_ref, multispace0
-    ))(input)?;
-    Ok((r, ast::Type::Array { annotation: v.0, public: v.1.is_some(), name: v.3.to_string(),
-        element_type: v.5 }))
+fn parse_array_type(input: Input) -> PResult<ast::Type> {
+    let (r, (v, span)) = located(context("array", tuple((
+        parse_annotation, option(keyword("public")), keyword("array"), cut(tuple((
+        parse_identifier, keyword("of"), parse_type_ref, ws0)))
+    ))))(input)?;
+    Ok((r, ast::Type::Array { annotation: v.0, public: v.1.is_some(), name: v.3.0.to_string(),
+        element_type: v.3.2, span }))
 }
 
-fn parse_struct_type(input: &str) -> IResult<&str, ast::Type> {
-    let (r, v) = tuple((
-        parse_annotation, option(keyword("public")), keyword("struct"), parse_identifier, multispace0,
-        option( tuple((keyword("extends"), multispace0, parse_fqn))), multispace0,
-        option(keyword("polymorphic")), tag("{"), multispace0,
-        fold_many0(parse_field, || Vec::new(),
+fn parse_struct_type(input: Input) -> PResult<ast::Type> {
+    let (r, (v, span)) = located(context("struct", tuple((
+        parse_annotation, option(keyword("public")), keyword("struct"), cut(tuple((parse_identifier, ws0,
+        option( tuple((keyword("extends"), ws0, parse_fqn))), ws0,
+        option(keyword("polymorphic")), char('{'), ws0,
+        fold_many0(parse_field, Vec::new,
             |mut vec, field | { vec.push(field); vec}),
-        tag("}"), multispace0
-    ))(input)?;
-    let extend_fqn = if let Some(ex) = v.5 { Some(ex.2.to_string()) } else { None };
+        char('}'), ws0)))
+    ))))(input)?;
+    let (annotation, public, v) = (v.0, v.1, v.3);
+    let extend_fqn = v.2.map(|ex| ex.2.to_string());
     Ok((r, ast::Type::Struct {
-        annotation: v.0, public: v.1.is_some(), name: v.3.to_string(), polymorphic: v.7.is_some(),
-        extends: extend_fqn, fields: v.10 }))
+        annotation, public: public.is_some(), name: v.0.to_string(), polymorphic: v.4.is_some(),
+        extends: extend_fqn, fields: v.7, span }))
 }
 
-fn parse_union_type(input: &str) -> IResult<&str, ast::Type> {
-    let (r, v) = tuple ((
-        parse_annotation, option(keyword("public")), keyword("union"), parse_identifier, multispace0,
-        option(tuple((keyword("extends"), multispace0, parse_fqn))), multispace0,
-        tag("{"), multispace0,
-        fold_many0(parse_field, || Vec::new(),
+fn parse_union_type(input: Input) -> PResult<ast::Type> {
+    let (r, (v, span)) = located(context("union", tuple((
+        parse_annotation, option(keyword("public")), keyword("union"), cut(tuple((parse_identifier, ws0,
+        option(tuple((keyword("extends"), ws0, parse_fqn))), ws0,
+        char('{'), ws0,
+        fold_many0(parse_field, Vec::new,
                    |mut vec, field | { vec.push(field); vec}),
-        tag("}"), multispace0
-    ))(input)?;
-    let base = if let Some(ex) = v.5 { Some(ex.2.to_string()) } else { None };
-    Ok((r, ast::Type::Union { annotation: v.0, public: v.1.is_some(), name: v.3.to_string(),
-        base_type: base, fields: v.9 }))
+        char('}'), ws0)))
+    ))))(input)?;
+    let base = v.3.2.map(|ex| ex.2.to_string());
+    Ok((r, ast::Type::Union { annotation: v.0, public: v.1.is_some(), name: v.3.0.to_string(),
+        base_type: base, fields: v.3.6, span }))
 }
 
-fn parse_map_type(input: &str) -> IResult<&str, ast::Type> {
-    let (r, v) = tuple((
-        parse_annotation, option(keyword("public")), keyword("map"),
-        parse_identifier, multispace0, tag("{"), multispace0, parse_type_ref,
-        keyword("to"), parse_type_ref, multispace0, tag("}"), multispace0
-    ))(input)?;
-    Ok((r, ast::Type::Map { annotation: v.0, public: v.1.is_some(), name: v.3.to_string(),
-        key_type: v.7, value_type: v.9 }))
+fn parse_map_type(input: Input) -> PResult<ast::Type> {
+    let (r, (v, span)) = located(context("map", tuple((
+        parse_annotation, option(keyword("public")), keyword("map"), cut(tuple((
+        parse_identifier, ws0, char('{'), ws0, parse_type_ref,
+        keyword("to"), parse_type_ref, ws0, char('}'), ws0)))
+    ))))(input)?;
+    Ok((r, ast::Type::Map { annotation: v.0, public: v.1.is_some(), name: v.3.0.to_string(),
+        key_type: v.3.4, value_type: v.3.6, span }))
 }
 
-fn parse_integer_decimal(input: &str) -> IResult<&str, u64> {
-    let (r, v) = tuple( (digit1, multispace0) )(input)?;
-    Ok((r, u64::from_str(v.0).unwrap()))
+fn parse_integer_decimal(input: Input) -> PResult<u64> {
+    let (r, v) = tuple( (map_res(digit1, |d: Input| u64::from_str(d.fragment())), ws0) )(input)?;
+    Ok((r, v.0))
 }
 
-fn parse_integer_hex(input: &str) -> IResult<&str, u64> {
-    let (r, v) = tuple( (alt((tag("0x"), tag("0X"))), hex_digit1, multispace0))(input) ?;
-    Ok((r, u64::from_str_radix(v.1, 16).unwrap()))
+fn parse_integer_hex(input: Input) -> PResult<u64> {
+    let (r, v) = tuple( (alt((tag("0x"), tag("0X"))),
+                         map_res(hex_digit1, |h: Input| u64::from_str_radix(h.fragment(), 16)), ws0))(input) ?;
+    Ok((r, v.1))
 }
 
-fn parse_integer_bin(input: &str) -> IResult<&str, u64> {
+fn parse_integer_bin(input: Input) -> PResult<u64> {
     let is_bin_digit = |c: char| { c == '0' || c == '1'};
-    let (r, v) = tuple((alt((tag("0b"), tag("0B"))), take_while1(is_bin_digit),
-        multispace0))(input)?;
-    Ok((r, u64::from_str_radix(v.1, 2).unwrap()))
+    let (r, v) = tuple((alt((tag("0b"), tag("0B"))),
+                        map_res(take_while1(is_bin_digit), |b: Input| u64::from_str_radix(b.fragment(), 2)),
+                        ws0))(input)?;
+    Ok((r, v.1))
 }
 
-fn parse_integer(input: &str) -> IResult<&str, u64> {
+pub(crate) fn parse_integer(input: Input) -> PResult<u64> {
     let (r, v) = alt((parse_integer_hex, parse_integer_bin, parse_integer_decimal))(input)?;
     Ok((r, v))
 }
 
-fn parse_enumerator(input: &str) -> IResult<&str, ast::Enumerator> {
-    let (r, v) = tuple((parse_annotation, multispace0, parse_identifier, multispace0,
-        option( tuple(( tag("="), multispace0, parse_integer)) ), multispace0
-    ))(input)?;
-    let value = if let Some(val) = v.4 { Some(val.2) } else { None };
-    Ok((r, ast::Enumerator{ annotation: v.0, name: v.2.to_string(), val: value }))
+fn parse_enumerator(input: Input) -> PResult<ast::Enumerator> {
+    let (r, (v, span)) = located(tuple((parse_annotation, ws0, parse_identifier, ws0,
+        option( tuple(( char('='), ws0, parse_integer)) ), ws0
+    )))(input)?;
+    let value = v.4.map(|val| val.2);
+    Ok((r, ast::Enumerator{ annotation: v.0, name: v.2.to_string(), val: value, span }))
 }
 
-fn parse_enumeration(input: &str) -> IResult<&str, ast::Type> {
-    let (r, v) = tuple((
-        parse_annotation, option(keyword("public")), keyword("enumeration"),
-        parse_identifier, multispace0,
-        option(tuple((keyword("extends"), multispace0, parse_type_ref))), multispace0,
-        tag("{"), multispace0,
-        fold_many1( tuple((parse_enumerator, option(tag(","))) ) , || Vec::new(),
+fn parse_enumeration(input: Input) -> PResult<ast::Type> {
+    let (r, (v, span)) = located(context("enumeration", tuple((
+        parse_annotation, option(keyword("public")), keyword("enumeration"), cut(tuple((
+        parse_identifier, ws0,
+        option(tuple((keyword("extends"), ws0, parse_type_ref))), ws0,
+        char('{'), ws0,
+        fold_many1( tuple((parse_enumerator, option(char(','))) ) , Vec::new,
             |mut vec, item| {
                 vec.push(item.0);
                 vec }),
-        multispace0, tag("}"), multispace0
-    ))(input)?;
-    let extension = if let Some(ex) = v.5 {Some(ex.2)} else {None};
+        ws0, char('}'), ws0)))
+    ))))(input)?;
+    let (annotation, public, v) = (v.0, v.1, v.3);
+    let extension = if let Some(ex) = v.2 {Some(ex.2)} else {None};
     Ok((r, ast::Type::Enumeration {
-        annotation: v.0, public: v.1.is_some(), name: v.3.to_string(), base_type: extension,
-        enumerators: v.9 }))
+        annotation, public: public.is_some(), name: v.0.to_string(), base_type: extension,
+        enumerators: v.6, span }))
 }
 
-fn parse_error_enum_body(input: &str) -> IResult<&str, ast::MethodErrorSpec> {
+fn parse_error_enum_body(input: Input) -> PResult<ast::MethodErrorSpec> {
     let (r, v) = tuple((
         parse_annotation, keyword("error"),
-        option(tuple((keyword("extends"), multispace0, parse_type_ref))), multispace0,
-        tag("{"), multispace0,
-        fold_many1( tuple((parse_enumerator, option(tag(","))) ) , || Vec::new(),
+        option(tuple((keyword("extends"), ws0, parse_type_ref))), ws0,
+        char('{'), ws0,
+        fold_many1( tuple((parse_enumerator, option(char(','))) ) , Vec::new,
                     |mut vec, item| {
                         vec.push(item.0);
                         vec }),
-        multispace0, tag("}"), multispace0
+        ws0, char('}'), ws0
     ))(input)?;
     let extension = if let Some(ex) = v.2 {Some(ex.2)} else {None};
     Ok((r, ast::MethodErrorSpec::EnumerationBody { annotation: v.0, extends: extension,
         enumerators: v.6 }))
 }
 
-fn parse_error_ref(input: &str) -> IResult<&str, ast::MethodErrorSpec> {
+fn parse_error_ref(input: Input) -> PResult<ast::MethodErrorSpec> {
     let (r, v) = tuple((
-        parse_annotation, keyword("error"), parse_fqn, multispace0))(input)?;
+        parse_annotation, keyword("error"), parse_fqn, ws0))(input)?;
     Ok((r, ast::MethodErrorSpec::Reference {annotation: v.0, fqn: v.2.to_string() }))
 }
 
-fn parse_method(input: &str) -> IResult<&str, InterfaceContent> {
-    let (r, v) = tuple((
-        parse_annotation, keyword("method"), parse_identifier, multispace0,
-        option(tuple(( tag(":"), multispace0, parse_identifier))), multispace0,
-        option(keyword("fireAndForget")), tag("{"), multispace0,
-        option(tuple ((keyword("in"), tag("{"), multispace0, parse_argument_list, multispace0, tag("}"), multispace0))),
-        option(tuple ((keyword("out"), tag("{"), multispace0, parse_argument_list, multispace0, tag("}"), multispace0))),
-        option(alt((parse_error_ref, parse_error_enum_body))),
-        multispace0, tag("}"), multispace0
-    ))(input)?;
-    let slctr = if let Some(s) = v.4 { Some(s.2.to_string()) } else { None };
-    let in_args = if let Some(ag) = v.9 { ag.3 } else { Vec::new() };
-    let out_args = if let Some(ag) = v.10 { ag.3 } else { Vec::new() };
+fn parse_method(input: Input) -> PResult<InterfaceContent> {
+    let (r, (v, span)) = located(context("method", tuple((
+        parse_annotation, keyword("method"), cut(tuple((parse_identifier, ws0,
+        option(tuple(( char(':'), ws0, parse_identifier))), ws0,
+        option(keyword("fireAndForget")), char('{'), ws0,
+        option(tuple ((keyword("in"), char('{'), ws0, parse_argument_list, ws0, char('}'), ws0))),
+        option(tuple ((keyword("out"), char('{'), ws0, parse_argument_list, ws0, char('}'), ws0))),
+        option(alt((parse_error_enum_body, parse_error_ref))),
+        ws0, char('}'), ws0)))
+    ))))(input)?;
+    let (annotation, v) = (v.0, v.2);
+    let slctr = v.2.map(|s| s.2.to_string());
+    let in_args = if let Some(ag) = v.7 { ag.3 } else { Vec::new() };
+    let out_args = if let Some(ag) = v.8 { ag.3 } else { Vec::new() };
 
     Ok((r, InterfaceContent::Method( ast::Method {
-        annotation: v.0, name: v.2.to_string(), selector: slctr, fire_and_forget: v.6.is_some(),
-        in_args, out_args, error: v.11 })))
+        annotation, name: v.0.to_string(), selector: slctr, fire_and_forget: v.4.is_some(),
+        in_args, out_args, error: v.9, span })))
 }
 
-fn parse_type(input: &str) ->  IResult<&str, ast::Type> {
+fn parse_type(input: Input) -> PResult<ast::Type> {
     alt((parse_typedef, parse_array_type, parse_struct_type, parse_union_type, parse_map_type,
         parse_enumeration))(input)
 }
 
-fn parse_type_interf(input: &str) -> IResult<&str, InterfaceContent> {
+fn parse_type_interf(input: Input) -> PResult<InterfaceContent> {
     let (r, v) = parse_type(input)?;
     Ok((r, InterfaceContent::Type(v)))
 }
 
-fn parse_argument_list(input: &str) -> IResult<&str, Vec<ast::Argument>> {
-    fold_many0(tuple((parse_argument, multispace0 )), || Vec::new(),
+fn parse_argument_list(input: Input) -> PResult<Vec<ast::Argument>> {
+    fold_many0(tuple((parse_argument, ws0 )), Vec::new,
         |mut vec, item| { vec.push(item.0); vec })(input)
 }
 
-fn parse_broadcast(input: &str) -> IResult<&str, InterfaceContent> {
+fn parse_broadcast(input: Input) -> PResult<InterfaceContent> {
+    let (r, (v, span)) = located(context("broadcast", tuple((
+        parse_annotation, keyword("broadcast"), cut(tuple((ws0, parse_identifier, ws0,
+        option(tuple(( char(':'), ws0, parse_identifier))), ws0,
+        option(tuple((keyword("selective"), ws0))), char('{'), ws0,
+        option(tuple ((keyword("out"), ws0, char('{'), ws0, parse_argument_list, ws0, char('}'), ws0))),
+        ws0, char('}'), ws0)))
+    ))))(input)?;
+    let (annotation, v) = (v.0, v.2);
+    let slctr = v.3.map(|slc| slc.2.to_string());
+    let args = if let Some(ag) = v.8 { ag.4 } else { Vec::new() };
+    Ok((r, InterfaceContent::Broadcast(ast::Broadcast{
+        annotation, name: v.1.to_string(), selector: slctr, selective: v.5.is_some(), out_args: args,
+        span })))
+}
+
+fn parse_constant(input: Input) -> PResult<ast::Constant> {
+    let (r, (v, span)) = located(context("const", tuple((
+        parse_annotation, keyword("const"), cut(tuple((parse_type_ref, ws0, parse_array_specifier,
+        parse_identifier, ws0, char('='), ws0, parse_initializer_expression)))
+    ))))(input)?;
+    let (annotation, v) = (v.0, v.2);
+    Ok((r, ast::Constant { annotation, type_ref: v.0, array: v.2, name: v.3.to_string(),
+        value: v.7, span }))
+}
+
+fn parse_constant_interf(input: Input) -> PResult<InterfaceContent> {
+    let (r, v) = parse_constant(input)?;
+    Ok((r, InterfaceContent::Constant(v)))
+}
+
+/// FInitializerExpression: LogicalOrExpression | FCompoundInitializer | FBracketInitializer
+fn parse_initializer_expression(input: Input) -> PResult<ast::InitializerExpression> {
+    alt((
+        parse_compound_initializer, parse_bracket_initializer,
+        map(parse_logical_or_expression, ast::InitializerExpression::Expression)
+    ))(input)
+}
+
+fn parse_compound_initializer(input: Input) -> PResult<ast::InitializerExpression> {
     let (r, v) = tuple((
-        parse_annotation, keyword("broadcast"), multispace0, parse_identifier, multispace0,
-        option(tuple(( tag(":"), multispace0, parse_identifier))), multispace0,
-        option(tuple((keyword("selective"), multispace0))), tag("{"), multispace0,
-        option(tuple ((keyword("out"), multispace0, tag("{"), multispace0, parse_argument_list, multispace0, tag("}"), multispace0))),
-        multispace0, tag("}"), multispace0
+        char('{'), ws0,
+        separated_list0(pair(char(','), ws0), parse_field_initializer),
+        char('}'), ws0
     ))(input)?;
-    let slctr = if let Some(slc) = v.5 { Some(slc.2.to_string()) } else { None };
-    let args = if let Some(ag) = v.10 { ag.4 } else { Vec::new() };
-    Ok((r, InterfaceContent::Broadcast(ast::Broadcast{
-        annotation: v.0, name: v.3.to_string(), selector: slctr, selective: v.7.is_some(), out_args: args
-    })))
+    Ok((r, ast::InitializerExpression::Compound(v.2)))
+}
+
+fn parse_field_initializer(input: Input) -> PResult<ast::FieldInitializer> {
+    let (r, v) = tuple((
+        parse_identifier, ws0, char(':'), ws0, parse_initializer_expression
+    ))(input)?;
+    Ok((r, ast::FieldInitializer{ element: v.0.to_string(), value: v.4 }))
+}
+
+fn parse_bracket_initializer(input: Input) -> PResult<ast::InitializerExpression> {
+    let (r, v) = tuple((
+        char('['), ws0,
+        separated_list0(pair(char(','), ws0), parse_element_initializer),
+        char(']'), ws0
+    ))(input)?;
+    Ok((r, ast::InitializerExpression::Bracket(v.2)))
+}
+
+fn parse_element_initializer(input: Input) -> PResult<ast::ElementInitializer> {
+    let (r, v) = pair(
+        parse_initializer_expression,
+        option(preceded(pair(tag("=>"), ws0), parse_initializer_expression))
+    )(input)?;
+    Ok((r, ast::ElementInitializer{ first: v.0, second: v.1 }))
+}
+
+/// Parses the operator symbol `op` followed by optional whitespace.
+fn operator(op: &'static str, value: ast::Operator) -> impl Fn(Input) -> PResult<ast::Operator> {
+    move |i: Input| {
+        let (r, _) = terminated(tag(op), ws0)(i)?;
+        Ok((r, value))
+    }
+}
+
+/// The minus operator must not be confused with the '->' of a qualified element reference.
+fn minus_operator(input: Input) -> PResult<ast::Operator> {
+    let (r, _) = tuple((tag("-"), not(char('>')), ws0))(input)?;
+    Ok((r, ast::Operator::Subtraction))
+}
+
+/// Builds the left-associative binary operation tree `((first op1 e1) op2 e2) ...`
+fn fold_binary(first: ast::Expression, rest: Vec<(ast::Operator, ast::Expression)>) -> ast::Expression {
+    rest.into_iter().fold(first, |left, (op, right)| {
+        ast::Expression::Binary { left: Box::new(left), op, right: Box::new(right) }
+    })
+}
+
+fn parse_logical_or_expression(input: Input) -> PResult<ast::Expression> {
+    let (r, v) = pair(parse_logical_and_expression,
+        many0(pair(operator("||", ast::Operator::Or), parse_logical_and_expression)))(input)?;
+    Ok((r, fold_binary(v.0, v.1)))
+}
+
+fn parse_logical_and_expression(input: Input) -> PResult<ast::Expression> {
+    let (r, v) = pair(parse_equality_expression,
+        many0(pair(operator("&&", ast::Operator::And), parse_equality_expression)))(input)?;
+    Ok((r, fold_binary(v.0, v.1)))
+}
+
+fn parse_equality_expression(input: Input) -> PResult<ast::Expression> {
+    let (r, v) = pair(parse_relational_expression,
+        many0(pair(
+            alt((operator("==", ast::Operator::Equal), operator("!=", ast::Operator::Unequal))),
+            parse_relational_expression)))(input)?;
+    Ok((r, fold_binary(v.0, v.1)))
+}
+
+fn parse_relational_expression(input: Input) -> PResult<ast::Expression> {
+    let (r, v) = pair(parse_additive_expression,
+        many0(pair(
+            alt((operator("<=", ast::Operator::SmallerOrEqual), operator(">=", ast::Operator::GreaterOrEqual),
+                 operator("<", ast::Operator::Smaller), operator(">", ast::Operator::Greater))),
+            parse_additive_expression)))(input)?;
+    Ok((r, fold_binary(v.0, v.1)))
+}
+
+fn parse_additive_expression(input: Input) -> PResult<ast::Expression> {
+    let (r, v) = pair(parse_multiplicative_expression,
+        many0(pair(
+            alt((operator("+", ast::Operator::Addition), minus_operator)),
+            parse_multiplicative_expression)))(input)?;
+    Ok((r, fold_binary(v.0, v.1)))
+}
+
+fn parse_multiplicative_expression(input: Input) -> PResult<ast::Expression> {
+    let (r, v) = pair(parse_primary_expression,
+        many0(pair(
+            alt((operator("*", ast::Operator::Multiplication), operator("/", ast::Operator::Division))),
+            parse_primary_expression)))(input)?;
+    Ok((r, fold_binary(v.0, v.1)))
+}
+
+/// PrimaryExpression: FUnaryOperation | SimplePrimaryExpression
+fn parse_primary_expression(input: Input) -> PResult<ast::Expression> {
+    alt((parse_unary_operation, parse_simple_primary_expression))(input)
+}
+
+fn parse_unary_operation(input: Input) -> PResult<ast::Expression> {
+    let (r, v) = pair(
+        alt((operator("!", ast::Operator::Negation), minus_operator)),
+        parse_simple_primary_expression)(input)?;
+    Ok((r, ast::Expression::Unary { op: v.0, operand: Box::new(v.1) }))
+}
+
+/// SimplePrimaryExpression: FQualifiedElementRef | FCurrentError | FMethodErrorEnumRef | FConstant |
+///     '(' LogicalOrExpression ')'
+fn parse_simple_primary_expression(input: Input) -> PResult<ast::Expression> {
+    alt((
+        map(tuple((char('('), ws0, parse_logical_or_expression, char(')'), ws0)), |v| v.2),
+        map(parse_literal, ast::Expression::Literal),
+        map(keyword("errorval"), |_| ast::Expression::CurrentError),
+        map(tuple((keyword("errordef"), tag("."), ws0, parse_identifier, ws0)),
+            |v| ast::Expression::ErrorEnumRef(v.3.to_string())),
+        parse_qualified_element_ref
+    ))(input)
+}
+
+/// FQualifiedElementRef: element=FQN ('->' field=ID)*
+fn parse_qualified_element_ref(input: Input) -> PResult<ast::Expression> {
+    let (r, v) = tuple((
+        parse_fqn, ws0,
+        many0(map(tuple((tag("->"), ws0, parse_identifier, ws0)), |f| f.2.to_string()))
+    ))(input)?;
+    Ok((r, ast::Expression::ElementRef { element: v.0.to_string(), fields: v.2 }))
+}
+
+/// Recognizes INT '.' INT ('e' '-'? INT)? followed by the given suffix, e.g. `1.5e-3d`
+fn floating_point(suffix: char) -> impl Fn(Input) -> PResult<String> {
+    move |i: Input| {
+        let (r, v) = tuple((
+            recognize(tuple((digit1, char('.'), digit1,
+                             option(tuple((char('e'), option(char('-')), digit1)))))),
+            char(suffix), ws0
+        ))(i)?;
+        Ok((r, v.0.to_string()))
+    }
+}
+
+/// FConstant: FFloatConstant | FDoubleConstant | FIntegerConstant | FBooleanConstant | FStringConstant
+fn parse_literal(input: Input) -> PResult<ast::Literal> {
+    alt((
+        map(floating_point('d'), ast::Literal::Double),
+        map(floating_point('f'), ast::Literal::Float),
+        map(parse_integer, ast::Literal::Integer),
+        map(keyword("true"), |_| ast::Literal::Boolean(true)),
+        map(keyword("false"), |_| ast::Literal::Boolean(false)),
+        map(terminated(parse_string, ws0), ast::Literal::String),
+    ))(input)
+}
+
+/// FContract: 'contract' '{' ('vars' '{' (variables+=FDeclaration (';')?)* '}')?
+///     'PSM' stateGraph=FStateGraph '}'
+fn parse_contract(input: Input) -> PResult<ast::Contract> {
+    let (r, v) = context("contract", preceded(keyword("contract"), cut(tuple((
+        char('{'), ws0,
+        option(map(tuple((
+            keyword("vars"), char('{'), ws0,
+            many0(map(pair(parse_declaration, option(pair(char(';'), ws0))), |d| d.0)),
+            char('}'), ws0)), |vars| vars.3)),
+        keyword("PSM"), parse_state_graph,
+        char('}'), ws0
+    )))))(input)?;
+    Ok((r, ast::Contract{ variables: v.2.unwrap_or_default(), state_graph: v.4 }))
+}
+
+/// FDeclaration: type=FTypeRef (array?='[' ']')? name=ID ('=' rhs=FInitializerExpression)?
+fn parse_declaration(input: Input) -> PResult<ast::Declaration> {
+    let (r, v) = tuple((
+        parse_type_ref, ws0, parse_array_specifier, parse_identifier, ws0,
+        option(preceded(pair(char('='), ws0), parse_initializer_expression))
+    ))(input)?;
+    Ok((r, ast::Declaration{ type_ref: v.0, array: v.2, name: v.3.to_string(), value: v.5 }))
+}
+
+/// FStateGraph: '{' 'initial' initial=[FState|ID] states+=FState* '}'
+fn parse_state_graph(input: Input) -> PResult<ast::StateGraph> {
+    let (r, v) = tuple((
+        char('{'), keyword("initial"), parse_identifier, ws0,
+        many0(parse_state),
+        char('}'), ws0
+    ))(input)?;
+    Ok((r, ast::StateGraph{ initial: v.2.to_string(), states: v.4 }))
+}
+
+/// FState: (comment=FAnnotationBlock)? 'state' name=ID '{' transitions+=FTransition* '}'
+fn parse_state(input: Input) -> PResult<ast::State> {
+    let (r, v) = tuple((
+        parse_annotation, keyword("state"), parse_identifier, ws0, char('{'), ws0,
+        many0(parse_transition),
+        char('}'), ws0
+    ))(input)?;
+    Ok((r, ast::State{ annotation: v.0, name: v.2.to_string(), transitions: v.6 }))
+}
+
+/// FTransition: 'on' trigger=FTrigger (guard=FGuard)? '->' to=[FState|ID] (action=FBlock)?
+fn parse_transition(input: Input) -> PResult<ast::Transition> {
+    let (r, v) = tuple((
+        keyword("on"), parse_trigger,
+        option(map(tuple((char('['), ws0, parse_logical_or_expression, char(']'), ws0)),
+                   |g| g.2)),
+        tag("->"), ws0, parse_identifier, ws0,
+        option(parse_block)
+    ))(input)?;
+    Ok((r, ast::Transition{ trigger: v.1, guard: v.2, to: v.5.to_string(), action: v.7 }))
+}
+
+/// ID_WITH_SELECTOR: ID (':' ID)?
+fn parse_id_with_selector(input: Input) -> PResult<(String, Option<String>)> {
+    let (r, v) = tuple((
+        parse_identifier, ws0,
+        option(map(tuple((char(':'), ws0, parse_identifier, ws0)), |s| s.2.to_string()))
+    ))(input)?;
+    Ok((r, (v.0.to_string(), v.2)))
+}
+
+/// FEventOnIf: ('call' | 'respond' | 'error') ID_WITH_SELECTOR | 'signal' ID_WITH_SELECTOR |
+///     ('set' | 'update') ID
+fn parse_trigger(input: Input) -> PResult<ast::Trigger> {
+    alt((
+        map(preceded(keyword("call"), parse_id_with_selector),
+            |(method, selector)| ast::Trigger::Call{ method, selector }),
+        map(preceded(keyword("respond"), parse_id_with_selector),
+            |(method, selector)| ast::Trigger::Respond{ method, selector }),
+        map(preceded(keyword("error"), parse_id_with_selector),
+            |(method, selector)| ast::Trigger::Error{ method, selector }),
+        map(preceded(keyword("signal"), parse_id_with_selector),
+            |(broadcast, selector)| ast::Trigger::Signal{ broadcast, selector }),
+        map(tuple((keyword("set"), parse_identifier, ws0)),
+            |v| ast::Trigger::Set{ attribute: v.1.to_string() }),
+        map(tuple((keyword("update"), parse_identifier, ws0)),
+            |v| ast::Trigger::Update{ attribute: v.1.to_string() }),
+    ))(input)
+}
+
+/// FBlock: '{' (statements+=FStatement)* '}'
+fn parse_block(input: Input) -> PResult<Vec<ast::Statement>> {
+    let (r, v) = tuple((char('{'), ws0, many0(parse_statement), char('}'), ws0))(input)?;
+    Ok((r, v.2))
+}
+
+/// FStatement: FAssignment | FIfStatement | FBlock
+fn parse_statement(input: Input) -> PResult<ast::Statement> {
+    alt((parse_if_statement, map(parse_block, ast::Statement::Block), parse_assignment))(input)
+}
+
+/// FIfStatement: 'if' '(' condition=LogicalOrExpression ')' then=FStatement ('else' else=FStatement)?
+fn parse_if_statement(input: Input) -> PResult<ast::Statement> {
+    let (r, v) = tuple((
+        keyword("if"), char('('), ws0, parse_logical_or_expression, char(')'), ws0,
+        parse_statement,
+        option(preceded(keyword("else"), parse_statement))
+    ))(input)?;
+    Ok((r, ast::Statement::If{ condition: v.3, then: Box::new(v.6), otherwise: v.7.map(Box::new) }))
+}
+
+/// FAssignment: lhs=FQualifiedElementRef '=' rhs=LogicalOrExpression
+fn parse_assignment(input: Input) -> PResult<ast::Statement> {
+    let (r, v) = tuple((
+        parse_qualified_element_ref, char('='), ws0, parse_logical_or_expression
+    ))(input)?;
+    Ok((r, ast::Statement::Assignment{ lhs: v.0, rhs: v.3 }))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::ast::ClearSpans;
+
+    impl ClearSpans for InterfaceContent {
+        fn clear_spans(&mut self) {
+            match self {
+                InterfaceContent::Attribute(a) => a.clear_spans(),
+                InterfaceContent::Method(m) => m.clear_spans(),
+                InterfaceContent::Broadcast(b) => b.clear_spans(),
+                InterfaceContent::Constant(c) => c.clear_spans(),
+                InterfaceContent::Type(t) => t.clear_spans(),
+                InterfaceContent::Contract(_) => {},
+            }
+        }
+    }
+
+    impl ClearSpans for ModuleContent {
+        fn clear_spans(&mut self) {
+            match self {
+                ModuleContent::Interface(i) => i.clear_spans(),
+                ModuleContent::TypeCollection(t) => t.clear_spans(),
+            }
+        }
+    }
+
+    /// Runs `parser` on plain text so results can be compared with string literals.
+    fn run<'a, O>(mut parser: impl FnMut(Input<'a>) -> PResult<'a, O>, text: &'a str) -> IResult<&'a str, O> {
+        parser(Input::new_extra(text, None))
+            .map(|(r, o)| (*r.fragment(), o))
+            .map_err(|e| e.map(simple_error))
+    }
+
+    /// Like `run` with the spans of the result reset, so it can be compared with nodes built with
+    /// `span: Default::default()`.
+    fn run_node<'a, O: ClearSpans>(parser: impl FnMut(Input<'a>) -> PResult<'a, O>, text: &'a str)
+            -> IResult<&'a str, O> {
+        run(parser, text).map(|(r, o)| (r, o.without_spans()))
+    }
+
+    /// Like `run` for parsers recognizing a piece of the input.
+    fn run_str<'a>(parser: impl FnMut(Input<'a>) -> PResult<'a, Input<'a>>, text: &'a str) -> IResult<&'a str, &'a str> {
+        run(parser, text).map(|(r, o)| (r, *o.fragment()))
+    }
+
+    #[test]
+    fn test_spans() {
+        let text = "  /* c */ attribute Int8 x\n  attribute\n UInt8 y readonly  ";
+        let (r, a) = run(parse_attribute, text).unwrap();
+        let a = if let InterfaceContent::Attribute(a) = a { a } else { panic!("attribute expected") };
+        assert_eq!((a.span.offset, a.span.len, a.span.line, a.span.column), (10, 16, 1, 11));
+        let (_, b) = run(parse_attribute, r).unwrap();
+        let b = if let InterfaceContent::Attribute(b) = b { b } else { panic!("attribute expected") };
+        assert_eq!((b.span.offset, b.span.len, b.span.line, b.span.column), (0, 27, 1, 1));
+
+        let file: Arc<Path> = Arc::from(Path::new("x.fidl"));
+        let (_, m) = parse_module_input(Input::new_extra("package a.b\n\ninterface X {\n}\n", Some(&file))).unwrap();
+        assert_eq!(m.interfaces[0].span.file.as_deref(), Some(Path::new("x.fidl")));
+        assert_eq!((m.interfaces[0].span.line, m.interfaces[0].span.column), (3, 1));
+    }
+
+    #[test]
+    fn test_recovery() {
+        let text = "package a.b
+interface First {
+    method ok { }
+    method broken { in { UInt8 } }
+    attribute UInt8 good
+    attribute UInt8 1x
+    // method in a comment is no resynchronization point
+    broadcast fine { }
+}
+garbage here
+typeCollection Types {
+    struct S { UInt8 }
+    const UInt8 C = 1
+}
+interface Unclosed {
+    attribute UInt8 a
+interface Last {
+    method m { }
+}
+";
+        let (module, diagnostics) = parse_fidl_recovering(text, None);
+        assert_eq!(module.package, "a.b");
+        let messages: Vec<_> = diagnostics.iter().map(|d| (d.span.line, d.message.as_str())).collect();
+        assert_eq!(messages, vec![
+            (4, "expected `}` to close method `broken`, found `in`"),
+            (6, "expected identifier in attribute, found `1x`"),
+            (10, "expected `interface`, `typeCollection` or end of file, found `garbage`"),
+            (12, "expected `}` to close struct `S`, found `UInt8`"),
+            (17, "expected `}` to close interface `Unclosed`, found `interface`"),
+        ]);
+        assert_eq!(diagnostics[0].context.len(), 2);
+
+        let first = &module.interfaces[0];
+        assert_eq!(first.methods.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), vec!["ok"]);
+        assert_eq!(first.attributes.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(), vec!["good"]);
+        assert_eq!(first.broadcasts.len(), 1);
+        assert_eq!(first.span.line, 2);
+        assert!(module.type_collections[0].types.is_empty());
+        assert_eq!(module.type_collections[0].constants.len(), 1);
+        assert_eq!(module.interfaces.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(),
+                   vec!["First", "Unclosed", "Last"]);
+        assert_eq!(module.interfaces[1].attributes.len(), 1);
+        assert_eq!(module.interfaces[2].methods.len(), 1);
+
+        let (module, diagnostics) = parse_fidl_recovering("packag a\nimport model \"x.fidl\"\ninterface I { }", None);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(module.imports.len(), 1);
+        assert_eq!(module.interfaces.len(), 1);
+
+        let (_, diagnostics) = parse_fidl_recovering("package a\ninterface I { }\n", None);
+        assert!(diagnostics.is_empty());
+
+        // members follow the order of a strict parse, there is at most one contract
+        let text = "package a\ninterface I {\n  contract { PSM { initial A state A { } } }\n  method m { }
+  contract { PSM { initial B state B { } } }\n  typedef T is UInt8\n}";
+        let (module, diagnostics) = parse_fidl_recovering(text, None);
+        let messages: Vec<_> = diagnostics.iter().map(|d| (d.span.line, d.message.as_str())).collect();
+        assert_eq!(messages, vec![(4, "expected `}` to close interface `I`, found `method`"),
+                                  (5, "expected `}` to close interface `I`, found `contract`")]);
+        assert_eq!(parse_fidl(text, None, ParseMode::Strict).unwrap_err().message, diagnostics[0].message);
+        let interface = &module.interfaces[0];
+        assert_eq!(interface.contract.as_ref().map(|c| c.state_graph.initial.as_str()), Some("A"));
+        assert!(interface.methods.is_empty());
+        assert_eq!(interface.types.len(), 1);
+    }
+
+    #[test]
+    fn test_skip_to_keyword() {
+        let skip = |text| *skip_to_keyword(Input::new_extra(text, None), &MODULE_KEYWORDS).fragment();
+        assert_eq!(skip("garbage \"interface\" /* interface */ interface I { }"), "interface I { }");
+        assert_eq!(skip("garbage 'typeCollection \" interface' <** interface **> typeCollection T"), "typeCollection T");
+        assert_eq!(skip("interface I // interface\n"), "");
+    }
 
     #[test]
     fn test_method() {
-        assert_eq!(parse_method("method resetHistory fireAndForget {}"),
-            Ok(("", InterfaceContent::Method(ast::Method{
+        assert_eq!(run_node(parse_method, "method resetHistory fireAndForget {}"),
+            Ok(("", InterfaceContent::Method(ast::Method{ span: Default::default(),
                 annotation: None, name: "resetHistory".to_string(), fire_and_forget:true, selector: None,
                 in_args: Vec::new(), out_args: Vec::new(), error: None }))));
-        assert_eq!(parse_method("method setStrength { in { Strength s } }"),
-            Ok(("", InterfaceContent::Method(ast::Method{
+        assert_eq!(run_node(parse_method, "method setStrength { in { Strength s } }"),
+            Ok(("", InterfaceContent::Method(ast::Method{ span: Default::default(),
                 annotation: None, name: "setStrength".to_string(), fire_and_forget: false, selector: None,
                 out_args: Vec::new(), error: None, in_args: vec![
-                    ast::Argument{annotation: None, name: "s".to_string(), type_ref: ast::TypeRef::Derived("Strength".to_string()), array: false}
+                    ast::Argument{ span: Default::default(), annotation: None, name: "s".to_string(), type_ref: ast::TypeRef::Derived("Strength".to_string()), array: false}
                 ]
         }))));
-        assert_eq!(parse_method("method getCount { out { UInt32 counter } }"),
-            Ok(("", InterfaceContent::Method(ast::Method{
+        assert_eq!(run_node(parse_method, "method getCount { out { UInt32 counter } }"),
+            Ok(("", InterfaceContent::Method(ast::Method{ span: Default::default(),
                 annotation: None, name: "getCount".to_string(), fire_and_forget: false, selector: None,
                 in_args: Vec::new(), error: None, out_args: vec![
-                    ast::Argument{annotation: None, name: "counter".to_string(), type_ref: ast::TypeRef::UInt32, array: false}
+                    ast::Argument{ span: Default::default(), annotation: None, name: "counter".to_string(), type_ref: ast::TypeRef::UInt32, array: false}
         ]}))));
-        assert_eq!(parse_method("method callable { in { Int8 a Int32 b } out { String[] r } error ErrorTypes }"),
-            Ok(("", InterfaceContent::Method(ast::Method{annotation: None, name: "callable".to_string(),
+        assert_eq!(run_node(parse_method, "method callable { in { Int8 a Int32 b } out { String[] r } error ErrorTypes }"),
+            Ok(("", InterfaceContent::Method(ast::Method{ span: Default::default(), annotation: None, name: "callable".to_string(),
                 fire_and_forget: false, selector: None,
                 in_args: vec![
-                    ast::Argument{annotation: None, name: "a".to_string(), array: false, type_ref: ast::TypeRef::Int8},
-                    ast::Argument{annotation: None, name: "b".to_string(), array: false, type_ref: ast::TypeRef::Int32},
+                    ast::Argument{ span: Default::default(), annotation: None, name: "a".to_string(), array: false, type_ref: ast::TypeRef::Int8},
+                    ast::Argument{ span: Default::default(), annotation: None, name: "b".to_string(), array: false, type_ref: ast::TypeRef::Int32},
                 ],
                 out_args: vec![
-                    ast::Argument{annotation: None, name: "r".to_string(), array: true, type_ref: ast::TypeRef::String}
+                    ast::Argument{ span: Default::default(), annotation: None, name: "r".to_string(), array: true, type_ref: ast::TypeRef::String}
                 ],
                 error: Some(ast::MethodErrorSpec::Reference {annotation: None, fqn: "ErrorTypes".to_string()})
             }))));
@@ -459,77 +1366,274 @@ mod test {
 
     #[test]
     fn test_broadcast() {
-        assert_eq!(parse_broadcast("broadcast SignalA{ }"), Ok(("", InterfaceContent::Broadcast(
-            ast::Broadcast{ annotation: None, selective: false, selector: None, name: "SignalA".to_string(),
+        assert_eq!(run_node(parse_broadcast, "broadcast SignalA{ }"), Ok(("", InterfaceContent::Broadcast(
+            ast::Broadcast{ span: Default::default(), annotation: None, selective: false, selector: None, name: "SignalA".to_string(),
                 out_args: Vec::new()}))));
-        assert_eq!(parse_broadcast("broadcast SignalB selective { out{ Int8[] a Boolean b} }"),
-           Ok(("", InterfaceContent::Broadcast( ast::Broadcast{
+        assert_eq!(run_node(parse_broadcast, "broadcast SignalB selective { out{ Int8[] a Boolean b} }"),
+           Ok(("", InterfaceContent::Broadcast( ast::Broadcast{ span: Default::default(),
                annotation: None, selective: true, selector: None, name: "SignalB".to_string(),
                 out_args: vec![
-                    ast::Argument{ annotation: None, name: "a".to_string(), array: true, type_ref: ast::TypeRef::Int8},
-                    ast::Argument{ annotation: None, name: "b".to_string(), array: false, type_ref: ast::TypeRef::Boolean},
+                    ast::Argument{ span: Default::default(), annotation: None, name: "a".to_string(), array: true, type_ref: ast::TypeRef::Int8},
+                    ast::Argument{ span: Default::default(), annotation: None, name: "b".to_string(), array: false, type_ref: ast::TypeRef::Boolean},
                 ]
        }))));
     }
 
     #[test]
     fn test_type_collection() {
-        assert_eq!(parse_type_collection("typeCollection my_type_collection {}"),
-            Ok(("", ModuleContent::TypeCollection( ast::TypeCollection{
-                annotation: None, name: Some("my_type_collection".to_string()), version: None, types: Vec::new()
+        assert_eq!(run_node(parse_type_collection, "typeCollection my_type_collection {}"),
+            Ok(("", ModuleContent::TypeCollection( ast::TypeCollection{ span: Default::default(),
+                annotation: None, name: Some("my_type_collection".to_string()), version: None, types: Vec::new(),
+                constants: Vec::new()
             }))));
-        assert_eq!(parse_type_collection("typeCollection my_type_collection { version{ major 1 minor 100}}"),
-           Ok(("", ModuleContent::TypeCollection( ast::TypeCollection{
-               annotation: None, name: Some("my_type_collection".to_string()), version: Some((1, 100)), types: Vec::new()
+        assert_eq!(run_node(parse_type_collection, "typeCollection my_type_collection { version{ major 1 minor 100}}"),
+           Ok(("", ModuleContent::TypeCollection( ast::TypeCollection{ span: Default::default(),
+               annotation: None, name: Some("my_type_collection".to_string()), version: Some((1, 100)), types: Vec::new(),
+               constants: Vec::new()
            }))));
     }
 
     #[test]
     fn test_enumeration() {
-        assert_eq!(parse_enumeration("public enumeration MyEnum { A=1 B=100, C D }"),
-            Ok(("", ast::Type::Enumeration {annotation: None, public: true, name: "MyEnum".to_string(),
+        assert_eq!(run_node(parse_enumeration, "public enumeration MyEnum { A=1 B=100, C D }"),
+            Ok(("", ast::Type::Enumeration { span: Default::default(), annotation: None, public: true, name: "MyEnum".to_string(),
                 base_type: None, enumerators: vec![
-                    ast::Enumerator{ annotation: None, name: "A".to_string(), val: Some(1) },
-                    ast::Enumerator{ annotation: None, name: "B".to_string(), val: Some(100) },
-                    ast::Enumerator{ annotation: None, name: "C".to_string(), val: None },
-                    ast::Enumerator{ annotation: None, name: "D".to_string(), val: None },
+                    ast::Enumerator{ span: Default::default(), annotation: None, name: "A".to_string(), val: Some(1) },
+                    ast::Enumerator{ span: Default::default(), annotation: None, name: "B".to_string(), val: Some(100) },
+                    ast::Enumerator{ span: Default::default(), annotation: None, name: "C".to_string(), val: None },
+                    ast::Enumerator{ span: Default::default(), annotation: None, name: "D".to_string(), val: None },
                 ]
         })));
     }
 
     #[test]
     fn test_enumerator() {
-        assert_eq!(parse_enumerator(" enum_value_1 "), Ok(("", ast::Enumerator{
+        assert_eq!(run_node(parse_enumerator, " enum_value_1 "), Ok(("", ast::Enumerator{ span: Default::default(),
             annotation: None, name: "enum_value_1".to_string(), val: None })));
-        assert_eq!(parse_enumerator("<** some comment **>\n SIGNAL_UNBEFUELLT = 0x12"), Ok(("", ast::Enumerator {
-            annotation: Some(" some comment ".to_string()), name: "SIGNAL_UNBEFUELLT".to_string(), val: Some(0x12u64) })));
+        assert_eq!(run_node(parse_enumerator, "<** some comment **>\n SIGNAL_UNBEFUELLT = 0x12"), Ok(("", ast::Enumerator { span: Default::default(),
+            annotation: Some(" some comment ".into()), name: "SIGNAL_UNBEFUELLT".to_string(), val: Some(0x12u64) })));
+    }
+
+    #[test]
+    fn test_expression() {
+        use ast::Expression::{Binary, Unary, ElementRef, Literal};
+        assert_eq!(run(parse_logical_or_expression, "1 + 2 * 3"), Ok(("", Binary {
+            left: Box::new(Literal(ast::Literal::Integer(1))), op: ast::Operator::Addition,
+            right: Box::new(Binary {
+                left: Box::new(Literal(ast::Literal::Integer(2))), op: ast::Operator::Multiplication,
+                right: Box::new(Literal(ast::Literal::Integer(3))) })
+        })));
+        assert_eq!(run(parse_logical_or_expression, "(a - b) <= -MAX || !ok"), Ok(("", Binary {
+            left: Box::new(Binary {
+                left: Box::new(Binary {
+                    left: Box::new(ElementRef { element: "a".to_string(), fields: Vec::new() }),
+                    op: ast::Operator::Subtraction,
+                    right: Box::new(ElementRef { element: "b".to_string(), fields: Vec::new() }) }),
+                op: ast::Operator::SmallerOrEqual,
+                right: Box::new(Unary { op: ast::Operator::Subtraction,
+                    operand: Box::new(ElementRef { element: "MAX".to_string(), fields: Vec::new() }) }) }),
+            op: ast::Operator::Or,
+            right: Box::new(Unary { op: ast::Operator::Negation,
+                operand: Box::new(ElementRef { element: "ok".to_string(), fields: Vec::new() }) })
+        })));
+        assert_eq!(run(parse_logical_or_expression, "pos->x->y == errordef.FAILED"), Ok(("", Binary {
+            left: Box::new(ElementRef { element: "pos".to_string(), fields: vec!["x".to_string(), "y".to_string()] }),
+            op: ast::Operator::Equal,
+            right: Box::new(ast::Expression::ErrorEnumRef("FAILED".to_string()))
+        })));
+        // keywords are only recognized as complete words
+        for name in ["true_flag", "falsey", "errorval2", "errordefs"] {
+            assert_eq!(run(parse_logical_or_expression, name),
+                       Ok(("", ElementRef { element: name.to_string(), fields: Vec::new() })));
+        }
+    }
+
+    #[test]
+    fn test_literal() {
+        assert_eq!(run(parse_literal, "0x1F "), Ok(("", ast::Literal::Integer(0x1f))));
+        assert_eq!(run(parse_literal, "1.5e-3d"), Ok(("", ast::Literal::Double("1.5e-3".to_string()))));
+        assert_eq!(run(parse_literal, "2.0f"), Ok(("", ast::Literal::Float("2.0".to_string()))));
+        assert_eq!(run(parse_literal, "true "), Ok(("", ast::Literal::Boolean(true))));
+        assert_eq!(run(parse_literal, "\"text\" "), Ok(("", ast::Literal::String("text".to_string()))));
+        assert!(run(parse_literal, "falsehood").is_err());
+    }
+
+    #[test]
+    fn test_constant() {
+        assert_eq!(run_node(parse_constant, "const UInt16 MAX_SPEED = 250"), Ok(("", ast::Constant { span: Default::default(),
+            annotation: None, type_ref: ast::TypeRef::UInt16, array: false, name: "MAX_SPEED".to_string(),
+            value: ast::InitializerExpression::Expression(ast::Expression::Literal(ast::Literal::Integer(250)))
+        })));
+        assert_eq!(run_node(parse_constant, "<** origin **>\nconst Point[] POINTS = [ { x: 1, y: -2 }, {} ]"), Ok(("", ast::Constant { span: Default::default(),
+            annotation: Some(" origin ".into()), type_ref: ast::TypeRef::Derived("Point".to_string()),
+            array: true, name: "POINTS".to_string(),
+            value: ast::InitializerExpression::Bracket(vec![
+                ast::ElementInitializer { first: ast::InitializerExpression::Compound(vec![
+                    ast::FieldInitializer { element: "x".to_string(), value: ast::InitializerExpression::Expression(
+                        ast::Expression::Literal(ast::Literal::Integer(1))) },
+                    ast::FieldInitializer { element: "y".to_string(), value: ast::InitializerExpression::Expression(
+                        ast::Expression::Unary { op: ast::Operator::Subtraction,
+                            operand: Box::new(ast::Expression::Literal(ast::Literal::Integer(2))) }) },
+                    ]), second: None },
+                ast::ElementInitializer { first: ast::InitializerExpression::Compound(Vec::new()), second: None },
+            ])
+        })));
+        assert_eq!(run_node(parse_constant, "const Names NAMES = [1 => \"one\", 2 => \"two\"]"), Ok(("", ast::Constant { span: Default::default(),
+            annotation: None, type_ref: ast::TypeRef::Derived("Names".to_string()), array: false,
+            name: "NAMES".to_string(),
+            value: ast::InitializerExpression::Bracket(vec![
+                ast::ElementInitializer {
+                    first: ast::InitializerExpression::Expression(ast::Expression::Literal(ast::Literal::Integer(1))),
+                    second: Some(ast::InitializerExpression::Expression(
+                        ast::Expression::Literal(ast::Literal::String("one".to_string())))) },
+                ast::ElementInitializer {
+                    first: ast::InitializerExpression::Expression(ast::Expression::Literal(ast::Literal::Integer(2))),
+                    second: Some(ast::InitializerExpression::Expression(
+                        ast::Expression::Literal(ast::Literal::String("two".to_string())))) },
+            ])
+        })));
+    }
+
+    #[test]
+    fn test_transition() {
+        assert_eq!(run(parse_transition, "on call start:withId -> Running"), Ok(("", ast::Transition {
+            trigger: ast::Trigger::Call{ method: "start".to_string(), selector: Some("withId".to_string()) },
+            guard: None, to: "Running".to_string(), action: None })));
+        assert_eq!(run(parse_transition, "on set speed [speed > 10] -> Fast { count = count + 1 }"),
+            Ok(("", ast::Transition {
+                trigger: ast::Trigger::Set{ attribute: "speed".to_string() },
+                guard: Some(ast::Expression::Binary {
+                    left: Box::new(ast::Expression::ElementRef{ element: "speed".to_string(), fields: Vec::new() }),
+                    op: ast::Operator::Greater,
+                    right: Box::new(ast::Expression::Literal(ast::Literal::Integer(10))) }),
+                to: "Fast".to_string(),
+                action: Some(vec![ast::Statement::Assignment {
+                    lhs: ast::Expression::ElementRef{ element: "count".to_string(), fields: Vec::new() },
+                    rhs: ast::Expression::Binary {
+                        left: Box::new(ast::Expression::ElementRef{ element: "count".to_string(), fields: Vec::new() }),
+                        op: ast::Operator::Addition,
+                        right: Box::new(ast::Expression::Literal(ast::Literal::Integer(1))) } }])
+            })));
+    }
+
+    #[test]
+    fn test_statement() {
+        assert_eq!(run(parse_statement, "if (ok) { a = 1 } else b = 2"), Ok(("", ast::Statement::If {
+            condition: ast::Expression::ElementRef{ element: "ok".to_string(), fields: Vec::new() },
+            then: Box::new(ast::Statement::Block(vec![ast::Statement::Assignment {
+                lhs: ast::Expression::ElementRef{ element: "a".to_string(), fields: Vec::new() },
+                rhs: ast::Expression::Literal(ast::Literal::Integer(1)) }])),
+            otherwise: Some(Box::new(ast::Statement::Assignment {
+                lhs: ast::Expression::ElementRef{ element: "b".to_string(), fields: Vec::new() },
+                rhs: ast::Expression::Literal(ast::Literal::Integer(2)) }))
+        })));
+    }
+
+    #[test]
+    fn test_contract() {
+        let txt = "contract { vars { UInt32 count = 0; Boolean busy } \
+                   PSM { initial Idle state Idle { on call start -> Running } \
+                   <** running **> state Running { on signal done -> Idle on error start -> Idle } } }";
+        assert_eq!(run(parse_contract, txt), Ok(("", ast::Contract {
+            variables: vec![
+                ast::Declaration{ type_ref: ast::TypeRef::UInt32, array: false, name: "count".to_string(),
+                    value: Some(ast::InitializerExpression::Expression(
+                        ast::Expression::Literal(ast::Literal::Integer(0)))) },
+                ast::Declaration{ type_ref: ast::TypeRef::Boolean, array: false, name: "busy".to_string(),
+                    value: None },
+            ],
+            state_graph: ast::StateGraph {
+                initial: "Idle".to_string(),
+                states: vec![
+                    ast::State{ annotation: None, name: "Idle".to_string(), transitions: vec![
+                        ast::Transition{ trigger: ast::Trigger::Call{ method: "start".to_string(), selector: None },
+                            guard: None, to: "Running".to_string(), action: None }
+                    ]},
+                    ast::State{ annotation: Some(" running ".into()), name: "Running".to_string(), transitions: vec![
+                        ast::Transition{ trigger: ast::Trigger::Signal{ broadcast: "done".to_string(), selector: None },
+                            guard: None, to: "Idle".to_string(), action: None },
+                        ast::Transition{ trigger: ast::Trigger::Error{ method: "start".to_string(), selector: None },
+                            guard: None, to: "Idle".to_string(), action: None },
+                    ]},
+                ]
+            }
+        })));
+    }
+
+    #[test]
+    fn test_type_ref() {
+        assert_eq!(run(parse_type_ref, "UInt16 speed"), Ok((" speed", ast::TypeRef::UInt16)));
+        assert_eq!(run(parse_type_ref, "a.b.MyType x"), Ok((" x", ast::TypeRef::Derived("a.b.MyType".to_string()))));
+        assert_eq!(run(parse_type_ref, "IntegerType x"), Ok((" x", ast::TypeRef::Derived("IntegerType".to_string()))));
+        assert_eq!(run(parse_type_ref, "Integer x"), Ok((" x", ast::TypeRef::IntegerInterval(None, None))));
+        assert_eq!(run(parse_type_ref, "Integer(0, 100) x"), Ok((" x", ast::TypeRef::IntegerInterval(Some(0), Some(100)))));
+        assert_eq!(run(parse_type_ref, "Integer ( minInt , 42 ) x"), Ok((" x", ast::TypeRef::IntegerInterval(None, Some(42)))));
+        assert_eq!(run(parse_type_ref, "Integer(-40,maxInt) x"), Ok((" x", ast::TypeRef::IntegerInterval(Some(-40), None))));
+        assert_eq!(run(parse_type_ref, "Integer(-99999999999999999999, 99999999999999999999) x"),
+                   Ok((" x", ast::TypeRef::IntegerInterval(Some(-99999999999999999999), Some(99999999999999999999)))));
+        assert!(matches!(run(parse_type_ref, "Integer(0, 999999999999999999999999999999999999999999) x"),
+                         Err(nom::Err::Failure(_))));
+        assert!(matches!(run(parse_type_ref, "Integer(minInt, 1 x"), Err(nom::Err::Failure(_))));
+    }
+
+    #[test]
+    fn test_comments() {
+        let txt = "interface /* name follows */ Commented // trailing comment
+            {
+                version { /* v */ major 1 // major version
+                          minor 2 /* minor version */ }
+                method m /* no selector */ {
+                    in { /* first */ Int8 a // comment
+                         Int16 /* type before name */ b }
+                }
+                enumeration E { A = 1, /* second: */ B // last
+                }
+                // attribute Int8 commentedOut
+            } // end";
+        let (r, interface) = run_node(parse_interface, txt).unwrap();
+        assert_eq!(r, "");
+        if let ModuleContent::Interface(intf) = interface {
+            assert_eq!(intf.name, "Commented");
+            assert_eq!(intf.version, Some((1, 2)));
+            assert!(intf.attributes.is_empty());
+            assert_eq!(intf.methods[0].in_args.len(), 2);
+            assert_eq!(intf.methods[0].in_args[1].name, "b");
+            assert_eq!(intf.types[0], ast::Type::Enumeration { span: Default::default(), annotation: None, public: false,
+                name: "E".to_string(), base_type: None, enumerators: vec![
+                    ast::Enumerator{ span: Default::default(), annotation: None, name: "A".to_string(), val: Some(1) },
+                    ast::Enumerator{ span: Default::default(), annotation: None, name: "B".to_string(), val: None },
+                ]});
+        }
+        else {
+            panic!("interface is not ModuleContent::Interface");
+        }
     }
 
     #[test]
     fn test_integer_decimal() {
-        assert_eq!(parse_integer_decimal("1234"), Ok(("", 1234u64)));
-        assert_eq!(parse_integer_decimal("0"), Ok(("", 0u64)));
-        assert_eq!(parse_integer_decimal("65535"), Ok(("", 0xffffu64)));
+        assert_eq!(run(parse_integer_decimal, "1234"), Ok(("", 1234u64)));
+        assert_eq!(run(parse_integer_decimal, "0"), Ok(("", 0u64)));
+        assert_eq!(run(parse_integer_decimal, "65535"), Ok(("", 0xffffu64)));
     }
 
     #[test]
     fn test_integer_hex() {
-        assert_eq!(parse_integer_hex("0x1234"), Ok(("", 0x1234u64)));
-        assert_eq!(parse_integer_hex("0X0"), Ok(("", 0u64)));
-        assert_eq!(parse_integer_hex("0xffffffff"), Ok(("", 0xffffffffu64)));
+        assert_eq!(run(parse_integer_hex, "0x1234"), Ok(("", 0x1234u64)));
+        assert_eq!(run(parse_integer_hex, "0X0"), Ok(("", 0u64)));
+        assert_eq!(run(parse_integer_hex, "0xffffffff"), Ok(("", 0xffffffffu64)));
     }
 
     #[test]
     fn test_integer_binary() {
-        assert_eq!(parse_integer_bin("0b1101"), Ok(("", 13u64)));
-        assert_eq!(parse_integer_bin("0B11110001"), Ok(("", 0xf1u64)));
-        assert_eq!(parse_integer_bin("0b0"), Ok(("", 0)));
+        assert_eq!(run(parse_integer_bin, "0b1101"), Ok(("", 13u64)));
+        assert_eq!(run(parse_integer_bin, "0B11110001"), Ok(("", 0xf1u64)));
+        assert_eq!(run(parse_integer_bin, "0b0"), Ok(("", 0)));
     }
 
     #[test]
     fn test_map_type() {
-        assert_eq!(parse_map_type("public map myMap { type_x to Boolean }"),
-            Ok(("", ast::Type::Map {
+        assert_eq!(run_node(parse_map_type, "public map myMap { type_x to Boolean }"),
+            Ok(("", ast::Type::Map { span: Default::default(),
                 annotation: None, public: true, name: "myMap".to_string(),
                 key_type: ast::TypeRef::Derived("type_x".to_string()), value_type: ast::TypeRef::Boolean
         })));
@@ -537,186 +1641,210 @@ mod test {
 
     #[test]
     fn test_union_type() {
-        assert_eq!(parse_union_type("<**comment**> union A_Union extends X_Type {\n <**a**> Int32 counter \n Int64 long_counter }"),
-            Ok(("", ast::Type::Union {
-                annotation: Some("comment".to_string()), public: false, base_type: Some("X_Type".to_string()),
+        assert_eq!(run_node(parse_union_type, "<**comment**> union A_Union extends X_Type {\n <**a**> Int32 counter \n Int64 long_counter }"),
+            Ok(("", ast::Type::Union { span: Default::default(),
+                annotation: Some("comment".into()), public: false, base_type: Some("X_Type".to_string()),
                 name: "A_Union".to_string(), fields: vec![
-                    ast::Field{ annotation: Some("a".to_string()), name: "counter".to_string(), array: false, type_ref: ast::TypeRef::Int32},
-                    ast::Field{ annotation: None, name: "long_counter".to_string(), array: false, type_ref: ast::TypeRef::Int64},
+                    ast::Field{ span: Default::default(), annotation: Some("a".into()), name: "counter".to_string(), array: false, type_ref: ast::TypeRef::Int32},
+                    ast::Field{ span: Default::default(), annotation: None, name: "long_counter".to_string(), array: false, type_ref: ast::TypeRef::Int64},
                 ]
         })));
     }
 
     #[test]
     fn test_struct_type() {
-        assert_eq!(parse_struct_type("public struct MyStruct {\n Int8 a\n UInt32 b String[] c} XYZ"),
-            Ok(("XYZ", ast::Type::Struct { annotation: None, public: true, name: "MyStruct".to_string(),
+        assert_eq!(run_node(parse_struct_type, "public struct MyStruct {\n Int8 a\n UInt32 b String[] c} XYZ"),
+            Ok(("XYZ", ast::Type::Struct { span: Default::default(), annotation: None, public: true, name: "MyStruct".to_string(),
                 polymorphic: false, extends: None,
                 fields: vec![
-                    ast::Field{ annotation: None, name: "a".to_string(), array: false, type_ref: ast::TypeRef::Int8},
-                    ast::Field{ annotation: None, name: "b".to_string(), array: false, type_ref: ast::TypeRef::UInt32},
-                    ast::Field{ annotation: None, name: "c".to_string(), array: true, type_ref: ast::TypeRef::String},
+                    ast::Field{ span: Default::default(), annotation: None, name: "a".to_string(), array: false, type_ref: ast::TypeRef::Int8},
+                    ast::Field{ span: Default::default(), annotation: None, name: "b".to_string(), array: false, type_ref: ast::TypeRef::UInt32},
+                    ast::Field{ span: Default::default(), annotation: None, name: "c".to_string(), array: true, type_ref: ast::TypeRef::String},
               ]
         })));
     }
 
     #[test]
     fn test_array_type() {
-        assert_eq!(parse_array_type("public array MyArray of UInt32  AAA"),
-                   Ok(("AAA", ast::Type::Array{annotation: None, public: true, name: "MyArray".to_string(),
+        assert_eq!(run_node(parse_array_type, "public array MyArray of UInt32  AAA"),
+                   Ok(("AAA", ast::Type::Array{ span: Default::default(), annotation: None, public: true, name: "MyArray".to_string(),
                        element_type: ast::TypeRef::UInt32})));
-        assert_eq!(parse_array_type("<** nothing \n here \n to see **>\n  array SomeArray of Boolean    AAA"),
-                   Ok(("AAA", ast::Type::Array{annotation: Some(" nothing \n here \n to see ".to_string()),
+        assert_eq!(run_node(parse_array_type, "<** nothing \n here \n to see **>\n  array SomeArray of Boolean    AAA"),
+                   Ok(("AAA", ast::Type::Array{ span: Default::default(), annotation: Some(" nothing \n here \n to see ".into()),
                        public: false, name: "SomeArray".to_string(), element_type: ast::TypeRef::Boolean })));
     }
 
     #[test]
     fn test_typedef() {
-        assert_eq!(parse_typedef("public typedef MyType is Int8  AAA"),
-            Ok(("AAA", ast::Type::TypeDef{annotation: None, public: true, name: "MyType".to_string(),
+        assert_eq!(run_node(parse_typedef, "public typedef MyType is Int8  AAA"),
+            Ok(("AAA", ast::Type::TypeDef{ span: Default::default(), annotation: None, public: true, name: "MyType".to_string(),
                         actual_type: ast::TypeRef::Int8, array: false})));
-        assert_eq!(parse_typedef("<** nothing **>\n typedef SomeArray is Boolean [  ]  AAA"),
-                   Ok(("AAA", ast::Type::TypeDef{annotation: Some(" nothing ".to_string()),
+        assert_eq!(run_node(parse_typedef, "<** nothing **>\n typedef SomeArray is Boolean [  ]  AAA"),
+                   Ok(("AAA", ast::Type::TypeDef{ span: Default::default(), annotation: Some(" nothing ".into()),
                        public: false, name: "SomeArray".to_string(), actual_type: ast::TypeRef::Boolean,
                        array: true})));
     }
 
     #[test]
     fn test_field() {
-        assert_eq!(parse_field("Boolean my_bool   "),
-                   Ok(("", ast::Field{annotation: None, name: "my_bool".to_string(), array: false, type_ref: ast::TypeRef::Boolean})));
-        assert_eq!(parse_field("UInt32[] an_array \nA"),
-                   Ok(("A", ast::Field{annotation: None, name: "an_array".to_string(), array: true, type_ref: ast::TypeRef::UInt32})));
-        assert_eq!(parse_field("<** a little comment**>\n     MyOwnType field \n"),
-                   Ok(("", ast::Field{annotation: Some(" a little comment".to_string()),
+        assert_eq!(run_node(parse_field, "Boolean my_bool   "),
+                   Ok(("", ast::Field{ span: Default::default(), annotation: None, name: "my_bool".to_string(), array: false, type_ref: ast::TypeRef::Boolean})));
+        assert_eq!(run_node(parse_field, "UInt32[] an_array \nA"),
+                   Ok(("A", ast::Field{ span: Default::default(), annotation: None, name: "an_array".to_string(), array: true, type_ref: ast::TypeRef::UInt32})));
+        assert_eq!(run_node(parse_field, "<** a little comment**>\n     MyOwnType field \n"),
+                   Ok(("", ast::Field{ span: Default::default(), annotation: Some(" a little comment".into()),
                        name: "field".to_string(), array: false, type_ref: ast::TypeRef::Derived("MyOwnType".to_string())})));
     }
 
     #[test]
     fn test_argument() {
-        assert_eq!(parse_argument("Boolean my_bool   "),
-                   Ok(("", ast::Argument{ annotation: None, name: "my_bool".to_string(), array: false, type_ref: ast::TypeRef::Boolean})));
-        assert_eq!(parse_argument("UInt32[] an_array \nA"),
-                   Ok(("A", ast::Argument{ annotation: None, name: "an_array".to_string(), array: true, type_ref: ast::TypeRef::UInt32})));
-        assert_eq!(parse_argument("<** a little comment**>\n     MyOwnType field \n"),
-                   Ok(("", ast::Argument{ annotation: Some(" a little comment".to_string()),
+        assert_eq!(run_node(parse_argument, "Boolean my_bool   "),
+                   Ok(("", ast::Argument{ span: Default::default(), annotation: None, name: "my_bool".to_string(), array: false, type_ref: ast::TypeRef::Boolean})));
+        assert_eq!(run_node(parse_argument, "UInt32[] an_array \nA"),
+                   Ok(("A", ast::Argument{ span: Default::default(), annotation: None, name: "an_array".to_string(), array: true, type_ref: ast::TypeRef::UInt32})));
+        assert_eq!(run_node(parse_argument, "<** a little comment**>\n     MyOwnType field \n"),
+                   Ok(("", ast::Argument{ span: Default::default(), annotation: Some(" a little comment".into()),
                        name: "field".to_string(), array: false, type_ref: ast::TypeRef::Derived("MyOwnType".to_string())})));
     }
 
     #[test]
     fn test_attribute() {
-        assert_eq!(parse_attribute("attribute Int8 my_int_8  }"), Ok(("}", InterfaceContent::Attribute(
-            ast::Attribute{ annotation: None, name: "my_int_8".to_string(), array: false,
+        assert_eq!(run_node(parse_attribute, "attribute Int8 my_int_8  }"), Ok(("}", InterfaceContent::Attribute(
+            ast::Attribute{ span: Default::default(), annotation: None, name: "my_int_8".to_string(), array: false,
                 read_only: false, no_subscription: false, no_read: false, type_ref: ast::TypeRef::Int8,
         }))));
-        assert_eq!(parse_attribute("attribute MyType[] a readonly  noSubscription  "), Ok(("", InterfaceContent::Attribute(
-            ast::Attribute{ annotation: None, name: "a".to_string(), array: true,
+        assert_eq!(run_node(parse_attribute, "attribute MyType[] a readonly  noSubscription  "), Ok(("", InterfaceContent::Attribute(
+            ast::Attribute{ span: Default::default(), annotation: None, name: "a".to_string(), array: true,
                 read_only: true, no_subscription: true, no_read: false, type_ref: ast::TypeRef::Derived("MyType".to_string()),
         }))));
     }
 
     #[test]
     fn test_annotation() {
-        assert_eq!(parse_annotation("<** an annotation \n comment with multiple \n lines **> adf" ),
-                   Ok(("adf", Some(" an annotation \n comment with multiple \n lines ".to_string()))));
-        assert_eq!(parse_annotation("<**an annotation**>\n adf" ),
-                   Ok(("adf", Some("an annotation".to_string()))));
-        assert_eq!(parse_annotation("\n  <*an annotation**>\n adf" ),
+        assert_eq!(run(parse_annotation, "<** an annotation \n comment with multiple \n lines **> adf" ),
+                   Ok(("adf", Some(" an annotation \n comment with multiple \n lines ".into()))));
+        assert_eq!(run(parse_annotation, "<**an annotation**>\n adf" ),
+                   Ok(("adf", Some("an annotation".into()))));
+        assert_eq!(run(parse_annotation, "\n  <*an annotation**>\n adf" ),
                    Ok(("\n  <*an annotation**>\n adf", None)));
     }
 
+    #[test]
+    fn test_annotation_entries() {
+        let block = ast::AnnotationBlock::from(" @description : Speed in km/h\n  @author: J. Doe \\@ ACME\n\
+            @deprecated:use \\*speed2\\* instead\\n @see: x@y @x-custom-tag: \\u00e4 ");
+        assert_eq!(block.entries, vec![
+            ast::Annotation{ kind: ast::AnnotationKind::Description, text: "Speed in km/h".to_string() },
+            ast::Annotation{ kind: ast::AnnotationKind::Author, text: "J. Doe @ ACME".to_string() },
+            ast::Annotation{ kind: ast::AnnotationKind::Deprecated, text: "use *speed2* instead".to_string() },
+            ast::Annotation{ kind: ast::AnnotationKind::See, text: "x@y".to_string() },
+            ast::Annotation{ kind: ast::AnnotationKind::Custom("x-custom-tag".to_string()), text: "ä".to_string() },
+        ]);
+        assert_eq!(block.first(&ast::AnnotationKind::Author), Some("J. Doe @ ACME"));
+        assert_eq!(block.first(&ast::AnnotationKind::Details), None);
+
+        let untagged = ast::AnnotationBlock::from(" plain text \n@param: x");
+        assert_eq!(untagged.as_str(), " plain text \n@param: x");
+        assert_eq!(untagged.entries, vec![
+            ast::Annotation{ kind: ast::AnnotationKind::Description, text: "plain text".to_string() },
+            ast::Annotation{ kind: ast::AnnotationKind::Param, text: "x".to_string() },
+        ]);
+    }
+
     #[test]
     fn test_version() {
-        assert_eq!(parse_version("version{ major 1 minor 3}"), Ok(("", Some((1, 3)))));
-        assert_eq!(parse_version("not a version"), Ok(("not a version", None)));
+        assert_eq!(run(parse_version, "version{ major 1 minor 3}"), Ok(("", Some((1, 3)))));
+        assert_eq!(run(parse_version, "not a version"), Ok(("not a version", None)));
+        assert!(matches!(run(parse_version, "version { major 99999999999 minor 1 }"), Err(nom::Err::Failure(_))));
+        assert!(matches!(run(parse_version, "version { major 1 }"), Err(nom::Err::Failure(_))));
     }
 
     #[test]
     fn test_import() {
-        assert_eq!(parse_import(" import a.b.c from 'a_b-file.fidl'"),
-                   Ok(("", ast::Import{ uri: "a_b-file.fidl".to_string(), namespace: "a.b.c".to_string()})));
-        assert_eq!(parse_import(" import model   'a_b-file.fidl' \n a new line"),
-                   Ok((" \n a new line", ast::Import{ uri: "a_b-file.fidl".to_string(), namespace: String::new()})));
-        assert_eq!(parse_import(" import a.b.c.* from 'a_b-file.fidl'"),
-                   Ok(("", ast::Import{ uri: "a_b-file.fidl".to_string(), namespace: "a.b.c.*".to_string()})));
+        assert_eq!(run_node(parse_import, " import a.b.c from 'a_b-file.fidl'"),
+                   Ok(("", ast::Import{ span: Default::default(), uri: "a_b-file.fidl".to_string(), namespace: "a.b.c".to_string()})));
+        assert_eq!(run_node(parse_import, " import model   'a_b-file.fidl' \n a new line"),
+                   Ok((" \n a new line", ast::Import{ span: Default::default(), uri: "a_b-file.fidl".to_string(), namespace: String::new()})));
+        assert_eq!(run_node(parse_import, " import a.b.c.* from 'a_b-file.fidl'"),
+                   Ok(("", ast::Import{ span: Default::default(), uri: "a_b-file.fidl".to_string(), namespace: "a.b.c.*".to_string()})));
     }
 
     #[test]
     fn test_interface() {
         let txt = "interface MyInterface { version {major 1 minor 34} }    ";
-        let (_, interface) = parse_interface(txt).unwrap();
+        let (_, interface) = run_node(parse_interface, txt).unwrap();
         if let ModuleContent::Interface(intf) = interface {
             assert_eq!(intf.annotation, None);
             assert_eq!(intf.name, "MyInterface");
             assert_eq!(intf.version, Some((1, 34)));
         }
         else {
-            assert!(false, "interface is not ModuleContent::Interface");
+            panic!("interface is not ModuleContent::Interface");
         }
 
         let txt = "<** This is an annotation **> \ninterface Another_Interface\n{ \n}";
-        let (_, interface) = parse_interface(txt).unwrap();
+        let (_, interface) = run_node(parse_interface, txt).unwrap();
         if let ModuleContent::Interface(intf) = interface {
-            assert_eq!(intf.annotation, Some(" This is an annotation ".to_string()));
+            assert_eq!(intf.annotation, Some(" This is an annotation ".into()));
             assert_eq!(intf.name, "Another_Interface");
             assert_eq!(intf.version, None);
         }
         else {
-            assert!(false, "interface is not ModuleContent::Interface");
+            panic!("interface is not ModuleContent::Interface");
         }
     }
 
     #[test]
     fn test_string() {
-        assert_eq!(parse_string("\"a string \"add"), Ok(("add", "a string ".to_string())));
-        assert_eq!(parse_string("\'a string \'add"), Ok(("add", "a string ".to_string())));
+        assert_eq!(run(parse_string, "\"a string \"add"), Ok(("add", "a string ".to_string())));
+        assert_eq!(run(parse_string, "\'a string \'add"), Ok(("add", "a string ".to_string())));
     }
 
     #[test]
     fn test_identifier_ok() {
-        assert_eq!(parse_identifier("aSimpleIdentifier"), Ok(("", "aSimpleIdentifier")));
-        assert_eq!(parse_identifier("Simple_Identifier9 adfj"), Ok((" adfj", "Simple_Identifier9")));
-        assert_eq!(parse_identifier("^_aNew_identity!09adf"), Ok(("!09adf", "^_aNew_identity")));
+        assert_eq!(run_str(parse_identifier, "aSimpleIdentifier"), Ok(("", "aSimpleIdentifier")));
+        assert_eq!(run_str(parse_identifier, "Simple_Identifier9 adfj"), Ok((" adfj", "Simple_Identifier9")));
+        assert_eq!(run_str(parse_identifier, "^_aNew_identity!09adf"), Ok(("!09adf", "^_aNew_identity")));
     }
 
     #[test]
     fn test_identifier_nok() {
-        assert_eq!(parse_identifier(" aSimpleIdentifier"),
-                   Err(nom::Err::Error(nom::error::Error::new(" aSimpleIdentifier", nom::error::ErrorKind::Tag))));
-        assert_eq!(parse_identifier("9invalid with number"),
-                   Err(nom::Err::Error(nom::error::Error::new("9invalid with number", nom::error::ErrorKind::Tag))));
-        assert_eq!(parse_identifier("!ui ui"),
-                   Err(nom::Err::Error(nom::error::Error::new("!ui ui", nom::error::ErrorKind::Tag))));
+        assert_eq!(run_str(parse_identifier, " aSimpleIdentifier"),
+                   Err(nom::Err::Error(nom::error::Error::new(" aSimpleIdentifier", nom::error::ErrorKind::Alpha))));
+        assert_eq!(run_str(parse_identifier, "9invalid with number"),
+                   Err(nom::Err::Error(nom::error::Error::new("9invalid with number", nom::error::ErrorKind::Alpha))));
+        assert_eq!(run_str(parse_identifier, "!ui ui"),
+                   Err(nom::Err::Error(nom::error::Error::new("!ui ui", nom::error::ErrorKind::Alpha))));
     }
 
     #[test]
     fn test_imported_fqn() {
-        assert_eq!(parse_imported_fqn("ac.b.de  "), Ok(("", "ac.b.de")));
-        assert_eq!(parse_imported_fqn("_a.cde.zd1.* "), Ok(("", "_a.cde.zd1.*")));
+        assert_eq!(run_str(parse_imported_fqn, "ac.b.de  "), Ok(("", "ac.b.de")));
+        assert_eq!(run_str(parse_imported_fqn, "_a.cde.zd1.* "), Ok(("", "_a.cde.zd1.*")));
     }
 
     #[test]
     fn test_fqn_ok() {
-        assert_eq!(parse_fqn("acad.ad09_.ab"), Ok(("", "acad.ad09_.ab")));
-        assert_eq!(parse_fqn("_903.xaf.Ab9.__holla therest"), Ok((" therest", "_903.xaf.Ab9.__holla")));
-        assert_eq!(parse_fqn("_903 xaf.Ab9.__holla therest"), Ok((" xaf.Ab9.__holla therest", "_903")));
+        assert_eq!(run_str(parse_fqn, "acad.ad09_.ab"), Ok(("", "acad.ad09_.ab")));
+        assert_eq!(run_str(parse_fqn, "_903.xaf.Ab9.__holla therest"), Ok((" therest", "_903.xaf.Ab9.__holla")));
+        assert_eq!(run_str(parse_fqn, "_903 xaf.Ab9.__holla therest"), Ok((" xaf.Ab9.__holla therest", "_903")));
     }
 
     #[test]
     fn test_fqn_nok() {
-        assert_eq!(parse_fqn("0acad.ad09_.ab"),
-                   Err(nom::Err::Error(nom::error::Error::new("0acad.ad09_.ab", nom::error::ErrorKind::Tag))));
+        assert_eq!(run_str(parse_fqn, "0acad.ad09_.ab"),
+                   Err(nom::Err::Error(nom::error::Error::new("0acad.ad09_.ab", nom::error::ErrorKind::Alpha))));
     }
 
     #[test]
     fn test_package_ok() {
-        assert_eq!(parse_package("  package    my.package"), Ok(("", "my.package".to_string())));
-        assert_eq!(parse_package("package ^anew.package.p01\nrubbish"), Ok(("rubbish", "^anew.package.p01".to_string())));
+        assert_eq!(run(parse_package, "  package    my.package"), Ok(("", "my.package".to_string())));
+        assert_eq!(run(parse_package, "package ^anew.package.p01\nrubbish"), Ok(("rubbish", "^anew.package.p01".to_string())));
     }
 
     #[test]
     fn test_package_nok() {
-        assert_eq!(parse_package("  ackage    my.package"),
+        assert_eq!(run(parse_package, "  ackage    my.package"),
                    Err(nom::Err::Error(nom::error::Error::new("  ackage    my.package", nom::error::ErrorKind::Verify))));
     }
 }
@@ -0,0 +1,658 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+//! Prints an `ast::Module` as canonically formatted FIDL text. Parsing the printed text yields
+//! the printed module again apart from the spans, which refer to the printed text:
+//! `parse_module(&print(&m)).unwrap().1.without_spans() == m.without_spans()` (see `ast::ClearSpans`).
+//! Annotation blocks are printed verbatim, strings containing `"` are quoted with `'`.
+
+use super::ast;
+
+/// Indentation of nested definitions.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Indent {
+    /// The given number of spaces per level
+    Spaces(usize),
+    Tab,
+}
+
+/// Where blank lines separate definitions.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BlankLines {
+    /// No blank lines at all
+    None,
+
+    /// Between package, imports, interfaces and type collections
+    Definitions,
+
+    /// Additionally between the members of interfaces and type collections
+    Members,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PrintOptions {
+    pub indent: Indent,
+    pub blank_lines: BlankLines,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        PrintOptions{ indent: Indent::Spaces(4), blank_lines: BlankLines::Members }
+    }
+}
+
+/// Prints the module with the default options: four spaces indentation and blank lines between
+/// definitions and members.
+pub fn print(module: &ast::Module) -> String {
+    print_with(module, &PrintOptions::default())
+}
+
+pub fn print_with(module: &ast::Module, options: &PrintOptions) -> String {
+    let mut printer = Printer{ options: *options, out: String::new(), level: 0 };
+    printer.module(module);
+    printer.out
+}
+
+struct Printer {
+    options: PrintOptions,
+    out: String,
+    level: usize,
+}
+
+impl Printer {
+    /// Writes one line at the current indentation.
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.level {
+            match self.options.indent {
+                Indent::Spaces(n) => self.out.push_str(&" ".repeat(n)),
+                Indent::Tab => self.out.push('\t'),
+            }
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    /// Writes a blank line if the policy asks for one at `level`, never two in a row.
+    fn blank(&mut self, level: BlankLines) {
+        let wanted = match self.options.blank_lines {
+            BlankLines::None => false,
+            BlankLines::Definitions => level == BlankLines::Definitions,
+            BlankLines::Members => true,
+        };
+        if wanted && !self.out.is_empty() && !self.out.ends_with("\n\n") && !self.out.ends_with("{\n") {
+            self.out.push('\n');
+        }
+    }
+
+    /// Writes `header {`, the block written by `body` one level deeper and the closing brace.
+    /// Empty blocks are written as `header { }`.
+    fn block(&mut self, header: &str, body: impl FnOnce(&mut Printer)) {
+        let start = self.out.len();
+        let header = if header.is_empty() { String::new() } else { format!("{} ", header) };
+        self.line(&format!("{}{{", header));
+        let inner = self.out.len();
+        self.level += 1;
+        body(self);
+        self.level -= 1;
+        if self.out.len() == inner {
+            self.out.truncate(start);
+            self.line(&format!("{}{{ }}", header));
+        } else {
+            while self.out.ends_with("\n\n") {
+                self.out.pop();
+            }
+            self.line("}");
+        }
+    }
+
+    fn annotation(&mut self, annotation: &Option<ast::AnnotationBlock>) {
+        if let Some(annotation) = annotation {
+            self.line(&format!("<**{}**>", annotation.raw));
+        }
+    }
+
+    fn module(&mut self, module: &ast::Module) {
+        self.line(&format!("package {}", module.package));
+        self.blank(BlankLines::Definitions);
+        for import in &module.imports {
+            if import.namespace.is_empty() {
+                self.line(&format!("import model {}", string(&import.uri)));
+            } else {
+                self.line(&format!("import {} from {}", import.namespace, string(&import.uri)));
+            }
+        }
+        for type_collection in &module.type_collections {
+            self.blank(BlankLines::Definitions);
+            self.type_collection(type_collection);
+        }
+        for interface in &module.interfaces {
+            self.blank(BlankLines::Definitions);
+            self.interface(interface);
+        }
+    }
+
+    fn version(&mut self, version: &Option<(u32, u32)>) {
+        if let Some((major, minor)) = version {
+            self.line(&format!("version {{ major {} minor {} }}", major, minor));
+        }
+    }
+
+    fn type_collection(&mut self, type_collection: &ast::TypeCollection) {
+        self.annotation(&type_collection.annotation);
+        let header = match &type_collection.name {
+            Some(name) => format!("typeCollection {}", name),
+            None => "typeCollection".to_string(),
+        };
+        self.block(&header, |p| {
+            p.version(&type_collection.version);
+            p.types_and_constants(&type_collection.types, &type_collection.constants);
+        });
+    }
+
+    fn interface(&mut self, interface: &ast::Interface) {
+        self.annotation(&interface.annotation);
+        let mut header = format!("interface {}", interface.name);
+        if let Some(base) = &interface.extends {
+            header.push_str(&format!(" extends {}", base));
+        }
+        if let Some(manages) = &interface.manages {
+            header.push_str(&format!(" manages {}", manages.join(", ")));
+        }
+        self.block(&header, |p| {
+            p.version(&interface.version);
+            for attribute in &interface.attributes {
+                p.blank(BlankLines::Members);
+                p.attribute(attribute);
+            }
+            for method in &interface.methods {
+                p.blank(BlankLines::Members);
+                p.method(method);
+            }
+            for broadcast in &interface.broadcasts {
+                p.blank(BlankLines::Members);
+                p.broadcast(broadcast);
+            }
+            p.types_and_constants(&interface.types, &interface.constants);
+            if let Some(contract) = &interface.contract {
+                p.blank(BlankLines::Members);
+                p.contract(contract);
+            }
+        });
+    }
+
+    fn types_and_constants(&mut self, types: &[ast::Type], constants: &[ast::Constant]) {
+        for tp in types {
+            self.blank(BlankLines::Members);
+            self.type_definition(tp);
+        }
+        for constant in constants {
+            self.blank(BlankLines::Members);
+            self.annotation(&constant.annotation);
+            self.line(&format!("const {} {} = {}", typed(&constant.type_ref, constant.array), constant.name,
+                               initializer(&constant.value)));
+        }
+    }
+
+    fn attribute(&mut self, attribute: &ast::Attribute) {
+        self.annotation(&attribute.annotation);
+        let mut text = format!("attribute {} {}", typed(&attribute.type_ref, attribute.array), attribute.name);
+        for (set, flag) in [(attribute.read_only, "readonly"), (attribute.no_read, "noRead"),
+                            (attribute.no_subscription, "noSubscription")] {
+            if set {
+                text.push(' ');
+                text.push_str(flag);
+            }
+        }
+        self.line(&text);
+    }
+
+    fn arguments(&mut self, keyword: &str, args: &[ast::Argument]) {
+        if args.is_empty() {
+            return;
+        }
+        self.block(keyword, |p| {
+            for arg in args {
+                p.annotation(&arg.annotation);
+                p.line(&format!("{} {}", typed(&arg.type_ref, arg.array), arg.name));
+            }
+        });
+    }
+
+    fn method(&mut self, method: &ast::Method) {
+        self.annotation(&method.annotation);
+        let mut header = format!("method {}", with_selector(&method.name, &method.selector));
+        if method.fire_and_forget {
+            header.push_str(" fireAndForget");
+        }
+        self.block(&header, |p| {
+            p.arguments("in", &method.in_args);
+            p.arguments("out", &method.out_args);
+            match &method.error {
+                Some(ast::MethodErrorSpec::Reference{ annotation, fqn }) => {
+                    p.annotation(annotation);
+                    p.line(&format!("error {}", fqn));
+                },
+                Some(ast::MethodErrorSpec::EnumerationBody{ annotation, extends, enumerators }) => {
+                    p.annotation(annotation);
+                    let header = match extends {
+                        Some(base) => format!("error extends {}", type_ref(base)),
+                        None => "error".to_string(),
+                    };
+                    p.block(&header, |p| p.enumerators(enumerators));
+                },
+                None => {},
+            }
+        });
+    }
+
+    fn broadcast(&mut self, broadcast: &ast::Broadcast) {
+        self.annotation(&broadcast.annotation);
+        let mut header = format!("broadcast {}", with_selector(&broadcast.name, &broadcast.selector));
+        if broadcast.selective {
+            header.push_str(" selective");
+        }
+        self.block(&header, |p| p.arguments("out", &broadcast.out_args));
+    }
+
+    fn enumerators(&mut self, enumerators: &[ast::Enumerator]) {
+        for enumerator in enumerators {
+            self.annotation(&enumerator.annotation);
+            match enumerator.val {
+                Some(value) => self.line(&format!("{} = {}", enumerator.name, value)),
+                None => self.line(&enumerator.name),
+            }
+        }
+    }
+
+    fn fields(&mut self, fields: &[ast::Field]) {
+        for field in fields {
+            self.annotation(&field.annotation);
+            self.line(&format!("{} {}", typed(&field.type_ref, field.array), field.name));
+        }
+    }
+
+    fn type_definition(&mut self, tp: &ast::Type) {
+        let (annotation, public) = match tp {
+            ast::Type::TypeDef{ annotation, public, .. } | ast::Type::Array{ annotation, public, .. } |
+            ast::Type::Struct{ annotation, public, .. } | ast::Type::Union{ annotation, public, .. } |
+            ast::Type::Map{ annotation, public, .. } | ast::Type::Enumeration{ annotation, public, .. } =>
+                (annotation, *public),
+        };
+        self.annotation(annotation);
+        let public = if public { "public " } else { "" };
+        match tp {
+            ast::Type::TypeDef{ name, array, actual_type, .. } =>
+                self.line(&format!("{}typedef {} is {}", public, name, typed(actual_type, *array))),
+            ast::Type::Array{ name, element_type, .. } =>
+                self.line(&format!("{}array {} of {}", public, name, type_ref(element_type))),
+            ast::Type::Struct{ name, polymorphic, extends, fields, .. } => {
+                let mut header = format!("{}struct {}", public, name);
+                if let Some(base) = extends {
+                    header.push_str(&format!(" extends {}", base));
+                }
+                if *polymorphic {
+                    header.push_str(" polymorphic");
+                }
+                self.block(&header, |p| p.fields(fields));
+            },
+            ast::Type::Union{ name, base_type, fields, .. } => {
+                let mut header = format!("{}union {}", public, name);
+                if let Some(base) = base_type {
+                    header.push_str(&format!(" extends {}", base));
+                }
+                self.block(&header, |p| p.fields(fields));
+            },
+            ast::Type::Map{ name, key_type, value_type, .. } =>
+                self.line(&format!("{}map {} {{ {} to {} }}", public, name, type_ref(key_type), type_ref(value_type))),
+            ast::Type::Enumeration{ name, base_type, enumerators, .. } => {
+                let mut header = format!("{}enumeration {}", public, name);
+                if let Some(base) = base_type {
+                    header.push_str(&format!(" extends {}", type_ref(base)));
+                }
+                self.block(&header, |p| p.enumerators(enumerators));
+            },
+        }
+    }
+
+    fn contract(&mut self, contract: &ast::Contract) {
+        self.block("contract", |p| {
+            if !contract.variables.is_empty() {
+                p.block("vars", |p| {
+                    for variable in &contract.variables {
+                        let mut text = format!("{} {}", typed(&variable.type_ref, variable.array), variable.name);
+                        if let Some(value) = &variable.value {
+                            text.push_str(&format!(" = {}", initializer(value)));
+                        }
+                        text.push(';');
+                        p.line(&text);
+                    }
+                });
+            }
+            p.block("PSM", |p| {
+                p.line(&format!("initial {}", contract.state_graph.initial));
+                for state in &contract.state_graph.states {
+                    p.annotation(&state.annotation);
+                    p.block(&format!("state {}", state.name), |p| {
+                        for transition in &state.transitions {
+                            p.transition(transition);
+                        }
+                    });
+                }
+            });
+        });
+    }
+
+    fn transition(&mut self, transition: &ast::Transition) {
+        let trigger = match &transition.trigger {
+            ast::Trigger::Call{ method, selector } => format!("call {}", with_selector(method, selector)),
+            ast::Trigger::Respond{ method, selector } => format!("respond {}", with_selector(method, selector)),
+            ast::Trigger::Error{ method, selector } => format!("error {}", with_selector(method, selector)),
+            ast::Trigger::Signal{ broadcast, selector } => format!("signal {}", with_selector(broadcast, selector)),
+            ast::Trigger::Set{ attribute } => format!("set {}", attribute),
+            ast::Trigger::Update{ attribute } => format!("update {}", attribute),
+        };
+        let mut header = format!("on {}", trigger);
+        if let Some(guard) = &transition.guard {
+            header.push_str(&format!(" [{}]", expression(guard)));
+        }
+        header.push_str(&format!(" -> {}", transition.to));
+        match &transition.action {
+            Some(statements) => self.block(&header, |p| p.statements(statements)),
+            None => self.line(&header),
+        }
+    }
+
+    fn statements(&mut self, statements: &[ast::Statement]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &ast::Statement) {
+        match statement {
+            ast::Statement::Assignment{ lhs, rhs } =>
+                self.line(&format!("{} = {}", expression(lhs), expression(rhs))),
+            ast::Statement::Block(statements) => self.block("", |p| p.statements(statements)),
+            ast::Statement::If{ condition, then, otherwise } => {
+                self.branch(&format!("if ({})", expression(condition)), then);
+                if let Some(otherwise) = otherwise {
+                    self.branch("else", otherwise);
+                }
+            },
+        }
+    }
+
+    /// Writes `header` followed by a block statement, other statements go to the next line.
+    fn branch(&mut self, header: &str, statement: &ast::Statement) {
+        match statement {
+            ast::Statement::Block(statements) => self.block(header, |p| p.statements(statements)),
+            _ => {
+                self.line(header);
+                self.level += 1;
+                self.statement(statement);
+                self.level -= 1;
+            },
+        }
+    }
+}
+
+fn with_selector(name: &str, selector: &Option<String>) -> String {
+    match selector {
+        Some(selector) => format!("{}:{}", name, selector),
+        None => name.to_string(),
+    }
+}
+
+/// FIDL strings have no escape sequences, strings containing `"` are quoted with `'`.
+fn string(text: &str) -> String {
+    if text.contains('"') {
+        format!("'{}'", text)
+    } else {
+        format!("\"{}\"", text)
+    }
+}
+
+fn type_ref(type_ref: &ast::TypeRef) -> String {
+    match type_ref {
+        ast::TypeRef::Derived(name) => name.clone(),
+        ast::TypeRef::Undefined => "undefined".to_string(),
+        ast::TypeRef::Int8 => "Int8".to_string(),
+        ast::TypeRef::UInt8 => "UInt8".to_string(),
+        ast::TypeRef::Int16 => "Int16".to_string(),
+        ast::TypeRef::UInt16 => "UInt16".to_string(),
+        ast::TypeRef::Int32 => "Int32".to_string(),
+        ast::TypeRef::UInt32 => "UInt32".to_string(),
+        ast::TypeRef::Int64 => "Int64".to_string(),
+        ast::TypeRef::UInt64 => "UInt64".to_string(),
+        ast::TypeRef::Boolean => "Boolean".to_string(),
+        ast::TypeRef::String => "String".to_string(),
+        ast::TypeRef::Float => "Float".to_string(),
+        ast::TypeRef::Double => "Double".to_string(),
+        ast::TypeRef::ByteBuffer => "ByteBuffer".to_string(),
+        ast::TypeRef::IntegerInterval(None, None) => "Integer".to_string(),
+        ast::TypeRef::IntegerInterval(lower, upper) => format!("Integer({}, {})",
+            lower.map_or("minInt".to_string(), |l| l.to_string()),
+            upper.map_or("maxInt".to_string(), |u| u.to_string())),
+    }
+}
+
+/// Type reference followed by `[]` for arrays.
+fn typed(tp: &ast::TypeRef, array: bool) -> String {
+    if array { format!("{}[]", type_ref(tp)) } else { type_ref(tp) }
+}
+
+fn initializer(value: &ast::InitializerExpression) -> String {
+    match value {
+        ast::InitializerExpression::Expression(e) => expression(e),
+        ast::InitializerExpression::Compound(fields) if fields.is_empty() => "{ }".to_string(),
+        ast::InitializerExpression::Compound(fields) => {
+            let fields: Vec<String> = fields.iter()
+                .map(|f| format!("{}: {}", f.element, initializer(&f.value)))
+                .collect();
+            format!("{{ {} }}", fields.join(", "))
+        },
+        ast::InitializerExpression::Bracket(elements) if elements.is_empty() => "[ ]".to_string(),
+        ast::InitializerExpression::Bracket(elements) => {
+            let elements: Vec<String> = elements.iter()
+                .map(|e| match &e.second {
+                    Some(second) => format!("{} => {}", initializer(&e.first), initializer(second)),
+                    None => initializer(&e.first),
+                })
+                .collect();
+            format!("[ {} ]", elements.join(", "))
+        },
+    }
+}
+
+/// Binding strength of binary operators, unary operations and primary expressions bind tightest.
+fn precedence(op: ast::Operator) -> u8 {
+    match op {
+        ast::Operator::Or => 1,
+        ast::Operator::And => 2,
+        ast::Operator::Equal | ast::Operator::Unequal => 3,
+        ast::Operator::Smaller | ast::Operator::SmallerOrEqual | ast::Operator::GreaterOrEqual |
+        ast::Operator::Greater => 4,
+        ast::Operator::Addition | ast::Operator::Subtraction => 5,
+        ast::Operator::Multiplication | ast::Operator::Division => 6,
+        ast::Operator::Negation => 7,
+    }
+}
+
+fn operator(op: ast::Operator) -> &'static str {
+    match op {
+        ast::Operator::Or => "||",
+        ast::Operator::And => "&&",
+        ast::Operator::Equal => "==",
+        ast::Operator::Unequal => "!=",
+        ast::Operator::Smaller => "<",
+        ast::Operator::SmallerOrEqual => "<=",
+        ast::Operator::GreaterOrEqual => ">=",
+        ast::Operator::Greater => ">",
+        ast::Operator::Addition => "+",
+        ast::Operator::Subtraction => "-",
+        ast::Operator::Multiplication => "*",
+        ast::Operator::Division => "/",
+        ast::Operator::Negation => "!",
+    }
+}
+
+/// Prints the expression with the parentheses needed to keep the tree: binary operators are left
+/// associative and the operand of a unary operation is a primary expression.
+fn expression(e: &ast::Expression) -> String {
+    match e {
+        ast::Expression::Binary{ left, op, right } => {
+            let p = precedence(*op);
+            let left = match left.as_ref() {
+                ast::Expression::Binary{ op: l, .. } if precedence(*l) < p => format!("({})", expression(left)),
+                _ => expression(left),
+            };
+            let right = match right.as_ref() {
+                ast::Expression::Binary{ op: r, .. } if precedence(*r) <= p => format!("({})", expression(right)),
+                _ => expression(right),
+            };
+            format!("{} {} {}", left, operator(*op), right)
+        },
+        ast::Expression::Unary{ op, operand } => match operand.as_ref() {
+            ast::Expression::Binary{ .. } | ast::Expression::Unary{ .. } => format!("{}({})", operator(*op), expression(operand)),
+            _ => format!("{}{}", operator(*op), expression(operand)),
+        },
+        ast::Expression::ElementRef{ element, fields } => {
+            let mut text = element.clone();
+            for field in fields {
+                text.push_str("->");
+                text.push_str(field);
+            }
+            text
+        },
+        ast::Expression::CurrentError => "errorval".to_string(),
+        ast::Expression::ErrorEnumRef(enumerator) => format!("errordef.{}", enumerator),
+        ast::Expression::Literal(literal) => match literal {
+            ast::Literal::Integer(value) => value.to_string(),
+            ast::Literal::Float(value) => format!("{}f", value),
+            ast::Literal::Double(value) => format!("{}d", value),
+            ast::Literal::Boolean(value) => value.to_string(),
+            ast::Literal::String(value) => string(value),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ast::ClearSpans;
+    use crate::parser::parse_module;
+
+    /// Prints the module parsed from `text`, checks that the printed text parses to the same module.
+    fn round_trip(text: &str, options: &PrintOptions) -> String {
+        let module = parse_module(text).unwrap().1;
+        let printed = print_with(&module, options);
+        let (rest, reparsed) = parse_module(&printed).unwrap();
+        assert_eq!(rest, "", "{}", printed);
+        assert_eq!(reparsed.without_spans(), module.without_spans(), "{}", printed);
+        printed
+    }
+
+    #[test]
+    fn test_format() {
+        let printed = round_trip("package a.b import c.* from \"c.fidl\" import model 'x\"y.fidl'
+            <** @description: Service **> interface I extends B manages C, D { version { major 1 minor 2 }
+            attribute UInt8[] a readonly noSubscription method m:s fireAndForget { in { String n } }
+            method e { out { Boolean ok } error extends Base { A = 1 B } } broadcast b selective { }
+            public struct S extends T polymorphic { Integer(-5, maxInt) i <** @see: x **> S[] more }
+            typedef Ids is UInt16[] map M { String to Double } const Float f = 1.5f }
+            typeCollection { enumeration E { X } }", &PrintOptions::default());
+        assert_eq!(printed, "package a.b
+
+import c.* from \"c.fidl\"
+import model 'x\"y.fidl'
+
+typeCollection {
+    enumeration E {
+        X
+    }
+}
+
+<** @description: Service **>
+interface I extends B manages C, D {
+    version { major 1 minor 2 }
+
+    attribute UInt8[] a readonly noSubscription
+
+    method m:s fireAndForget {
+        in {
+            String n
+        }
+    }
+
+    method e {
+        out {
+            Boolean ok
+        }
+        error extends Base {
+            A = 1
+            B
+        }
+    }
+
+    broadcast b selective { }
+
+    public struct S extends T polymorphic {
+        Integer(-5, maxInt) i
+        <** @see: x **>
+        S[] more
+    }
+
+    typedef Ids is UInt16[]
+
+    map M { String to Double }
+
+    const Float f = 1.5f
+}
+");
+    }
+
+    #[test]
+    fn test_options() {
+        let text = "package a interface I { attribute UInt8 a attribute UInt8 b struct S { UInt8 x } }
+            typeCollection T { }";
+        assert_eq!(round_trip(text, &PrintOptions{ indent: Indent::Tab, blank_lines: BlankLines::None }),
+                   "package a\ntypeCollection T { }\ninterface I {\n\tattribute UInt8 a\n\tattribute UInt8 b\n\
+                    \tstruct S {\n\t\tUInt8 x\n\t}\n}\n");
+        assert_eq!(round_trip(text, &PrintOptions{ indent: Indent::Spaces(2), blank_lines: BlankLines::Definitions }),
+                   "package a\n\ntypeCollection T { }\n\ninterface I {\n  attribute UInt8 a\n  attribute UInt8 b\n\
+                    \x20 struct S {\n    UInt8 x\n  }\n}\n");
+    }
+
+    #[test]
+    fn test_expressions() {
+        round_trip("package a typeCollection T {
+            const Int32 a = 1 - (2 - 3) * -(4 + 5) / 6
+            const Boolean b = !(x || y) && (a < 3 == true) || !z
+            const Int32 c = (1 + 2) * 3 - x->y->z
+            const String s = \"text\"
+            const Double d = 1.0e-3d
+            const S st = { a: 1, b: [ 1, 2 ], c: { } }
+            const M m = [ 1 => \"one\", 2 => \"two\" ]
+            const A e = [ ]
+        }", &PrintOptions::default());
+    }
+
+    #[test]
+    fn test_contract() {
+        let printed = round_trip("package a interface I { attribute UInt8 x method m { } broadcast b { }
+            contract { vars { UInt32 count = 0; Boolean busy }
+            PSM { initial Idle
+                state Idle { on call m [count < 3 && !busy] -> Busy { count = count + 1 busy = true }
+                    on set x -> Idle }
+                <** @description: busy **> state Busy { on respond m -> Idle {
+                    if (count > 2) { busy = false } else if (busy) count = 0 else busy = true }
+                    on signal b:sel -> Busy on error m -> Idle on update x -> Busy } } } }",
+            &PrintOptions::default());
+        assert!(printed.contains("        on call m [count < 3 && !busy] -> Busy {\n"), "{}", printed);
+        assert!(printed.contains("        if (count > 2) {\n                        busy = false\n                    }\n\
+                                  \x20                   else\n                        if (busy)\n"), "{}", printed);
+    }
+}
@@ -0,0 +1,311 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+//! Access to the FIDL sources for the compiler: the file system or texts held in memory.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Reason an import URI could not be resolved.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ResolveError {
+    /// No source found, `searched` lists the paths tried in search order
+    NotFound{ searched: Vec<PathBuf> },
+
+    /// URI scheme other than `file:`, `classpath:` and `platform:`
+    UnknownScheme{ scheme: String },
+
+    /// `platform:/resource/<project>/` URI of a project without configured root
+    UnknownProject{ project: String },
+
+    /// URI of a supported scheme not following its syntax
+    InvalidUri{ reason: String },
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::NotFound{ searched } if searched.is_empty() => write!(f, "file not found"),
+            ResolveError::NotFound{ searched } => {
+                let paths: Vec<String> = searched.iter().map(|p| p.display().to_string()).collect();
+                write!(f, "file not found, searched {}", paths.join(", "))
+            },
+            ResolveError::UnknownScheme{ scheme } => write!(f, "unknown URI scheme `{}`", scheme),
+            ResolveError::UnknownProject{ project } => write!(f, "no root directory configured for project `{}`", project),
+            ResolveError::InvalidUri{ reason } => f.write_str(reason),
+        }
+    }
+}
+
+/// Source of FIDL texts used by the compiler to resolve imports and read files.
+pub trait SourceProvider: Send + Sync {
+    /// `true` if `path` denotes a readable source.
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Unique name of the source at `path`, sources reached via different paths are parsed once.
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Text of the source at `path`.
+    fn read(&self, path: &Path) -> io::Result<String>;
+
+    /// Root directory of an Eclipse project, used for `platform:/resource/<project>/` URIs.
+    fn project_root(&self, _project: &str) -> Option<&Path> {
+        None
+    }
+
+    /// Resolves the import `uri` of `importer`. Plain paths and `file:` URIs are taken as they are
+    /// if absolute, relative ones are searched in the directory of the importer and then in
+    /// `search_dirs`. `classpath:/` URIs are searched in `search_dirs` only and
+    /// `platform:/resource/<project>/` URIs relative to the root of the project.
+    fn resolve(&self, uri: &str, importer: Option<&Path>, search_dirs: &[PathBuf]) -> Result<PathBuf, ResolveError> {
+        let (scheme, rest) = match split_scheme(uri) {
+            Some((scheme, rest)) => (scheme.to_ascii_lowercase(), percent_decode(rest)?),
+            None => return self.search(Path::new(uri), importer.and_then(Path::parent), search_dirs),
+        };
+        match scheme.as_str() {
+            "file" => {
+                // file:///abs, file://localhost/abs, file:/abs or file:relative
+                let path = match rest.strip_prefix("//") {
+                    Some(authority) => match authority.find('/') {
+                        Some(0) => authority,
+                        Some(i) if &authority[..i] == "localhost" => &authority[i..],
+                        _ => return Err(ResolveError::InvalidUri{ reason: format!("unsupported host in `{}`", uri) }),
+                    },
+                    None => rest.as_str(),
+                };
+                self.search(Path::new(path), importer.and_then(Path::parent), search_dirs)
+            },
+            "classpath" => self.search(Path::new(rest.trim_start_matches('/')), None, search_dirs),
+            "platform" => {
+                let Some(resource) = rest.strip_prefix("/resource/") else {
+                    return Err(ResolveError::InvalidUri{ reason: format!(
+                        "only `platform:/resource/<project>/` URIs are supported, found `{}`", uri) });
+                };
+                let (project, path) = resource.split_once('/').unwrap_or((resource, ""));
+                let root = self.project_root(project)
+                    .ok_or_else(|| ResolveError::UnknownProject{ project: project.to_string() })?;
+                let file = root.join(path);
+                if self.exists(&file) { Ok(file) } else { Err(ResolveError::NotFound{ searched: vec![file] }) }
+            },
+            _ => Err(ResolveError::UnknownScheme{ scheme }),
+        }
+    }
+
+    /// Searches `path`: absolute paths as they are, relative ones in `current_dir` and then in
+    /// `search_dirs`.
+    fn search(&self, path: &Path, current_dir: Option<&Path>, search_dirs: &[PathBuf]) -> Result<PathBuf, ResolveError> {
+        if path.is_absolute() {
+            if self.exists(path) {
+                return Ok(path.to_path_buf())
+            }
+            return Err(ResolveError::NotFound{ searched: vec![path.to_path_buf()] });
+        }
+
+        let mut searched = Vec::new();
+        for dir in current_dir.into_iter().chain(search_dirs.iter().map(PathBuf::as_path)) {
+            let f = dir.join(path);
+            if self.exists(&f) {
+                return Ok(f);
+            }
+            searched.push(f);
+        }
+        Err(ResolveError::NotFound{ searched })
+    }
+}
+
+/// Sources read from the file system.
+#[derive(Clone, Debug, Default)]
+pub struct FileSystem {
+    projects: HashMap<String, PathBuf>,
+}
+
+impl FileSystem {
+    pub fn new() -> Self {
+        FileSystem::default()
+    }
+
+    /// Sets the root directory of `project` for `platform:/resource/<project>/` URIs.
+    pub fn with_project(mut self, project: impl Into<String>, root: impl Into<PathBuf>) -> Self {
+        self.projects.insert(project.into(), root.into());
+        self
+    }
+}
+
+impl SourceProvider for FileSystem {
+    fn exists(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        path.canonicalize()
+    }
+
+    fn read(&self, path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn project_root(&self, project: &str) -> Option<&Path> {
+        self.projects.get(project).map(PathBuf::as_path)
+    }
+}
+
+/// Sources held in memory, e.g. unsaved editor buffers or files extracted from an archive.
+/// Paths are compared after removing `.` and `..` components.
+#[derive(Clone, Debug, Default)]
+pub struct InMemory {
+    files: HashMap<PathBuf, String>,
+    projects: HashMap<String, PathBuf>,
+}
+
+impl InMemory {
+    pub fn new() -> Self {
+        InMemory::default()
+    }
+
+    /// Adds or replaces the source at `path`.
+    pub fn insert(&mut self, path: impl AsRef<Path>, text: impl Into<String>) {
+        self.files.insert(normalize(path.as_ref()), text.into());
+    }
+
+    /// Builder style variant of `insert`.
+    pub fn with(mut self, path: impl AsRef<Path>, text: impl Into<String>) -> Self {
+        self.insert(path, text);
+        self
+    }
+
+    /// Sets the root directory of `project` for `platform:/resource/<project>/` URIs.
+    pub fn with_project(mut self, project: impl Into<String>, root: impl Into<PathBuf>) -> Self {
+        self.projects.insert(project.into(), root.into());
+        self
+    }
+}
+
+impl SourceProvider for InMemory {
+    fn exists(&self, path: &Path) -> bool {
+        self.files.contains_key(&normalize(path))
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        let path = normalize(path);
+        if self.files.contains_key(&path) {
+            Ok(path)
+        } else {
+            Err(not_found(&path))
+        }
+    }
+
+    fn read(&self, path: &Path) -> io::Result<String> {
+        let path = normalize(path);
+        self.files.get(&path).cloned().ok_or_else(|| not_found(&path))
+    }
+
+    fn project_root(&self, project: &str) -> Option<&Path> {
+        self.projects.get(project).map(PathBuf::as_path)
+    }
+}
+
+/// Splits `scheme:rest`, schemes have at least two characters so Windows drive letters are
+/// taken as paths.
+fn split_scheme(uri: &str) -> Option<(&str, &str)> {
+    let (scheme, rest) = uri.split_once(':')?;
+    let mut chars = scheme.chars();
+    let valid = scheme.len() > 1
+        && chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid.then_some((scheme, rest))
+}
+
+/// Decodes `%XX` escapes of URIs.
+fn percent_decode(text: &str) -> Result<String, ResolveError> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = text.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| ResolveError::InvalidUri{ reason: format!("invalid escape sequence in `{}`", text) })?;
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| ResolveError::InvalidUri{ reason: format!("invalid UTF-8 in `{}`", text) })
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display()))
+}
+
+/// Removes `.` and `..` components without consulting the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => if !result.pop() {
+                result.push("..");
+            },
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_in_memory() {
+        let sources = InMemory::new()
+            .with("api/Service.fidl", "package a")
+            .with("types/Types.fidl", "package b")
+            .with("api/Local.fidl", "package c");
+        assert_eq!(sources.read(Path::new("api/../types/./Types.fidl")).unwrap(), "package b");
+        assert_eq!(sources.canonicalize(Path::new("./api/Service.fidl")).unwrap(), PathBuf::from("api/Service.fidl"));
+        assert_eq!(sources.read(Path::new("Types.fidl")).unwrap_err().kind(), io::ErrorKind::NotFound);
+
+        let importer = Path::new("api/Service.fidl");
+        let search_dirs = [PathBuf::from("types")];
+        assert_eq!(sources.resolve("Local.fidl", Some(importer), &search_dirs), Ok(PathBuf::from("api/Local.fidl")));
+        assert_eq!(sources.resolve("Types.fidl", Some(importer), &search_dirs), Ok(PathBuf::from("types/Types.fidl")));
+        assert_eq!(sources.resolve("Missing.fidl", Some(importer), &search_dirs),
+                   Err(ResolveError::NotFound{ searched: vec![PathBuf::from("api/Missing.fidl"), PathBuf::from("types/Missing.fidl")] }));
+        assert_eq!(sources.resolve("Missing.fidl", None, &[]), Err(ResolveError::NotFound{ searched: vec![] }));
+    }
+
+    #[test]
+    fn test_uri_schemes() {
+        let sources = InMemory::new()
+            .with("/ws/common/model/Types.fidl", "package a")
+            .with("/lib/de/company/My Types.fidl", "package b")
+            .with("api/Local.fidl", "package c")
+            .with_project("common", "/ws/common");
+        let importer = Some(Path::new("api/Service.fidl"));
+        let search_dirs = [PathBuf::from("/lib")];
+        let resolve = |uri| sources.resolve(uri, importer, &search_dirs);
+
+        assert_eq!(resolve("classpath:/de/company/My%20Types.fidl"), Ok(PathBuf::from("/lib/de/company/My Types.fidl")));
+        assert_eq!(resolve("classpath:/Local.fidl"),
+                   Err(ResolveError::NotFound{ searched: vec![PathBuf::from("/lib/Local.fidl")] }));
+        assert_eq!(resolve("platform:/resource/common/model/Types.fidl"), Ok(PathBuf::from("/ws/common/model/Types.fidl")));
+        assert_eq!(resolve("platform:/resource/other/Types.fidl"),
+                   Err(ResolveError::UnknownProject{ project: "other".to_string() }));
+        assert!(matches!(resolve("platform:/plugin/common/Types.fidl"), Err(ResolveError::InvalidUri{ .. })));
+        assert_eq!(resolve("file:///ws/common/model/Types.fidl"), Ok(PathBuf::from("/ws/common/model/Types.fidl")));
+        assert_eq!(resolve("file://localhost/ws/common/model/Types.fidl"), Ok(PathBuf::from("/ws/common/model/Types.fidl")));
+        assert_eq!(resolve("file:Local.fidl"), Ok(PathBuf::from("api/Local.fidl")));
+        assert!(matches!(resolve("file://server/Types.fidl"), Err(ResolveError::InvalidUri{ .. })));
+        assert!(matches!(resolve("classpath:/a%2"), Err(ResolveError::InvalidUri{ .. })));
+        assert_eq!(resolve("http://example.com/Types.fidl"), Err(ResolveError::UnknownScheme{ scheme: "http".to_string() }));
+        assert_eq!(resolve("C:/Types.fidl"), Err(ResolveError::NotFound{ searched: vec![
+            PathBuf::from("api/C:/Types.fidl"), PathBuf::from("/lib/C:/Types.fidl")] }));
+    }
+}
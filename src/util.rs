@@ -4,41 +4,104 @@
 //
 // Author: Alexander Seifarth
 use nom;
-use nom::character::complete::multispace0;
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_until, take_while},
+    character::complete::multispace1,
+    combinator::recognize,
+    multi::{many0, many1},
+    sequence::{pair, tuple},
+};
+use super::parser::{Input, PResult};
 
-/// Return always an Ok with either a Some(O) or a None depending on whether the parser succeeds
-/// or not
+/// Return an Ok with either a Some(O) or a None depending on whether the parser succeeds
+/// or not. Failures of a parser that already committed (see `nom::combinator::cut`) are passed on.
 pub fn option<I, O, E: nom::error::ParseError<I>, F>( mut parser: F ) -> impl FnMut(I) -> nom::IResult<I, Option<O>, E>
     where F: FnMut(I) -> nom::IResult<I, O, E>, I: Clone
 {
     move |input: I| {
         match parser(input.clone()) {
             Ok((rem, value)) => Ok((rem, Some(value))),
-            Err(_) => Ok((input, None))
+            Err(nom::Err::Error(_)) => Ok((input, None)),
+            Err(e) => Err(e),
         }
     }
 }
 
-pub fn keyword(kwrd: &str) -> impl FnMut(&str) -> nom::IResult<&str, &str>
+/// Recognizes a `//` comment up to (but not including) the end of the line.
+fn line_comment(input: Input) -> PResult<Input> {
+    recognize(pair(tag("//"), take_while(|c: char| c != '\n' && c != '\r')))(input)
+}
+
+/// Recognizes a `/* ... */` comment, comments do not nest.
+fn block_comment(input: Input) -> PResult<Input> {
+    recognize(tuple((tag("/*"), take_until("*/"), tag("*/"))))(input)
+}
+
+/// Skips any whitespace, line comments and block comments (XTEXT terminals WS, SL_COMMENT and
+/// ML_COMMENT), replacement for `multispace0` that never fails.
+pub fn ws0(input: Input) -> PResult<Input> {
+    recognize(many0(alt((multispace1, line_comment, block_comment))))(input)
+}
+
+/// Like `ws0` but requires at least one whitespace character or comment.
+pub fn ws1(input: Input) -> PResult<Input> {
+    recognize(many1(alt((multispace1, line_comment, block_comment))))(input)
+}
+
+/// Recognizes the keyword `kwrd` with surrounding whitespace. The keyword must be a complete
+/// word, `true` does not match the beginning of `true_flag`.
+pub fn keyword<'a>(kwrd: &str) -> impl FnMut(Input<'a>) -> PResult<'a, Input<'a>>
 {
-    use nom::sequence::delimited;
-    use nom::combinator::verify;
-    use nom::character::complete::alpha1;
+    use nom::sequence::{delimited, terminated};
+    use nom::combinator::{not, peek};
+    use nom::character::complete::satisfy;
+    use nom::error::{ErrorKind, ParseError};
 
     let kwdr_str = kwrd.to_string();
-    move |i: &str| {
-        let k = kwdr_str.clone();
-        verify(delimited( multispace0, alpha1, multispace0), move |s| { s == k })(i)
+    move |i: Input<'a>| {
+        let k = kwdr_str.as_str();
+        delimited(ws0, terminated(tag(k), not(peek(satisfy(|c: char| c.is_alphanumeric() || c == '_')))), ws0)(i)
+            // report the mismatch where the keyword was expected
+            .map_err(|e| e.map(|_| ParseError::from_error_kind(i, ErrorKind::Verify)))
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::util::keyword;
+    use crate::util::{keyword, ws0, ws1};
+    use crate::parser::{Input, PResult};
+
+    /// Runs `parser` on `text` and returns remainder and output as plain strings.
+    fn run<'a>(mut parser: impl FnMut(Input<'a>) -> PResult<'a, Input<'a>>, text: &'a str)
+        -> Result<(&'a str, &'a str), ()> {
+        parser(Input::new_extra(text, None))
+            .map(|(r, v)| (*r.fragment(), *v.fragment()))
+            .map_err(|_| ())
+    }
 
     #[test]
     fn test_keyword() {
-        assert_eq!(keyword("identifier")("identifier _ax"), Ok(("_ax", "identifier")));
-        assert!(keyword("module")("moduleA").is_err());
+        assert_eq!(run(keyword("identifier"), "identifier _ax"), Ok(("_ax", "identifier")));
+        assert!(run(keyword("module"), "moduleA").is_err());
+        assert!(run(keyword("true"), "true_flag").is_err());
+        assert!(run(keyword("interface"), "interface1").is_err());
+    }
+
+    #[test]
+    fn test_keyword_with_comments() {
+        assert_eq!(run(keyword("interface"), "// leading\n interface /* trailing */ X"), Ok(("X", "interface")));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_ws() {
+        assert_eq!(run(ws0, " \n\t x"), Ok(("x", " \n\t ")));
+        assert_eq!(run(ws0, "x"), Ok(("x", "")));
+        assert_eq!(run(ws0, "// a comment\n  /* block\n comment */ x"), Ok(("x", "// a comment\n  /* block\n comment */ ")));
+        assert_eq!(run(ws0, "// comment at the end"), Ok(("", "// comment at the end")));
+        assert_eq!(run(ws0, "/* unterminated"), Ok(("/* unterminated", "")));
+        assert_eq!(run(ws0, "/ not a comment"), Ok(("/ not a comment", "")));
+        assert!(run(ws1, "x").is_err());
+        assert_eq!(run(ws1, "/**/x"), Ok(("x", "/**/")));
+    }
+}
@@ -0,0 +1,372 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+//! Semantic checks of parsed FIDL modules which do not need resolved references: duplicate
+//! definitions, clashing enumerator values, inverted integer intervals and missing versions.
+
+use super::ast;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Selects the checks done by `validate`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ValidationMode {
+    /// Checks for duplicate definitions and clashing enumerator values.
+    Default,
+
+    /// Additionally requires a version for each interface.
+    Strict,
+}
+
+/// Error found while validating the modules.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ValidationError {
+    /// Two definitions of the same kind with the same name in one scope, e.g. two fields of a
+    /// struct. `previous` is the location of the first definition.
+    Duplicate{ kind: &'static str, name: String, scope: String, file: PathBuf, span: ast::Span,
+        previous: (PathBuf, ast::Span) },
+
+    /// Two definitions of different kinds with the same name in one scope, e.g. a type and a
+    /// constant of an interface. `kinds` are the kinds of the first and of this definition.
+    NameCollision{ name: String, kinds: (&'static str, &'static str), scope: String, file: PathBuf,
+        span: ast::Span, previous: (PathBuf, ast::Span) },
+
+    /// Two enumerators of an enumeration with the same value.
+    DuplicateValue{ enumeration: String, name: String, other: String, value: u64, file: PathBuf,
+        span: ast::Span },
+
+    /// Integer interval whose lower bound is greater than its upper bound, `span` is the location
+    /// of the definition using it.
+    InvertedInterval{ lower: i128, upper: i128, file: PathBuf, span: ast::Span },
+
+    /// Interface without version, only reported with `ValidationMode::Strict`.
+    MissingVersion{ interface: String, file: PathBuf, span: ast::Span },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (file, span) = match self {
+            ValidationError::Duplicate{ file, span, .. } | ValidationError::NameCollision{ file, span, .. } |
+            ValidationError::DuplicateValue{ file, span, .. } | ValidationError::InvertedInterval{ file, span, .. } |
+            ValidationError::MissingVersion{ file, span, .. } => (file, span),
+        };
+        write!(f, "{}:{}:{}: error: ", file.display(), span.line, span.column)?;
+        match self {
+            ValidationError::Duplicate{ kind, name, scope, previous, .. } =>
+                write!(f, "{} `{}` is defined more than once in {}, first definition at {}:{}:{}",
+                       kind, name, scope, previous.0.display(), previous.1.line, previous.1.column),
+            ValidationError::NameCollision{ name, kinds, scope, previous, .. } =>
+                write!(f, "`{}` is defined as {} and as {} in {}, other definition at {}:{}:{}",
+                       name, kinds.0, kinds.1, scope, previous.0.display(), previous.1.line, previous.1.column),
+            ValidationError::DuplicateValue{ enumeration, name, other, value, .. } =>
+                write!(f, "enumerator `{}` of `{}` has the value {} of enumerator `{}`", name, enumeration, value, other),
+            ValidationError::InvertedInterval{ lower, upper, .. } =>
+                write!(f, "`Integer({}, {})` has a lower bound greater than its upper bound", lower, upper),
+            ValidationError::MissingVersion{ interface, .. } =>
+                write!(f, "interface `{}` has no version", interface),
+        }
+    }
+}
+
+/// Validates the parsed modules and returns all errors found.
+/// Type collections and interfaces of the same package share one name space, even if they are
+/// defined in different files. So do the types and constants of all anonymous type collections
+/// of a package.
+pub fn validate(modules: &[(ast::Module, PathBuf)], mode: ValidationMode) -> Vec<ValidationError> {
+    let mut validator = Validator{ errors: Vec::new() };
+    let mut packages: HashMap<&str, Names> = HashMap::new();
+    for (module, file) in modules {
+        let package = packages.entry(&module.package).or_default();
+        let scope = format!("package `{}`", module.package);
+        for tc in &module.type_collections {
+            if let Some(name) = &tc.name {
+                validator.define(package, "type collection", name, file, &tc.span, &scope);
+            }
+        }
+        for interface in &module.interfaces {
+            validator.define(package, "interface", &interface.name, file, &interface.span, &scope);
+        }
+    }
+
+    let mut anonymous: HashMap<&str, Names> = HashMap::new();
+    for (module, file) in modules {
+        let package = module.package.as_str();
+        for tc in &module.type_collections {
+            match &tc.name {
+                Some(name) => validator.definitions(&mut Names::default(), &format!("{}.{}", package, name),
+                    &format!("type collection `{}.{}`", package, name), file, &tc.types, &tc.constants),
+                None => validator.definitions(anonymous.entry(package).or_default(), package,
+                    &format!("package `{}`", package), file, &tc.types, &tc.constants),
+            }
+        }
+        for interface in &module.interfaces {
+            validator.interface(package, file, interface, mode);
+        }
+    }
+    validator.errors
+}
+
+/// Names defined in one scope with the kind and location of their first definition.
+#[derive(Default)]
+struct Names<'a>(HashMap<&'a str, (&'static str, &'a Path, &'a ast::Span)>);
+
+struct Validator {
+    errors: Vec<ValidationError>,
+}
+
+impl Validator {
+    /// Records the definition of `name`, reports a duplicate or a name collision if the name is
+    /// already defined with the same or with another kind.
+    fn define<'a>(&mut self, names: &mut Names<'a>, kind: &'static str, name: &'a str, file: &'a Path,
+                  span: &'a ast::Span, scope: &str) {
+        let Some((first_kind, first_file, first_span)) = names.0.get(name) else {
+            names.0.insert(name, (kind, file, span));
+            return;
+        };
+        let previous = (first_file.to_path_buf(), (*first_span).clone());
+        self.errors.push(if *first_kind == kind {
+            ValidationError::Duplicate{ kind, name: name.to_string(), scope: scope.to_string(),
+                file: file.to_path_buf(), span: span.clone(), previous }
+        } else {
+            ValidationError::NameCollision{ name: name.to_string(), kinds: (first_kind, kind),
+                scope: scope.to_string(), file: file.to_path_buf(), span: span.clone(), previous }
+        });
+    }
+
+    fn interface(&mut self, package: &str, file: &Path, interface: &ast::Interface, mode: ValidationMode) {
+        let fqn = format!("{}.{}", package, interface.name);
+        let scope = format!("interface `{}`", fqn);
+        if mode == ValidationMode::Strict && interface.version.is_none() {
+            self.errors.push(ValidationError::MissingVersion{ interface: fqn.clone(), file: file.to_path_buf(),
+                span: interface.span.clone() });
+        }
+        self.definitions(&mut Names::default(), &fqn, &scope, file, &interface.types, &interface.constants);
+
+        let mut attributes = Names::default();
+        for attribute in &interface.attributes {
+            self.define(&mut attributes, "attribute", &attribute.name, file, &attribute.span, &scope);
+            self.interval(&attribute.type_ref, file, &attribute.span);
+        }
+
+        // methods and broadcasts may be overloaded with different selectors
+        let mut methods = HashMap::new();
+        for method in &interface.methods {
+            let name = qualified_name(&method.name, &method.selector);
+            self.unique(&mut methods, "method", name, file, &method.span, &scope);
+            let method_scope = format!("method `{}.{}`", fqn, method.name);
+            let mut args = Names::default();
+            for arg in method.in_args.iter().chain(&method.out_args) {
+                self.define(&mut args, "argument", &arg.name, file, &arg.span, &method_scope);
+                self.interval(&arg.type_ref, file, &arg.span);
+            }
+            if let Some(ast::MethodErrorSpec::EnumerationBody{ enumerators, .. }) = &method.error {
+                self.enumerators(&format!("{}.{}.error", fqn, method.name), file, enumerators);
+            }
+        }
+        let mut broadcasts = HashMap::new();
+        for broadcast in &interface.broadcasts {
+            let name = qualified_name(&broadcast.name, &broadcast.selector);
+            self.unique(&mut broadcasts, "broadcast", name, file, &broadcast.span, &scope);
+            let broadcast_scope = format!("broadcast `{}.{}`", fqn, broadcast.name);
+            let mut args = Names::default();
+            for arg in &broadcast.out_args {
+                self.define(&mut args, "argument", &arg.name, file, &arg.span, &broadcast_scope);
+                self.interval(&arg.type_ref, file, &arg.span);
+            }
+        }
+    }
+
+    /// Like `define` but for names built from several parts, e.g. `name:selector`.
+    fn unique<'a>(&mut self, names: &mut HashMap<String, &'a ast::Span>, kind: &'static str, name: String,
+                  file: &Path, span: &'a ast::Span, scope: &str) {
+        if let Some(first) = names.get(&name) {
+            self.errors.push(ValidationError::Duplicate{ kind, name, scope: scope.to_string(),
+                file: file.to_path_buf(), span: span.clone(), previous: (file.to_path_buf(), (*first).clone()) });
+        } else {
+            names.insert(name, span);
+        }
+    }
+
+    /// Checks the types and constants of an interface or type collection, `names` are the names
+    /// already defined in its scope.
+    fn definitions<'a>(&mut self, names: &mut Names<'a>, container: &str, scope: &str, file: &'a Path,
+                       types: &'a [ast::Type], constants: &'a [ast::Constant]) {
+        for tp in types {
+            self.define(names, "type", tp.name(), file, tp.span(), scope);
+            let fqn = format!("{}.{}", container, tp.name());
+            let type_scope = format!("{} `{}`", kind_name(tp), fqn);
+            match tp {
+                ast::Type::Struct{ fields, .. } | ast::Type::Union{ fields, .. } => {
+                    let mut field_names = Names::default();
+                    for field in fields {
+                        self.define(&mut field_names, "field", &field.name, file, &field.span, &type_scope);
+                        self.interval(&field.type_ref, file, &field.span);
+                    }
+                },
+                ast::Type::Enumeration{ enumerators, .. } => self.enumerators(&fqn, file, enumerators),
+                ast::Type::TypeDef{ actual_type, span, .. } => self.interval(actual_type, file, span),
+                ast::Type::Array{ element_type, span, .. } => self.interval(element_type, file, span),
+                ast::Type::Map{ key_type, value_type, span, .. } => {
+                    self.interval(key_type, file, span);
+                    self.interval(value_type, file, span);
+                },
+            }
+        }
+        for constant in constants {
+            self.define(names, "constant", &constant.name, file, &constant.span, scope);
+            self.interval(&constant.type_ref, file, &constant.span);
+        }
+    }
+
+    /// Reports an integer interval with inverted bounds used by the definition at `span`.
+    fn interval(&mut self, type_ref: &ast::TypeRef, file: &Path, span: &ast::Span) {
+        if let ast::TypeRef::IntegerInterval(Some(lower), Some(upper)) = type_ref {
+            if lower > upper {
+                self.errors.push(ValidationError::InvertedInterval{ lower: *lower, upper: *upper,
+                    file: file.to_path_buf(), span: span.clone() });
+            }
+        }
+    }
+
+    /// Checks names and explicit values of the enumerators of one enumeration.
+    fn enumerators(&mut self, enumeration: &str, file: &Path, enumerators: &[ast::Enumerator]) {
+        let scope = format!("enumeration `{}`", enumeration);
+        let mut names = Names::default();
+        let mut values: HashMap<u64, &str> = HashMap::new();
+        for enumerator in enumerators {
+            self.define(&mut names, "enumerator", &enumerator.name, file, &enumerator.span, &scope);
+            if let Some(value) = enumerator.val {
+                match values.get(&value) {
+                    Some(other) if *other != enumerator.name => self.errors.push(ValidationError::DuplicateValue{
+                        enumeration: enumeration.to_string(), name: enumerator.name.clone(), other: other.to_string(),
+                        value, file: file.to_path_buf(), span: enumerator.span.clone() }),
+                    Some(_) => {},
+                    None => { values.insert(value, &enumerator.name); },
+                }
+            }
+        }
+    }
+}
+
+fn qualified_name(name: &str, selector: &Option<String>) -> String {
+    match selector {
+        Some(selector) => format!("{}:{}", name, selector),
+        None => name.to_string(),
+    }
+}
+
+fn kind_name(tp: &ast::Type) -> &'static str {
+    match tp {
+        ast::Type::TypeDef{ .. } => "typedef",
+        ast::Type::Array{ .. } => "array",
+        ast::Type::Struct{ .. } => "struct",
+        ast::Type::Union{ .. } => "union",
+        ast::Type::Map{ .. } => "map",
+        ast::Type::Enumeration{ .. } => "enumeration",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser;
+
+    fn validate_texts(texts: &[&str], mode: ValidationMode) -> Vec<ValidationError> {
+        let modules: Vec<_> = texts.iter().enumerate()
+            .map(|(i, text)| (parser::parse_module(text).unwrap().1, PathBuf::from(format!("{}.fidl", i))))
+            .collect();
+        validate(&modules, mode)
+    }
+
+    fn messages(errors: &[ValidationError]) -> Vec<String> {
+        errors.iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        let errors = validate_texts(&["package a
+            typeCollection T {
+                enumeration E { A = 1 B = 2 C }
+                struct S { UInt8 x  UInt8 y }
+                const UInt8 X = 1
+            }
+            interface I {
+                version { major 1 minor 0 }
+                method m { in { UInt8 x } out { UInt8 y } }
+                method m:other { in { UInt8 x } }
+                broadcast b { out { UInt8 x } }
+                broadcast b:selective { out { UInt8 x } }
+            }"], ValidationMode::Strict);
+        assert_eq!(errors, vec![]);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let errors = validate_texts(&["package a
+typeCollection T {
+    enumeration E { A = 1 B = 1 A }
+    struct S { UInt8 x  UInt8 x }
+    union U { UInt8 x }
+    union U { UInt8 x }
+    const UInt8 S = 1
+}
+interface I {
+    attribute UInt8 a
+    attribute UInt8 a
+    method m { in { UInt8 x } out { UInt8 x } }
+    method m { }
+    method m:s { }
+    broadcast b { }
+    broadcast b { }
+}", "package a\ntypeCollection T { }"], ValidationMode::Default);
+        assert_eq!(messages(&errors), vec![
+            "1.fidl:2:1: error: type collection `T` is defined more than once in package `a`, first definition at 0.fidl:2:1",
+            "0.fidl:3:27: error: enumerator `B` of `a.T.E` has the value 1 of enumerator `A`",
+            "0.fidl:3:33: error: enumerator `A` is defined more than once in enumeration `a.T.E`, first definition at 0.fidl:3:21",
+            "0.fidl:4:25: error: field `x` is defined more than once in struct `a.T.S`, first definition at 0.fidl:4:16",
+            "0.fidl:6:5: error: type `U` is defined more than once in type collection `a.T`, first definition at 0.fidl:5:5",
+            "0.fidl:7:5: error: `S` is defined as type and as constant in type collection `a.T`, other definition at 0.fidl:4:5",
+            "0.fidl:11:5: error: attribute `a` is defined more than once in interface `a.I`, first definition at 0.fidl:10:5",
+            "0.fidl:12:37: error: argument `x` is defined more than once in method `a.I.m`, first definition at 0.fidl:12:21",
+            "0.fidl:13:5: error: method `m` is defined more than once in interface `a.I`, first definition at 0.fidl:12:5",
+            "0.fidl:16:5: error: broadcast `b` is defined more than once in interface `a.I`, first definition at 0.fidl:15:5",
+        ]);
+    }
+
+    #[test]
+    fn test_package_scope() {
+        let errors = validate_texts(&["package a
+typeCollection { typedef X is UInt8 }
+typeCollection T { }
+interface T { }", "package a\ntypeCollection { typedef X is UInt16 const UInt8 X = 1 }"], ValidationMode::Default);
+        assert_eq!(messages(&errors), vec![
+            "0.fidl:4:1: error: `T` is defined as type collection and as interface in package `a`, other definition at 0.fidl:3:1",
+            "1.fidl:2:18: error: type `X` is defined more than once in package `a`, first definition at 0.fidl:2:18",
+            "1.fidl:2:38: error: `X` is defined as type and as constant in package `a`, other definition at 0.fidl:2:18",
+        ]);
+    }
+
+    #[test]
+    fn test_inverted_interval() {
+        let errors = validate_texts(&["package a
+typeCollection { typedef T is Integer(5, 2) typedef U is Integer(2, 2) struct S { Integer(-1, -3) x } }
+interface I { attribute Integer(minInt, -3) a  method m { in { Integer(1, 0) x } } }"], ValidationMode::Default);
+        assert_eq!(messages(&errors), vec![
+            "0.fidl:2:18: error: `Integer(5, 2)` has a lower bound greater than its upper bound",
+            "0.fidl:2:83: error: `Integer(-1, -3)` has a lower bound greater than its upper bound",
+            "0.fidl:3:64: error: `Integer(1, 0)` has a lower bound greater than its upper bound",
+        ]);
+    }
+
+    #[test]
+    fn test_missing_version() {
+        let text = "package a\ninterface I { }";
+        assert_eq!(validate_texts(&[text], ValidationMode::Default), vec![]);
+        assert_eq!(messages(&validate_texts(&[text], ValidationMode::Strict)),
+                   vec!["0.fidl:2:1: error: interface `a.I` has no version"]);
+    }
+}
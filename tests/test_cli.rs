@@ -0,0 +1,78 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn fipa(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_fipa"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_check() {
+    let output = fipa(&["check", "-I", "tests/data/common", "tests/data/Service1.fidl", "tests/data/Service2.fidl"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let output = fipa(&["check", "tests/linker/Navigation.fidl"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Navigation.fidl:22:14: error: cannot resolve type `Vehicle.Speed`"), "{}", stderr);
+    assert!(stderr.ends_with("1 error\n"), "{}", stderr);
+
+    let output = fipa(&["check", "tests/deployment/Invalid.fdepl"]);
+    assert_eq!(output.status.code(), Some(1));
+
+    assert_eq!(fipa(&["check"]).status.code(), Some(2));
+}
+
+#[test]
+fn test_deps() {
+    let output = fipa(&["deps", "tests/imports/Top.fidl"]);
+    assert!(output.status.success());
+    let files: Vec<PathBuf> = String::from_utf8_lossy(&output.stdout).lines().map(PathBuf::from).collect();
+    assert_eq!(files.len(), 4);
+    assert!(files[0].ends_with("Base.fidl"));
+    assert!(files[3].ends_with("Top.fidl"));
+}
+
+#[test]
+fn test_fmt() {
+    let file = std::env::temp_dir().join(format!("fipa_test_fmt_{}.fidl", std::process::id()));
+    std::fs::write(&file, "package a interface I { attribute UInt8 a }").unwrap();
+    let path = file.to_str().unwrap();
+
+    let output = fipa(&["fmt", "--check", path]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), path);
+
+    assert!(fipa(&["fmt", path]).status.success());
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "package a\n\ninterface I {\n    attribute UInt8 a\n}\n");
+    assert!(fipa(&["fmt", "--check", path]).status.success());
+
+    std::fs::write(&file, "package a // keep me\n").unwrap();
+    assert_eq!(fipa(&["fmt", path]).status.code(), Some(1));
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "package a // keep me\n");
+    std::fs::remove_file(&file).unwrap();
+}
+
+#[test]
+fn test_dump() {
+    let output = fipa(&["dump", "tests/type_collection1.fidl"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("package: \"de.titnc.fidl_test\""));
+
+    let output = fipa(&["dump", "--json", "tests/type_collection1.fidl"]);
+    if cfg!(feature = "serde") {
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).contains("\"package\": \"de.titnc.fidl_test\""));
+    } else {
+        assert_eq!(output.status.code(), Some(1));
+    }
+}
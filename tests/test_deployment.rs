@@ -0,0 +1,111 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+use fipa::compiler::{self, ParseError};
+use fipa::deployment::ast::{Definition, Value};
+use fipa::deployment::someip::{SomeIpDeployment, StringEncoding};
+use fipa::deployment::validator;
+use fipa::model::Model;
+use fipa::parser::ParseMode;
+use std::path::{Path, PathBuf};
+
+fn deployment_dir() -> PathBuf {
+    let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    d.push("tests/deployment");
+    d
+}
+
+fn file_name(file: &Path) -> &str {
+    file.file_name().unwrap().to_str().unwrap()
+}
+
+#[test]
+fn test_deployment_imports() {
+    let fdepls = vec![deployment_dir().join("Service.fdepl")];
+    let (deployments, modules, errors) = compiler::parse_fdepls_sync(&fdepls, &[], 256, ParseMode::Strict, false);
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(deployments.iter().map(|(_, f)| file_name(f)).collect::<Vec<_>>(),
+               vec!["SomeIpSpec.fdepl", "Service.fdepl"]);
+    assert_eq!(modules.iter().map(|(_, f)| file_name(f)).collect::<Vec<_>>(), vec!["Service.fidl"]);
+
+    let spec = &deployments[0].0.specifications[0];
+    assert_eq!(spec.name, "org.example.deployment.SomeIp");
+    assert_eq!(spec.declarations.len(), 7);
+
+    let service = &deployments[1].0;
+    assert_eq!(service.definitions.len(), 2);
+    let Definition::Interface(interface) = &service.definitions[0] else { panic!("interface deployment expected") };
+    assert_eq!(interface.target, "org.example.Service");
+    assert_eq!(interface.properties[0].value, Value::Integer(0x1234));
+    assert_eq!(interface.methods[0].in_args[0].properties[0].value, Value::Reference("utf16le".to_string()));
+    assert_eq!(interface.broadcasts[0].properties[1].value, Value::Array(vec![Value::Integer(1), Value::Integer(2)]));
+    let Definition::Provider(provider) = &service.definitions[1] else { panic!("provider deployment expected") };
+    assert_eq!(provider.instances[0].name.as_deref(), Some("Main"));
+}
+
+#[test]
+fn test_deployment_syntax_error() {
+    let fdepls = vec![deployment_dir().join("Broken.fdepl")];
+    let (deployments, modules, errors) = compiler::parse_fdepls_sync(&fdepls, &[], 256, ParseMode::Strict, false);
+    assert!(deployments.is_empty() && modules.is_empty());
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    match &errors[0] {
+        ParseError::SyntaxError {diagnostic, ..} => {
+            assert_eq!((diagnostic.span.line, diagnostic.span.column), (6, 5));
+            assert!(diagnostic.to_string().contains("while parsing method `get`"), "{}", diagnostic);
+        },
+        e => panic!("unexpected error {:?}", e),
+    }
+}
+
+#[test]
+fn test_deployment_validation() {
+    let fdepls = vec![deployment_dir().join("Service.fdepl")];
+    let (deployments, modules, _) = compiler::parse_fdepls_sync(&fdepls, &[], 256, ParseMode::Strict, false);
+    let (model, _) = Model::build(&modules);
+    assert_eq!(validator::validate(&deployments, &model), vec![]);
+
+    let fdepls = vec![deployment_dir().join("Invalid.fdepl")];
+    let (deployments, modules, errors) = compiler::parse_fdepls_sync(&fdepls, &[], 256, ParseMode::Strict, false);
+    assert!(errors.is_empty(), "{:?}", errors);
+    let (model, _) = Model::build(&modules);
+    let messages: Vec<String> = validator::validate(&deployments, &model).iter()
+        .map(|e| e.to_string().replace(deployment_dir().to_str().unwrap(), "dir"))
+        .collect();
+    assert_eq!(messages, vec![
+        "dir/Invalid.fdepl:6:5: error: invalid value of property `SomeIpReliable` of interface `org.example.Service`, expected Boolean\n  interface `org.example.Service` defined at dir/Service.fidl:3:1",
+        "dir/Invalid.fdepl:8:5: error: mandatory property `SomeIpMethodID` is not set for method `org.example.Service.get`\n  method `org.example.Service.get` defined at dir/Service.fidl:6:5",
+        "dir/Invalid.fdepl:10:20: error: invalid value of property `SomeIpStringEncoding` of argument `name` of method `org.example.Service.get`, expected one of `utf8`, `utf16le`, `utf16be`\n  argument `name` of method `org.example.Service.get` defined at dir/Service.fidl:7:14",
+        "dir/Invalid.fdepl:4:1: error: mandatory property `SomeIpEventID` is not set for broadcast `org.example.Service.changed`\n  broadcast `org.example.Service.changed` defined at dir/Service.fidl:10:5",
+        "dir/Invalid.fdepl:14:5: error: broadcast `removed` not found in interface `org.example.Service`\n  interface `org.example.Service` defined at dir/Service.fidl:3:1",
+    ]);
+}
+
+#[test]
+fn test_someip_deployment() {
+    let fdepls = vec![deployment_dir().join("Service.fdepl")];
+    let (deployments, modules, _) = compiler::parse_fdepls_sync(&fdepls, &[], 256, ParseMode::Strict, false);
+    let (someip, errors) = SomeIpDeployment::build(&deployments);
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    let module = &modules[0].0;
+    let interface = &module.interfaces[0];
+    let deployment = someip.interface_of(&module.package, interface).unwrap();
+    assert_eq!(deployment.service_id, 0x1234);
+    assert_eq!(deployment.attribute(&interface.attributes[0]).unwrap().notifier_id, Some(0x8001));
+
+    let method = deployment.method(&interface.methods[0]).unwrap();
+    assert_eq!((method.method_id, method.reliable), (1, true));
+    assert_eq!(method.in_args[0].encoding.string_encoding, StringEncoding::Utf16Le);
+
+    let broadcast = deployment.broadcast(&interface.broadcasts[0]).unwrap();
+    assert_eq!((broadcast.event_id, broadcast.reliable), (0x8002, false));
+    assert_eq!(broadcast.event_groups, vec![1, 2]);
+
+    let instances: Vec<_> = someip.instances_of("org.example.Service").collect();
+    assert_eq!(instances.len(), 1);
+    assert_eq!((instances[0].provider.as_str(), instances[0].instance_id), ("Server", 1));
+}
@@ -0,0 +1,116 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+use fipa::compiler::{self, ParseError};
+use fipa::parser::ParseMode;
+use std::path::PathBuf;
+
+fn imports_dir() -> PathBuf {
+    let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    d.push("tests/imports");
+    d
+}
+
+fn file_names(files: &[PathBuf]) -> Vec<&str> {
+    files.iter().map(|f| f.file_name().unwrap().to_str().unwrap()).collect()
+}
+
+#[test]
+fn test_diamond() {
+    let d = imports_dir();
+    let (modules, errors, graph) =
+        compiler::parse_fidls_sync(&[d.join("Top.fidl")], &[], 256, true, ParseMode::Strict, true);
+    assert!(errors.is_empty());
+    assert_eq!(modules.len(), 4);
+    assert_eq!(graph.nodes().len(), 4);
+    assert_eq!(graph.edges().len(), 4);
+
+    let packages: Vec<_> = modules.iter().map(|(m, _)| m.package.as_str()).collect();
+    assert_eq!(packages, vec!["imports.base", "imports.left", "imports.right", "imports.top"]);
+
+    let top = d.join("Top.fidl").canonicalize().unwrap();
+    let imports: Vec<_> = graph.imports_of(&top).map(|e| (e.namespace.as_str(), e.uri.as_str())).collect();
+    assert_eq!(imports, vec![("imports.left.*", "Left.fidl"), ("imports.right.*", "Right.fidl")]);
+    assert_eq!(file_names(&graph.topological_order()), vec!["Base.fidl", "Left.fidl", "Right.fidl", "Top.fidl"]);
+}
+
+#[test]
+fn test_cycle() {
+    let d = imports_dir();
+    let (modules, errors, graph) =
+        compiler::parse_fidls_sync(&[d.join("CycleB.fidl")], &[], 256, true, ParseMode::Strict, false);
+    assert_eq!(modules.len(), 2);
+    assert_eq!(errors.len(), 1);
+    match &errors[0] {
+        ParseError::ImportCycle { cycle } => assert_eq!(file_names(cycle), vec!["CycleA.fidl", "CycleB.fidl"]),
+        e => panic!("unexpected error {:?}", e),
+    }
+    assert_eq!(file_names(&graph.topological_order()), vec!["CycleB.fidl", "CycleA.fidl"]);
+}
+
+#[test]
+fn test_max_import_nesting() {
+    let d = imports_dir();
+    let (modules, errors, _) = compiler::parse_fidls_sync(&[d.join("Chain1.fidl")], &[], 3, true, ParseMode::Strict, false);
+    assert!(errors.is_empty());
+    assert_eq!(modules.len(), 4);
+
+    let (modules, errors, _) = compiler::parse_fidls_sync(&[d.join("Chain1.fidl")], &[], 2, true, ParseMode::Strict, false);
+    assert_eq!(modules.len(), 3);
+    assert_eq!(errors.len(), 1);
+    match &errors[0] {
+        ParseError::MaxImportNestingReached { file, referenced_by, chain } => {
+            assert!(file.ends_with("Chain4.fidl"));
+            assert!(referenced_by.as_ref().unwrap().ends_with("Chain3.fidl"));
+            assert_eq!(file_names(chain), vec!["Chain1.fidl", "Chain2.fidl", "Chain3.fidl", "Chain4.fidl"]);
+        },
+        e => panic!("unexpected error {:?}", e),
+    }
+
+    // the limit applies to the import chain, files imported via a shorter chain are fine
+    let (modules, errors, _) = compiler::parse_fidls_sync(&[d.join("Chain1.fidl"), d.join("Chain3.fidl")], &[], 1, true, ParseMode::Strict, false);
+    assert!(errors.is_empty());
+    assert_eq!(modules.len(), 4);
+
+    let (modules, errors, _) = compiler::parse_fidls_sync(&[d.join("Chain1.fidl")], &[], 0, true, ParseMode::Strict, false);
+    assert_eq!(modules.len(), 1);
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn test_missing_imports() {
+    let d = imports_dir();
+    let search_dir = d.join("search");
+    let (modules, errors, _) =
+        compiler::parse_fidls_sync(&[d.join("Missing.fidl")], std::slice::from_ref(&search_dir), 256, true, ParseMode::Strict, false);
+    let packages: Vec<_> = modules.iter().map(|(m, _)| m.package.as_str()).collect();
+    assert_eq!(packages, vec!["imports.base", "imports.missing"]);
+
+    assert_eq!(errors.len(), 2);
+    for (error, uri) in errors.iter().zip(["Nowhere.fidl", "Gone.fidl"]) {
+        match error {
+            ParseError::FileNotFound { file, referenced_by, searched } => {
+                assert_eq!(file, &PathBuf::from(uri));
+                assert!(referenced_by.as_ref().unwrap().ends_with("Missing.fidl"));
+                assert_eq!(searched, &vec![d.join(uri), search_dir.join(uri)]);
+            },
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_matches_sync() {
+    let d = imports_dir();
+    let fidls = [d.join("Top.fidl"), d.join("Chain1.fidl")];
+    let (modules, errors, graph) = compiler::parse_fidls_with_graph(&fidls, &[], 2, true, ParseMode::Strict).await;
+    let (sync_modules, sync_errors, sync_graph) = compiler::parse_fidls_sync(&fidls, &[], 2, true, ParseMode::Strict, true);
+    assert_eq!(modules, sync_modules);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(sync_errors.len(), 1);
+    assert_eq!(graph.topological_order(), sync_graph.topological_order());
+}
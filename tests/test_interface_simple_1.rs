@@ -4,16 +4,18 @@
 //
 // Author: Alexander Seifarth
 
-use fipa;
+use fipa::ast::ClearSpans;
+
 
 #[test]
 fn test_interface_simple_1a() {
     let fidl_text = include_str!("interface1a.fidl");
 
-    let module_result = fipa::parser::parse_module(&fidl_text);
+    let module_result = fipa::parser::parse_module(fidl_text);
     assert!(module_result.is_ok());
 
-    let (_, module) = module_result.unwrap();
+    // compare the content only, spans are tested in test_interface_simple_1a_spans
+    let module = module_result.unwrap().1.without_spans();
 
     // package name
     assert_eq!(module.package, "de.titnc.my_test.package");
@@ -40,70 +42,73 @@ fn test_interface_simple_1a() {
     //     attribute Int8 counter readonly
     assert_eq!(my_interface.attributes[0].annotation, None);
     assert_eq!(my_interface.attributes[0].name, "counter");
-    assert_eq!(my_interface.attributes[0].array, false);
-    assert_eq!(my_interface.attributes[0].no_read, false);
-    assert_eq!(my_interface.attributes[0].read_only, true);
-    assert_eq!(my_interface.attributes[0].no_subscription, false);
+    assert!(!my_interface.attributes[0].array);
+    assert!(!my_interface.attributes[0].no_read);
+    assert!(my_interface.attributes[0].read_only);
+    assert!(!my_interface.attributes[0].no_subscription);
     assert_eq!(my_interface.attributes[0].type_ref, fipa::ast::TypeRef::Int8);
 
     //     attribute Boolean active
     assert_eq!(my_interface.attributes[1].annotation, None);
     assert_eq!(my_interface.attributes[1].name, "active");
-    assert_eq!(my_interface.attributes[1].array, false);
-    assert_eq!(my_interface.attributes[1].no_read, false);
-    assert_eq!(my_interface.attributes[1].read_only, false);
-    assert_eq!(my_interface.attributes[1].no_subscription, false);
+    assert!(!my_interface.attributes[1].array);
+    assert!(!my_interface.attributes[1].no_read);
+    assert!(!my_interface.attributes[1].read_only);
+    assert!(!my_interface.attributes[1].no_subscription);
     assert_eq!(my_interface.attributes[1].type_ref, fipa::ast::TypeRef::Boolean);
 
     //     attribute MyType[] headlines noSubscription
-    assert_eq!(my_interface.attributes[2].annotation, Some(" This is an attribute with annotation ".to_string()));
+    assert_eq!(my_interface.attributes[2].annotation, Some(" This is an attribute with annotation ".into()));
     assert_eq!(my_interface.attributes[2].name, "headlines");
-    assert_eq!(my_interface.attributes[2].array, true);
-    assert_eq!(my_interface.attributes[2].no_read, false);
-    assert_eq!(my_interface.attributes[2].read_only, false);
-    assert_eq!(my_interface.attributes[2].no_subscription, true);
+    assert!(my_interface.attributes[2].array);
+    assert!(!my_interface.attributes[2].no_read);
+    assert!(!my_interface.attributes[2].read_only);
+    assert!(my_interface.attributes[2].no_subscription);
     assert_eq!(my_interface.attributes[2].type_ref, fipa::ast::TypeRef::Derived("MyType".to_string()));
 
     // VehicleStatus
     let vehicle_status = &module.interfaces[1];
-    assert!(vehicle_status.annotation.is_some());
+    let vs_annotation = vehicle_status.annotation.as_ref().unwrap();
+    assert_eq!(vs_annotation.entries.len(), 2);
+    assert_eq!(vs_annotation.first(&fipa::ast::AnnotationKind::Description), Some("Annotation for a service"));
+    assert_eq!(vs_annotation.first(&fipa::ast::AnnotationKind::Author), Some("titnc"));
     assert_eq!(vehicle_status.name, "VehicleStatus");
     assert_eq!(vehicle_status.version, Some((2, 1)));
     assert_eq!(vehicle_status.attributes.len(), 1);
     assert_eq!(vehicle_status.types.len(), 2);
     assert_eq!(vehicle_status.extends, Some("VehicleBase".to_string()));
 
-    assert_eq!(vehicle_status.attributes[0], fipa::ast::Attribute{
+    assert_eq!(vehicle_status.attributes[0], fipa::ast::Attribute{ span: Default::default(),
         annotation: None, name: "actualFesMode".to_string(), array: false, read_only: true,
         no_read: false, no_subscription: false, type_ref: fipa::ast::TypeRef::Derived("FesMode".to_string()) });
 
-    assert_eq!(vehicle_status.types[0], fipa::ast::Type::TypeDef {
+    assert_eq!(vehicle_status.types[0], fipa::ast::Type::TypeDef { span: Default::default(),
         annotation: None, name: "Flag".to_string(), public: false, array: false,
         actual_type: fipa::ast::TypeRef::Boolean });
-    assert_eq!(vehicle_status.types[1], fipa::ast::Type::Enumeration {
+    assert_eq!(vehicle_status.types[1], fipa::ast::Type::Enumeration { span: Default::default(),
         annotation: None, name: "FesMode".to_string(), public: true, base_type: None,
         enumerators: vec![
-            fipa::ast::Enumerator{ annotation: None, name: "SPORT_INDIVIDUAL".to_string(), val: Some(1) },
-            fipa::ast::Enumerator{ annotation: None, name: "COMFORT".to_string(), val: Some(2) },
-            fipa::ast::Enumerator{ annotation: None, name: "ECO".to_string(), val: Some(4) },
+            fipa::ast::Enumerator{ span: Default::default(), annotation: None, name: "SPORT_INDIVIDUAL".to_string(), val: Some(1) },
+            fipa::ast::Enumerator{ span: Default::default(), annotation: None, name: "COMFORT".to_string(), val: Some(2) },
+            fipa::ast::Enumerator{ span: Default::default(), annotation: None, name: "ECO".to_string(), val: Some(4) },
     ] });
 
     assert_eq!(vehicle_status.broadcasts.len(), 1);
-    assert_eq!(vehicle_status.broadcasts[0], fipa::ast::Broadcast{
-        annotation: Some(" a pure event -> broadcast\n    ".to_string()), selector: None, selective: false,
+    assert_eq!(vehicle_status.broadcasts[0], fipa::ast::Broadcast{ span: Default::default(),
+        annotation: Some(" a pure event -> broadcast\n    ".into()), selector: None, selective: false,
         name: "ZeroEmmissionZoneBorder".to_string(), out_args: vec![
-            fipa::ast::Argument{ annotation: None, array: false, name: "zoneEntered".to_string(), type_ref: fipa::ast::TypeRef::Boolean},
-            fipa::ast::Argument{ annotation: None, array: false, name: "zoneID".to_string(), type_ref: fipa::ast::TypeRef::Int8 },
+            fipa::ast::Argument{ span: Default::default(), annotation: None, array: false, name: "zoneEntered".to_string(), type_ref: fipa::ast::TypeRef::Boolean},
+            fipa::ast::Argument{ span: Default::default(), annotation: None, array: false, name: "zoneID".to_string(), type_ref: fipa::ast::TypeRef::Int8 },
         ]
     });
 
     assert_eq!(vehicle_status.methods.len(), 1);
-    assert_eq!(vehicle_status.methods[0], fipa::ast::Method {
+    assert_eq!(vehicle_status.methods[0], fipa::ast::Method { span: Default::default(),
         annotation: None, name: "setActiveStatistics".to_string(), fire_and_forget: true, selector: None,
         in_args: vec![
-            fipa::ast::Argument{annotation: None, name: "activeStatistics".to_string(), array: false,
+            fipa::ast::Argument{ span: Default::default(), annotation: None, name: "activeStatistics".to_string(), array: false,
                 type_ref: fipa::ast::TypeRef::Derived("StatisticsType".to_string())},
-            fipa::ast::Argument{annotation: None, name: "resetStatistics".to_string(), array: false,
+            fipa::ast::Argument{ span: Default::default(), annotation: None, name: "resetStatistics".to_string(), array: false,
                 type_ref: fipa::ast::TypeRef::Boolean}
         ],
         out_args: Vec::new(), error: None
@@ -116,4 +121,39 @@ fn test_interface_simple_1a() {
     assert_eq!(device_mgr.version, None);
     assert_eq!(device_mgr.extends, None);
     assert_eq!(device_mgr.manages, Some(vec!["Phone".to_string(), "de.titnc.Tablet".to_string()]));
-}
\ No newline at end of file
+    assert_eq!(device_mgr.methods.len(), 1);
+    assert_eq!(device_mgr.broadcasts.len(), 1);
+    assert_eq!(device_mgr.types.len(), 1);
+
+    let contract = device_mgr.contract.as_ref().unwrap();
+    assert_eq!(contract.variables.len(), 1);
+    assert_eq!(contract.variables[0].name, "connections");
+    assert_eq!(contract.state_graph.initial, "Idle");
+    assert_eq!(contract.state_graph.states.len(), 2);
+    assert_eq!(contract.state_graph.states[0].transitions[0].trigger,
+               fipa::ast::Trigger::Call{ method: "connect".to_string(), selector: None });
+    assert_eq!(contract.state_graph.states[0].transitions[0].to, "Connected");
+    assert!(contract.state_graph.states[0].transitions[0].action.is_some());
+    assert!(contract.state_graph.states[1].transitions[0].guard.is_some());
+    assert!(module.interfaces[0].contract.is_none());
+}
+
+#[test]
+fn test_interface_simple_1a_spans() {
+    let fidl_text = include_str!("interface1a.fidl");
+    let (_, module) = fipa::parser::parse_module(fidl_text).unwrap();
+
+    assert_eq!((module.imports[0].span.line, module.imports[0].span.column), (6, 1));
+    assert_eq!((module.imports[1].span.line, module.imports[1].span.column), (7, 1));
+
+    let interface = &module.interfaces[0];
+    assert_eq!((interface.span.line, interface.span.column), (9, 1));
+    assert!(interface.span.file.is_none());
+
+    assert_eq!((interface.attributes[0].span.line, interface.attributes[0].span.column), (11, 5));
+    assert_eq!(&fidl_text[interface.attributes[0].span.offset..][..interface.attributes[0].span.len],
+               "attribute Int8 counter readonly");
+
+    // the span of an annotated element starts at its annotation
+    assert_eq!((interface.attributes[2].span.line, interface.attributes[2].span.column), (15, 5));
+}
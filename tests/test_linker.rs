@@ -0,0 +1,63 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+#![cfg(feature = "async")]
+
+#[tokio::test]
+async fn test_linker() {
+    let mut d = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    d.push("tests/linker");
+
+    let (modules, errors) =
+        fipa::compiler::parse_fidls(&[d.join("Navigation.fidl")], &[], 256, true).await;
+    assert!(errors.is_empty());
+    assert_eq!(modules.len(), 2);
+
+    let (symbols, errors) = fipa::linker::link(&modules);
+    assert_eq!(symbols.get("de.titnc.types.Vehicle.Position").len(), 1);
+    assert_eq!(symbols.get("de.titnc.navigation.Navigation.Waypoints").len(), 1);
+
+    assert_eq!(errors.len(), 1);
+    match &errors[0] {
+        fipa::linker::LinkError::Unresolved { name, expected, file, span } => {
+            assert_eq!(name, "Vehicle.Speed");
+            assert_eq!(expected, "type");
+            assert!(file.ends_with("Navigation.fidl"));
+            assert_eq!(span.file.as_deref(), Some(file.as_path()));
+            assert_eq!((span.line, span.column), (22, 14));
+        },
+        e => panic!("unexpected error {:?}", e),
+    }
+}
+
+#[tokio::test]
+async fn test_model() {
+    use fipa::model::{MethodError, Model, TypeKind, TypeRef};
+    let mut d = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    d.push("tests/linker");
+
+    let (modules, _) =
+        fipa::compiler::parse_fidls(&[d.join("Navigation.fidl")], &[], 256, true).await;
+    let (model, errors) = Model::build(&modules);
+    assert_eq!(errors.len(), 1);
+
+    let navigation = model.find_interface("de.titnc.navigation.Navigation").unwrap();
+    let position = model.find_type("de.titnc.types.Vehicle.Position").unwrap();
+    let waypoints = model.find_type("de.titnc.navigation.Navigation.Waypoints").unwrap();
+    let route_error = model.find_type("de.titnc.navigation.Navigation.RouteError").unwrap();
+    assert_eq!(model[position].interface, None);
+    assert_eq!(model[waypoints].interface, Some(navigation));
+    assert_eq!(model[waypoints].kind, TypeKind::Array{ element_type: TypeRef::Type(position) });
+
+    let interface = &model[navigation];
+    assert_eq!(interface.version, Some((1, 0)));
+    assert_eq!(interface.types, vec![waypoints, route_error]);
+    assert_eq!(interface.attributes[0].type_ref, TypeRef::Type(position));
+    let route = &interface.methods[0];
+    assert_eq!(route.out_args[0].type_ref, TypeRef::Type(waypoints));
+    assert_eq!(route.error, Some(MethodError::Reference(TypeRef::Type(route_error))));
+    assert_eq!(interface.methods[1].in_args[0].type_ref, TypeRef::Unresolved("Vehicle.Speed".to_string()));
+}
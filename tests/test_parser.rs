@@ -4,7 +4,8 @@
 //
 // Author: Alexander Seifarth
 
-use fipa;
+#![cfg(feature = "async")]
+
 
 #[tokio::test]
 async fn test_parser() {
@@ -22,4 +23,52 @@ async fn test_parser() {
 
 
 
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_parser_syntax_error() {
+    let mut d = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    d.push("tests/errors");
+
+    let (modules, errors) =
+        fipa::compiler::parse_fidls(&[d.join("BrokenMethod.fidl")], &[], 256, true).await;
+    // the module is returned without the broken method
+    assert_eq!(modules.len(), 1);
+    assert_eq!(modules[0].0.interfaces[0].methods.len(), 1);
+    assert_eq!(modules[0].0.interfaces[0].methods[0].name, "ok");
+    assert_eq!(errors.len(), 1);
+    match &errors[0] {
+        fipa::compiler::ParseError::SyntaxError { file, diagnostic, .. } => {
+            assert!(file.ends_with("BrokenMethod.fidl"));
+            assert_eq!(diagnostic.message, "expected `}` to close method `broken`, found `out`");
+            assert_eq!((diagnostic.span.line, diagnostic.span.column), (10, 9));
+            assert_eq!(diagnostic.span.file.as_deref(), Some(file.as_path()));
+            assert!(diagnostic.to_string().contains("10 |         out { Boolean }\n   |         ^^^"));
+        },
+        e => panic!("unexpected error {:?}", e),
+    }
+}
+
+#[tokio::test]
+async fn test_parser_trailing_text() {
+    let mut d = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    d.push("tests/errors");
+    let fidls = vec![d.join("TrailingText.fidl")];
+
+    let (modules, errors) = fipa::compiler::parse_fidls(&fidls, &[], 256, true).await;
+    assert_eq!(modules.len(), 1);
+    assert_eq!(modules[0].0.interfaces.len(), 1);
+    match &errors[..] {
+        [fipa::compiler::ParseError::SyntaxError { diagnostic, .. }] => {
+            assert_eq!(diagnostic.found.as_deref(), Some("interfac"));
+            assert_eq!((diagnostic.span.line, diagnostic.span.column), (7, 1));
+        },
+        e => panic!("unexpected errors {:?}", e),
+    }
+
+    let (modules, errors) = fipa::compiler::parse_fidls_with_mode(
+        &fidls, &[], 256, true, fipa::parser::ParseMode::Lenient).await;
+    assert!(errors.is_empty());
+    assert_eq!(modules.len(), 1);
+    assert_eq!(modules[0].0.interfaces.len(), 1);
+}
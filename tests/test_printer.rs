@@ -0,0 +1,34 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+use fipa::ast::ClearSpans;
+use fipa::parser::parse_module;
+use fipa::printer::{print, print_with, BlankLines, Indent, PrintOptions};
+
+#[test]
+fn test_print_round_trip() {
+    let d = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let fidls = ["interface1a.fidl", "type_collection1.fidl", "data/Service1.fidl", "data/Service2.fidl",
+        "data/Types1.fidl", "data/common/Common.fidl", "linker/Navigation.fidl", "linker/Types.fidl",
+        "imports/Base.fidl", "imports/Top.fidl", "deployment/Service.fidl"];
+    let options = [PrintOptions::default(),
+        PrintOptions{ indent: Indent::Tab, blank_lines: BlankLines::None }];
+
+    for fidl in fidls {
+        let text = std::fs::read_to_string(d.join(fidl)).unwrap();
+        let module = parse_module(&text).unwrap().1.without_spans();
+        for options in &options {
+            let printed = print_with(&module, options);
+            let (rest, reparsed) = parse_module(&printed).unwrap();
+            assert_eq!(rest, "", "{}", fidl);
+            assert_eq!(reparsed.without_spans(), module, "{}", fidl);
+        }
+
+        // printing is canonical: printed text prints to itself
+        let printed = print(&module);
+        assert_eq!(print(&parse_module(&printed).unwrap().1), printed, "{}", fidl);
+    }
+}
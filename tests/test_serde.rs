@@ -0,0 +1,42 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+//
+// Author: Alexander Seifarth
+
+#![cfg(feature = "serde")]
+
+use fipa::{ast::{self, ClearSpans}, compiler, parser::ParseMode};
+
+#[test]
+fn test_json_round_trip() {
+    let d = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+    let (modules, errors, _) = compiler::parse_fidls_sync(&[d.join("Service1.fidl"), d.join("Service2.fidl")],
+        &[d.join("common")], 256, true, ParseMode::Strict, false);
+    assert!(errors.is_empty());
+
+    let json: serde_json::Value = serde_json::from_str(&fipa::json::to_json(&modules, &errors)).unwrap();
+    assert_eq!(json["modules"].as_array().unwrap().len(), 4);
+    for ((module, file), entry) in modules.iter().zip(json["modules"].as_array().unwrap()) {
+        assert_eq!(entry["file"].as_str(), file.to_str());
+        let parsed: ast::Module = serde_json::from_value(entry["module"].clone()).unwrap();
+        // spans are serialized without their file
+        assert_eq!(parsed.span, ast::Span{ file: None, ..module.span.clone() });
+        assert_eq!(parsed.without_spans(), module.clone().without_spans());
+    }
+}
+
+#[test]
+fn test_json_errors() {
+    let d = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/errors");
+    let (modules, errors, _) = compiler::parse_fidls_sync(&[d.join("BrokenMethod.fidl")], &[], 256, true,
+        ParseMode::Strict, false);
+    assert!(!errors.is_empty());
+
+    let json: serde_json::Value = serde_json::from_str(&fipa::json::to_json(&modules, &errors)).unwrap();
+    let error = &json["errors"][0];
+    assert_eq!(error["kind"], "SyntaxError");
+    assert!(error["file"].as_str().unwrap().ends_with("BrokenMethod.fidl"));
+    assert!(error["line"].as_u64().is_some());
+    assert_eq!(error["message"].as_str().unwrap(), errors[0].to_string());
+}
@@ -4,13 +4,16 @@
 //
 // Author: Alexander Seifarth
 
+use fipa::ast::ClearSpans;
+
 #[test]
 fn test_type_collection1() {
     let fidl_text = include_str!("type_collection1.fidl");
-    let module_result = fipa::parser::parse_module(&fidl_text);
+    let module_result = fipa::parser::parse_module(fidl_text);
 
     assert!(module_result.is_ok());
-    let (_, module) = module_result.unwrap();
+    // compare the content only, not the source locations
+    let module = module_result.unwrap().1.without_spans();
 
     // package name
     assert_eq!(module.package, "de.titnc.fidl_test");
@@ -24,37 +27,53 @@ fn test_type_collection1() {
     assert_eq!(coll1.types.len(), 4);
 
     // public typedef Byte is UInt8
-    assert_eq!(coll1.types[0], fipa::ast::Type::TypeDef {
+    assert_eq!(coll1.types[0], fipa::ast::Type::TypeDef { span: Default::default(),
        annotation: None, public: true, name: "Byte".to_string(), array: false,
         actual_type: fipa::ast::TypeRef::UInt8 });
 
     // <** an array of int's **> public array IntArray of Int8
-    assert_eq!(coll1.types[1], fipa::ast::Type::Array {
-        annotation: Some(" an array of int's ".to_string()), public: true, name: "IntArray".to_string(),
+    assert_eq!(coll1.types[1], fipa::ast::Type::Array { span: Default::default(),
+        annotation: Some(" an array of int's ".into()), public: true, name: "IntArray".to_string(),
         element_type: fipa::ast::TypeRef::Int8 });
 
     // public struct AStruct { Byte a_single_byte Boolean flag String explanation }
-    assert_eq!(coll1.types[2], fipa::ast::Type::Struct {
+    assert_eq!(coll1.types[2], fipa::ast::Type::Struct { span: Default::default(),
         annotation: None, public: true, name: "AStruct".to_string(), extends: None, polymorphic: false,
         fields: vec![
-            fipa::ast::Field{ annotation: None, name: "a_single_byte".to_string(), array: false, type_ref: fipa::ast::TypeRef::Derived("Byte".to_string()) },
-            fipa::ast::Field{ annotation: None, name: "flag".to_string(), array: false, type_ref: fipa::ast::TypeRef::Boolean },
-            fipa::ast::Field{ annotation: None, name: "explanation".to_string(), array: false, type_ref: fipa::ast::TypeRef::String }
+            fipa::ast::Field{ span: Default::default(), annotation: None, name: "a_single_byte".to_string(), array: false, type_ref: fipa::ast::TypeRef::Derived("Byte".to_string()) },
+            fipa::ast::Field{ span: Default::default(), annotation: None, name: "flag".to_string(), array: false, type_ref: fipa::ast::TypeRef::Boolean },
+            fipa::ast::Field{ span: Default::default(), annotation: None, name: "explanation".to_string(), array: false, type_ref: fipa::ast::TypeRef::String }
     ] });
 
     // public enumeration Status { NO_SIGNAL, VALUE_1, SIGNAL_UNBEFUELLT = 0x0f, }
-    assert_eq!(coll1.types[3], fipa::ast::Type::Enumeration {
+    assert_eq!(coll1.types[3], fipa::ast::Type::Enumeration { span: Default::default(),
         annotation: None, public: true, name: "Status".to_string(), base_type: None,
         enumerators: vec! [
-            fipa::ast::Enumerator{ annotation: None, name: "NO_SIGNAL".to_string(), val: None},
-            fipa::ast::Enumerator{ annotation: None, name: "VALUE_1".to_string(), val: None },
-            fipa::ast::Enumerator{ annotation: None, name: "SIGNAL_UNBEFUELLT".to_string(), val: Some(0x0f)},
+            fipa::ast::Enumerator{ span: Default::default(), annotation: None, name: "NO_SIGNAL".to_string(), val: None},
+            fipa::ast::Enumerator{ span: Default::default(), annotation: None, name: "VALUE_1".to_string(), val: None },
+            fipa::ast::Enumerator{ span: Default::default(), annotation: None, name: "SIGNAL_UNBEFUELLT".to_string(), val: Some(0x0f)},
     ] });
 
+    assert_eq!(coll1.constants.len(), 2);
+    assert_eq!(coll1.constants[0], fipa::ast::Constant { span: Default::default(),
+        annotation: Some(" maximum speed in km/h ".into()), type_ref: fipa::ast::TypeRef::UInt16,
+        array: false, name: "MAX_SPEED".to_string(),
+        value: fipa::ast::InitializerExpression::Expression(
+            fipa::ast::Expression::Literal(fipa::ast::Literal::Integer(250))) });
+    assert_eq!(coll1.constants[1].name, "DEFAULT_STRUCT");
+    if let fipa::ast::InitializerExpression::Compound(fields) = &coll1.constants[1].value {
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[1].element, "flag");
+    }
+    else {
+        panic!("DEFAULT_STRUCT is not initialized by a compound initializer");
+    }
+
 
     let coll2 = &module.type_collections[1];
-    assert_eq!(coll2.annotation, Some(" an 'anonymous' type collection ".to_string()));
+    assert_eq!(coll2.annotation, Some(" an 'anonymous' type collection ".into()));
     assert_eq!(coll2.version, None);
     assert_eq!(coll2.name, None);
     assert_eq!(coll2.types.len(), 0);
+    assert_eq!(coll2.constants.len(), 0);
 }